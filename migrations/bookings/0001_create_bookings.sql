@@ -0,0 +1,29 @@
+-- Reservations made from /book. Checkout holds are rows with status 'held' that
+-- stop counting against capacity once hold_expires_at passes.
+CREATE TABLE IF NOT EXISTS bookings (
+    id                  TEXT PRIMARY KEY,
+    confirmation_number TEXT UNIQUE,
+    service             TEXT NOT NULL,
+    booking_date        TEXT NOT NULL,
+    start_time          TEXT NOT NULL,
+    start_at            TEXT NOT NULL,
+    end_at              TEXT NOT NULL,
+    quantity            INTEGER NOT NULL,
+    party_size          INTEGER NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'held',
+    hold_expires_at     TEXT,
+    customer_name       TEXT,
+    customer_email      TEXT,
+    customer_phone      TEXT,
+    notes               TEXT,
+    created_at          TEXT NOT NULL,
+    updated_at          TEXT NOT NULL,
+    cancelled_at        TEXT,
+    cancelled_by        TEXT,
+    cancel_reason       TEXT,
+    ip_address          TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_bookings_service_window ON bookings (service, booking_date, start_at);
+CREATE INDEX IF NOT EXISTS idx_bookings_status ON bookings (status, hold_expires_at);
+CREATE INDEX IF NOT EXISTS idx_bookings_email ON bookings (customer_email);
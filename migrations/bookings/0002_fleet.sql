@@ -0,0 +1,30 @@
+-- Rental fleet. fleet_categories holds how many units we own of each kind;
+-- fleet_assets are individually numbered units that can be pulled from service.
+CREATE TABLE IF NOT EXISTS fleet_categories (
+    category    TEXT PRIMARY KEY,
+    total_units INTEGER NOT NULL DEFAULT 0,
+    updated_at  TEXT NOT NULL
+);
+
+INSERT OR IGNORE INTO fleet_categories (category, total_units, updated_at) VALUES
+    ('tube', 40, datetime('now')),
+    ('kayak_single', 10, datetime('now')),
+    ('kayak_tandem', 6, datetime('now')),
+    ('canoe', 4, datetime('now')),
+    ('bike', 10, datetime('now')),
+    ('ebike', 6, datetime('now'));
+
+CREATE TABLE IF NOT EXISTS fleet_assets (
+    id                    TEXT PRIMARY KEY,
+    category              TEXT NOT NULL,
+    name                  TEXT NOT NULL,
+    serial                TEXT,
+    out_of_service        INTEGER NOT NULL DEFAULT 0,
+    out_of_service_reason TEXT,
+    out_of_service_since  TEXT,
+    notes                 TEXT,
+    created_at            TEXT NOT NULL,
+    updated_at            TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_fleet_assets_category ON fleet_assets (category, out_of_service);
@@ -0,0 +1,3 @@
+-- Same-day water bookings flagged when flow or NWS alerts turn unsafe
+ALTER TABLE bookings ADD COLUMN at_risk_reason TEXT;
+ALTER TABLE bookings ADD COLUMN at_risk_at TEXT;
@@ -0,0 +1,33 @@
+-- Promo codes and their redemptions. services is a JSON array of booking service
+-- slugs; an empty array means every service.
+CREATE TABLE IF NOT EXISTS promo_codes (
+    code           TEXT PRIMARY KEY,
+    description    TEXT NOT NULL DEFAULT '',
+    discount_type  TEXT NOT NULL,
+    amount         REAL NOT NULL,
+    services       TEXT NOT NULL DEFAULT '[]',
+    valid_from     TEXT,
+    valid_to       TEXT,
+    max_uses       INTEGER,
+    once_per_email INTEGER NOT NULL DEFAULT 0,
+    active         INTEGER NOT NULL DEFAULT 1,
+    created_by     TEXT NOT NULL DEFAULT '',
+    created_at     TEXT NOT NULL,
+    updated_at     TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS promo_redemptions (
+    id          TEXT PRIMARY KEY,
+    code        TEXT NOT NULL,
+    booking_id  TEXT NOT NULL,
+    email       TEXT NOT NULL,
+    subtotal    REAL NOT NULL,
+    discount    REAL NOT NULL,
+    redeemed_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_promo_redemptions_code ON promo_redemptions (code, email);
+CREATE INDEX IF NOT EXISTS idx_promo_redemptions_date ON promo_redemptions (redeemed_at);
+
+ALTER TABLE bookings ADD COLUMN promo_code TEXT;
+ALTER TABLE bookings ADD COLUMN promo_discount REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,41 @@
+-- Gift cards. Only a SHA-256 of the code is stored; staff see the last four characters.
+-- Amounts are integer cents. balance_cents is kept in step with the ledger.
+CREATE TABLE IF NOT EXISTS gift_cards (
+    id              TEXT PRIMARY KEY,
+    code_hash       TEXT NOT NULL UNIQUE,
+    last4           TEXT NOT NULL,
+    initial_cents   INTEGER NOT NULL,
+    balance_cents   INTEGER NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'active',
+    recipient_name  TEXT,
+    recipient_email TEXT,
+    purchaser_name  TEXT,
+    purchaser_email TEXT,
+    message         TEXT,
+    expires_on      TEXT,
+    issued_by       TEXT NOT NULL,
+    issued_at       TEXT NOT NULL,
+    voided_at       TEXT,
+    voided_by       TEXT,
+    void_reason     TEXT
+);
+
+-- One row per balance change: issue, redeem, refund, adjust, void
+CREATE TABLE IF NOT EXISTS gift_card_ledger (
+    id                  TEXT PRIMARY KEY,
+    card_id             TEXT NOT NULL,
+    entry_type          TEXT NOT NULL,
+    amount_cents        INTEGER NOT NULL,
+    balance_after_cents INTEGER NOT NULL,
+    booking_id          TEXT,
+    reference           TEXT,
+    note                TEXT,
+    created_by          TEXT NOT NULL,
+    created_at          TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_gift_card_ledger_card ON gift_card_ledger (card_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_gift_cards_recipient ON gift_cards (recipient_email);
+
+ALTER TABLE bookings ADD COLUMN gift_card_id TEXT;
+ALTER TABLE bookings ADD COLUMN gift_card_amount REAL NOT NULL DEFAULT 0;
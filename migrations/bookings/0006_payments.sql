@@ -0,0 +1,27 @@
+-- Card payments against bookings. One row per payment intent at the processor;
+-- amounts are integer cents.
+CREATE TABLE IF NOT EXISTS payments (
+    id           TEXT PRIMARY KEY,
+    booking_id   TEXT NOT NULL,
+    provider     TEXT NOT NULL,
+    intent_id    TEXT NOT NULL UNIQUE,
+    kind         TEXT NOT NULL,
+    amount_cents INTEGER NOT NULL,
+    currency     TEXT NOT NULL DEFAULT 'usd',
+    status       TEXT NOT NULL DEFAULT 'pending',
+    created_at   TEXT NOT NULL,
+    updated_at   TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_payments_booking ON payments (booking_id);
+
+-- Webhook events already handled, so a redelivered event is a no-op
+CREATE TABLE IF NOT EXISTS payment_events (
+    event_id    TEXT PRIMARY KEY,
+    event_type  TEXT NOT NULL,
+    intent_id   TEXT,
+    received_at TEXT NOT NULL
+);
+
+ALTER TABLE bookings ADD COLUMN amount_paid REAL NOT NULL DEFAULT 0;
+ALTER TABLE bookings ADD COLUMN payment_status TEXT NOT NULL DEFAULT 'unpaid';
@@ -0,0 +1,4 @@
+-- Day-before reminder emails go out once per booking; a reschedule clears this
+ALTER TABLE bookings ADD COLUMN reminder_sent_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_bookings_date_status ON bookings (booking_date, status);
@@ -0,0 +1,8 @@
+-- Tubing shuttle seats. The run id, departure and put-in are copied from the shuttle
+-- schedule when the seat is taken, so the manifest survives schedule edits.
+ALTER TABLE bookings ADD COLUMN shuttle_run TEXT;
+ALTER TABLE bookings ADD COLUMN shuttle_departs TEXT;
+ALTER TABLE bookings ADD COLUMN shuttle_put_in TEXT;
+ALTER TABLE bookings ADD COLUMN shuttle_seats INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_bookings_shuttle ON bookings (booking_date, shuttle_run);
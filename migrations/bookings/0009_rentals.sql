@@ -0,0 +1,37 @@
+-- Equipment check-out / check-in. Times are local YYYY-MM-DDTHH:MM like booking
+-- windows. An asset can be on only one open rental at a time.
+CREATE TABLE IF NOT EXISTS rentals (
+    id                 TEXT PRIMARY KEY,
+    booking_id         TEXT,
+    customer_name      TEXT NOT NULL,
+    customer_phone     TEXT,
+    customer_email     TEXT,
+    checked_out_at     TEXT NOT NULL,
+    expected_return_at TEXT NOT NULL,
+    checked_in_at      TEXT,
+    status             TEXT NOT NULL DEFAULT 'out',
+    late_fee           REAL NOT NULL DEFAULT 0,
+    checked_out_by     TEXT,
+    checked_in_by      TEXT,
+    notes              TEXT,
+    created_at         TEXT NOT NULL,
+    updated_at         TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rentals_status ON rentals (status);
+CREATE INDEX IF NOT EXISTS idx_rentals_checked_out ON rentals (checked_out_at);
+CREATE INDEX IF NOT EXISTS idx_rentals_booking ON rentals (booking_id);
+
+CREATE TABLE IF NOT EXISTS rental_assets (
+    rental_id    TEXT NOT NULL,
+    asset_id     TEXT NOT NULL,
+    asset_name   TEXT NOT NULL,
+    category     TEXT NOT NULL,
+    returned_at  TEXT,
+    late_minutes INTEGER NOT NULL DEFAULT 0,
+    late_fee     REAL NOT NULL DEFAULT 0,
+    return_note  TEXT,
+    PRIMARY KEY (rental_id, asset_id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_rental_assets_out ON rental_assets (asset_id) WHERE returned_at IS NULL;
@@ -0,0 +1,25 @@
+-- Maintenance log per fleet asset. While any record is open the asset is out of
+-- service; out_for_maintenance marks assets the log took out, so closing the last
+-- record returns them without touching assets staff pulled for other reasons.
+CREATE TABLE IF NOT EXISTS asset_maintenance (
+    id                 TEXT PRIMARY KEY,
+    asset_id           TEXT NOT NULL,
+    status             TEXT NOT NULL DEFAULT 'open',
+    opened_on          TEXT NOT NULL,
+    completed_on       TEXT,
+    work_done          TEXT NOT NULL,
+    repair_service_ids TEXT,
+    parts              TEXT,
+    cost               REAL NOT NULL DEFAULT 0,
+    mechanic           TEXT,
+    scheduled_service  INTEGER NOT NULL DEFAULT 0,
+    created_by         TEXT,
+    created_at         TEXT NOT NULL,
+    updated_at         TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_asset_maintenance_asset ON asset_maintenance (asset_id, status);
+CREATE INDEX IF NOT EXISTS idx_asset_maintenance_status ON asset_maintenance (status, opened_on);
+CREATE INDEX IF NOT EXISTS idx_rental_assets_asset ON rental_assets (asset_id);
+
+ALTER TABLE fleet_assets ADD COLUMN out_for_maintenance INTEGER NOT NULL DEFAULT 0;
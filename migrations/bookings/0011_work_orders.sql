@@ -0,0 +1,40 @@
+-- Bike repair work orders opened by customers from the repair catalog. The ticket is
+-- the customer's handle for the public status lookup; events keep each status change.
+CREATE TABLE IF NOT EXISTS work_orders (
+    id                 TEXT PRIMARY KEY,
+    ticket             TEXT NOT NULL UNIQUE,
+    status             TEXT NOT NULL DEFAULT 'received',
+    customer_name      TEXT NOT NULL,
+    customer_email     TEXT NOT NULL,
+    customer_phone     TEXT NOT NULL,
+    bike               TEXT NOT NULL,
+    problem            TEXT,
+    repair_service_ids TEXT NOT NULL,
+    estimate_min       REAL NOT NULL,
+    estimate_max       REAL NOT NULL,
+    promised_on        TEXT NOT NULL,
+    final_price        REAL,
+    mechanic           TEXT,
+    customer_message   TEXT,
+    staff_notes        TEXT,
+    ready_at           TEXT,
+    picked_up_at       TEXT,
+    ip_address         TEXT,
+    created_at         TEXT NOT NULL,
+    updated_at         TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_work_orders_queue ON work_orders (status, promised_on);
+CREATE INDEX IF NOT EXISTS idx_work_orders_email ON work_orders (customer_email);
+
+CREATE TABLE IF NOT EXISTS work_order_events (
+    id            TEXT PRIMARY KEY,
+    work_order_id TEXT NOT NULL,
+    from_status   TEXT,
+    status        TEXT NOT NULL,
+    note          TEXT,
+    created_by    TEXT,
+    created_at    TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_work_order_events_order ON work_order_events (work_order_id, created_at);
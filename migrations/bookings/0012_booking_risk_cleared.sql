@@ -0,0 +1,5 @@
+-- Which hazard flagged a booking, and which one staff cleared it for. The sweep skips
+-- a booking only for the hazard it was cleared for, so worse conditions flag it again.
+ALTER TABLE bookings ADD COLUMN at_risk_key TEXT;
+ALTER TABLE bookings ADD COLUMN risk_cleared_at TEXT;
+ALTER TABLE bookings ADD COLUMN risk_cleared_key TEXT;
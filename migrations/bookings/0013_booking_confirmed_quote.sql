@@ -0,0 +1,4 @@
+-- Price breakdown (JSON PriceQuote) saved when a booking is confirmed, so later price
+-- list edits don't change what a confirmed customer owes. NULL for older bookings,
+-- which keep pricing from the live list.
+ALTER TABLE bookings ADD COLUMN confirmed_quote TEXT;
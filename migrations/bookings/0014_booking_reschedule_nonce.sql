@@ -0,0 +1,4 @@
+-- Changes whenever a booking is moved, so an old reschedule link stops working after
+-- it has been used. Open bookings get one now so their next reminder carries a link.
+ALTER TABLE bookings ADD COLUMN reschedule_nonce TEXT;
+UPDATE bookings SET reschedule_nonce = lower(hex(randomblob(8))) WHERE status IN ('confirmed', 'at_risk');
@@ -0,0 +1,26 @@
+-- Signed liability waivers submitted from /waiver
+CREATE TABLE IF NOT EXISTS waivers (
+    id                TEXT PRIMARY KEY,
+    rental_type       TEXT NOT NULL,
+    adventure_date    TEXT NOT NULL,
+    group_size        INTEGER NOT NULL DEFAULT 1,
+    first_name        TEXT NOT NULL,
+    last_name         TEXT NOT NULL,
+    email             TEXT NOT NULL,
+    phone             TEXT,
+    dob               TEXT NOT NULL,
+    emergency_contact TEXT NOT NULL,
+    emergency_phone   TEXT NOT NULL,
+    has_minors        INTEGER NOT NULL DEFAULT 0,
+    minors_info       TEXT,
+    signature         TEXT NOT NULL,
+    sign_date         TEXT NOT NULL,
+    legal_text_hash   TEXT NOT NULL,
+    submitted_at      TEXT NOT NULL,
+    ip_address        TEXT NOT NULL,
+    user_agent        TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_waivers_adventure_date ON waivers (adventure_date);
+CREATE INDEX IF NOT EXISTS idx_waivers_name ON waivers (last_name, first_name);
+CREATE INDEX IF NOT EXISTS idx_waivers_email ON waivers (email);
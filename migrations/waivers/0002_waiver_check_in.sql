@@ -0,0 +1,5 @@
+-- Front desk check-in tracking
+ALTER TABLE waivers ADD COLUMN checked_in_at TEXT;
+ALTER TABLE waivers ADD COLUMN checked_in_by TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_waivers_rental_date ON waivers (rental_type, adventure_date);
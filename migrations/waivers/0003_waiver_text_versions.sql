@@ -0,0 +1,32 @@
+-- Versioned release agreement text. Versions are immutable; publishing a new
+-- one never changes what earlier signers agreed to.
+CREATE TABLE IF NOT EXISTS waiver_text_versions (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    label           TEXT NOT NULL,
+    paragraphs      TEXT NOT NULL, -- JSON array of strings
+    acknowledgement TEXT NOT NULL,
+    content_hash    TEXT NOT NULL,
+    effective_date  TEXT NOT NULL,
+    created_at      TEXT NOT NULL,
+    created_by      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_waiver_text_effective ON waiver_text_versions (effective_date);
+
+-- Version 1: the agreement originally hard-coded in static/waiver.html
+-- (must match WaiverTextVersion::builtin())
+INSERT INTO waiver_text_versions
+    (id, label, paragraphs, acknowledgement, content_hash, effective_date, created_at, created_by)
+VALUES (
+    1,
+    'Original release agreement',
+    '["In exchange for participation in the activities of Regular and E-Bike Rental, Kayak rental, and/or ''Tubing'' on the Wisconsin River organized by \"Riverview Adventure Company\", or \"RAC\" as named throughout this document, of 740 Water Street, Sauk City, Wisconsin, 53583 and/or use of the property, facilities, and services of RAC, I agree for myself and (if applicable) for the members of my family, to the following:","1. Agreement To Follow Directions. I agree to observe and obey all posted rules and warnings, and further agree to follow any oral instructions or directions given by RAC, or the employees, representatives, or agents of RAC.","2. Assumption of the Risks and Release. I recognize that there are certain inherent risks associated with the above-described activities and I assume full responsibility for personal injury to myself and (if applicable) my family members, and further release and discharge RAC for injury, loss, or damage arising out of my or my family''s use of or presence upon the facilities of RAC, whether caused by the fault of myself, my family, RAC, third parties acting with permission from RAC, or any other individual(s) or circumstances.","3. Indemnification. I agree to indemnify and defend RAC against all claims, causes of action, damages, judgments, costs, or expenses, including attorney fees and other litigation costs, which may in any way arise from my or my family''s use of or presence at locations where RAC equipment or activities are occurring.","4. Fees. I agree to pay for all damages to the facilities of RAC caused by any negligent, reckless, or willful actions by me or my family.","5. Minors. I consent to the participation of me and any minors listed in the activities above and agree on behalf of the above minor to all of the terms and conditions of this agreement. By signing this Release of Liability, I represent that I have legal authority over and custody of minors listed.","6. Applicable Law. Any legal or equitable claim that may arise from participation in the above shall be resolved under Wisconsin law.","7. No Pressure. I agree and acknowledge that I am under no pressure to sign this agreement and that I have been given a reasonable opportunity to review it before signing.","8. Arm''s Length Agreement. This agreement and each of its terms are the product of an arm''s length negotiation between the Parties. In the event any ambiguity is found to exist in the interpretation of this agreement or any of its provisions, the Parties, and each of them, explicitly reject the application of any legal or equitable rule of interpretation which would lead to a construction either \"for\" or \"against\" a particular party based upon their status as the drafter of a specific term, language, or provision giving rise to such ambiguity.","9. Enforceability. The invalidity or unenforceability of any provision of this agreement, whether standing alone or as applied to a particular occurrence or circumstance, shall not affect the validity or enforceability of any other provision of this agreement or of any other applications of such provision, as the case may be. Such invalid or unenforceable provision shall be deemed not to be a part of this agreement."]',
+    'I have read this entire document and understand it. I further understand that by signing this release, I voluntarily surrender certain legal rights. I acknowledge that my electronic signature is the same as a handwritten one.',
+    'a4be33595cc81f117d463a196b14a560dcb95b87319b0c9d7087337130d640eb',
+    '2026-01-01',
+    '2026-01-01T00:00:00+00:00',
+    'system'
+);
+
+ALTER TABLE waivers ADD COLUMN text_version_id INTEGER REFERENCES waiver_text_versions (id);
+UPDATE waivers SET text_version_id = 1 WHERE text_version_id IS NULL;
@@ -0,0 +1,6 @@
+-- Structured minors (JSON array of {name, dob, age, below_minimum_age}).
+-- minors_info is kept for waivers signed with the old free-text field.
+ALTER TABLE waivers ADD COLUMN minors TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE waivers ADD COLUMN minor_age_flag INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_waivers_age_flag ON waivers (adventure_date, minor_age_flag);
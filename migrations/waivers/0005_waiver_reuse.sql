@@ -0,0 +1,5 @@
+-- Season waiver reuse: "confirm for today" rows point at the original signature
+ALTER TABLE waivers ADD COLUMN reuses_waiver_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_waivers_email_dob ON waivers (email, dob);
+CREATE INDEX IF NOT EXISTS idx_waivers_reuses ON waivers (reuses_waiver_id);
@@ -0,0 +1,18 @@
+-- Group waiver links: a leader creates a group and shares /g/<code>
+CREATE TABLE IF NOT EXISTS waiver_groups (
+    code             TEXT PRIMARY KEY,
+    name             TEXT NOT NULL,
+    leader_name      TEXT NOT NULL,
+    leader_email     TEXT NOT NULL,
+    rental_type      TEXT NOT NULL,
+    adventure_date   TEXT NOT NULL,
+    expected_members INTEGER NOT NULL,
+    created_at       TEXT NOT NULL,
+    ip_address       TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_waiver_groups_date ON waiver_groups (adventure_date);
+
+ALTER TABLE waivers ADD COLUMN group_code TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_waivers_group ON waivers (group_code);
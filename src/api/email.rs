@@ -0,0 +1,65 @@
+//! Transactional email over an HTTP API (Resend-compatible)
+
+use worker::*;
+
+const DEFAULT_EMAIL_API_URL: &str = "https://api.resend.com/emails";
+
+/// Sender configuration; absent when `EMAIL_API_KEY` is not set
+pub struct EmailConfig {
+    pub api_url: String,
+    pub api_key: String,
+    pub from: String,
+}
+
+impl EmailConfig {
+    pub fn from_env(env: &Env) -> Option<Self> {
+        let api_key = env.secret("EMAIL_API_KEY").ok()?.to_string();
+        let from = env
+            .var("EMAIL_FROM")
+            .map(|v| v.to_string())
+            .unwrap_or_else(|_| "Riverview Adventure Company <riverviewadventureco@gmail.com>".to_string());
+        let api_url = env
+            .var("EMAIL_API_URL")
+            .map(|v| v.to_string())
+            .unwrap_or_else(|_| DEFAULT_EMAIL_API_URL.to_string());
+        Some(Self { api_url, api_key, from })
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, html: &str, text: &str) -> Result<()> {
+        let body = serde_json::json!({
+            "from": self.from,
+            "to": [to],
+            "subject": subject,
+            "html": html,
+            "text": text,
+        });
+
+        let headers = Headers::new();
+        headers.set("Authorization", &format!("Bearer {}", self.api_key))?;
+        headers.set("Content-Type", "application/json")?;
+
+        let mut init = RequestInit::new();
+        init.with_method(Method::Post);
+        init.with_headers(headers);
+        init.with_body(Some(body.to_string().into()));
+
+        let request = Request::new_with_init(&self.api_url, &init)?;
+        let mut response = Fetch::Request(request).send().await?;
+
+        if !(200..300).contains(&response.status_code()) {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(Error::from(format!("Email API error {}: {}", response.status_code(), detail)));
+        }
+        Ok(())
+    }
+}
+
+/// Escape user-supplied text for an HTML email body
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
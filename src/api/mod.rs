@@ -1,4 +1,7 @@
 //! External API clients
 
 pub mod usgs;
+pub mod nwps;
 pub mod weather;
+pub mod email;
+pub mod payments;
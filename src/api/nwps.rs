@@ -0,0 +1,28 @@
+//! NWS National Water Prediction Service (NWPS) gauge forecasts
+
+use worker::*;
+
+const NWPS_API_BASE: &str = "https://api.water.noaa.gov/nwps/v1";
+
+/// Raw stage/flow forecast JSON for a gauge (NWS LID or USGS site number), parsed by
+/// `FlowForecast::parse`
+pub async fn fetch_stageflow_forecast(gauge_id: &str) -> Result<String> {
+    let url = format!("{}/gauges/{}/stageflow/forecast", NWPS_API_BASE, gauge_id);
+    
+    let headers = Headers::new();
+    headers.set("User-Agent", "RiverviewAdventure/5.0 (riverviewadventureco@gmail.com)")?;
+    headers.set("Accept", "application/json")?;
+    
+    let mut init = RequestInit::new();
+    init.with_method(Method::Get);
+    init.with_headers(headers);
+    
+    let request = Request::new_with_init(&url, &init)?;
+    let mut response = Fetch::Request(request).send().await?;
+    
+    if response.status_code() != 200 {
+        return Err(Error::from(format!("NWPS forecast error {}", response.status_code())));
+    }
+    
+    response.text().await
+}
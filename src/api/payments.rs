@@ -0,0 +1,438 @@
+//! Card payments through a Stripe-compatible API
+
+use serde::Deserialize;
+use worker::*;
+use crate::models::PaymentKind;
+use crate::utils::auth;
+
+const DEFAULT_PAYMENT_API_URL: &str = "https://api.stripe.com";
+
+/// Webhooks signed longer ago than this are rejected as replays
+pub const WEBHOOK_TOLERANCE_SECS: i64 = 300;
+
+/// A charge to start at the processor
+pub struct IntentRequest<'a> {
+    pub amount_cents: i64,
+    pub currency: &'a str,
+    pub booking_id: &'a str,
+    pub kind: PaymentKind,
+    pub description: String,
+    pub receipt_email: Option<&'a str>,
+    /// Repeating a request with the same key returns the same intent
+    pub idempotency_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentIntent {
+    pub id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: WebhookData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookData {
+    pub object: serde_json::Value,
+}
+
+impl WebhookEvent {
+    /// The intent a `payment_intent.*` event is about
+    pub fn intent(&self) -> Option<PaymentIntent> {
+        if !self.event_type.starts_with("payment_intent.") {
+            return None;
+        }
+        serde_json::from_value(self.data.object.clone()).ok()
+    }
+}
+
+pub trait PaymentProvider {
+    /// Recorded on each payment row
+    fn name(&self) -> &'static str;
+    /// Key for the processor's browser SDK, if configured
+    fn publishable_key(&self) -> Option<&str>;
+    async fn create_intent(&self, request: &IntentRequest<'_>) -> Result<PaymentIntent>;
+    /// Cancel an open intent so it can no longer be paid; fails if it already was
+    async fn cancel_intent(&self, intent_id: &str) -> Result<PaymentIntent>;
+    /// Check a webhook signature and parse the event; `now` is Unix seconds
+    fn verify_webhook(&self, payload: &str, signature: &str, now: i64) -> std::result::Result<WebhookEvent, String>;
+}
+
+/// The configured processor; absent when `PAYMENT_API_KEY` is not set
+pub fn payment_provider(env: &Env) -> Option<impl PaymentProvider> {
+    StripeProvider::from_env(env)
+}
+
+/// Stripe, or anything speaking its API (`PAYMENT_API_URL` points at a mock in testing)
+pub struct StripeProvider {
+    pub api_url: String,
+    pub api_key: String,
+    pub webhook_secret: Option<String>,
+    pub publishable_key: Option<String>,
+}
+
+impl StripeProvider {
+    pub fn from_env(env: &Env) -> Option<Self> {
+        let api_key = env.secret("PAYMENT_API_KEY").ok()?.to_string();
+        let api_url = env
+            .var("PAYMENT_API_URL")
+            .map(|v| v.to_string().trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| DEFAULT_PAYMENT_API_URL.to_string());
+        Some(Self {
+            api_url,
+            api_key,
+            webhook_secret: env.secret("PAYMENT_WEBHOOK_SECRET").ok().map(|s| s.to_string()),
+            publishable_key: env.var("PAYMENT_PUBLISHABLE_KEY").ok().map(|v| v.to_string()),
+        })
+    }
+}
+
+/// A POST to the processor, kept apart from the fetch so it can be checked on its own
+#[derive(Debug, Clone)]
+pub struct ApiCall {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl StripeProvider {
+    /// `POST /v1/payment_intents` for a charge
+    pub fn intent_call(&self, request: &IntentRequest<'_>) -> ApiCall {
+        let mut form = vec![
+            ("amount", request.amount_cents.to_string()),
+            ("currency", request.currency.to_string()),
+            ("description", request.description.clone()),
+            ("automatic_payment_methods[enabled]", "true".to_string()),
+            ("metadata[booking_id]", request.booking_id.to_string()),
+            ("metadata[kind]", request.kind.slug().to_string()),
+        ];
+        if let Some(email) = request.receipt_email {
+            form.push(("receipt_email", email.to_string()));
+        }
+
+        ApiCall {
+            url: format!("{}/v1/payment_intents", self.api_url),
+            headers: vec![
+                ("Authorization", format!("Bearer {}", self.api_key)),
+                ("Content-Type", "application/x-www-form-urlencoded".to_string()),
+                ("Idempotency-Key", request.idempotency_key.clone()),
+            ],
+            body: form_encode(&form),
+        }
+    }
+
+    /// `POST /v1/payment_intents/:id/cancel`
+    pub fn cancel_call(&self, intent_id: &str) -> ApiCall {
+        ApiCall {
+            url: format!("{}/v1/payment_intents/{}/cancel", self.api_url, intent_id),
+            headers: vec![
+                ("Authorization", format!("Bearer {}", self.api_key)),
+                ("Content-Type", "application/x-www-form-urlencoded".to_string()),
+            ],
+            body: String::new(),
+        }
+    }
+
+    async fn post(&self, call: ApiCall) -> Result<PaymentIntent> {
+        let headers = Headers::new();
+        for (name, value) in &call.headers {
+            headers.set(name, value)?;
+        }
+
+        let mut init = RequestInit::new();
+        init.with_method(Method::Post);
+        init.with_headers(headers);
+        init.with_body(Some(call.body.into()));
+
+        let request = Request::new_with_init(&call.url, &init)?;
+        let mut response = Fetch::Request(request).send().await?;
+        let body = response.text().await.unwrap_or_default();
+        parse_intent(response.status_code(), &body)
+    }
+}
+
+/// The processor's reply to an intent call
+pub fn parse_intent(status: u16, body: &str) -> Result<PaymentIntent> {
+    if !(200..300).contains(&status) {
+        return Err(Error::from(format!("Payment API error {}: {}", status, body)));
+    }
+    serde_json::from_str(body).map_err(|e| Error::from(format!("Invalid payment intent: {}", e)))
+}
+
+impl PaymentProvider for StripeProvider {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn publishable_key(&self) -> Option<&str> {
+        self.publishable_key.as_deref()
+    }
+
+    async fn create_intent(&self, request: &IntentRequest<'_>) -> Result<PaymentIntent> {
+        self.post(self.intent_call(request)).await
+    }
+
+    async fn cancel_intent(&self, intent_id: &str) -> Result<PaymentIntent> {
+        self.post(self.cancel_call(intent_id)).await
+    }
+
+    /// `Stripe-Signature: t=<unix>,v1=<hex hmac of "t.payload">[,v1=...]`
+    fn verify_webhook(&self, payload: &str, signature: &str, now: i64) -> std::result::Result<WebhookEvent, String> {
+        let secret = self.webhook_secret.as_deref().ok_or("Webhook secret is not configured")?;
+
+        let mut timestamp = None;
+        let mut candidates = Vec::new();
+        for part in signature.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+                Some(("v1", v)) => candidates.push(v),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or("Signature has no timestamp")?;
+        if (now - timestamp).abs() > WEBHOOK_TOLERANCE_SECS {
+            return Err("Signature timestamp is outside the tolerance".to_string());
+        }
+
+        let signed = format!("{}.{}", timestamp, payload);
+        if !candidates.iter().any(|sig| auth::verify_hmac_hex(secret, &signed, sig)) {
+            return Err("Signature does not match".to_string());
+        }
+
+        serde_json::from_str(payload).map_err(|e| format!("Invalid event: {}", e))
+    }
+}
+
+/// `application/x-www-form-urlencoded` body
+fn form_encode(pairs: &[(&str, String)]) -> String {
+    let encode = |value: &str| -> String {
+        value
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+                b' ' => "+".to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    };
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hmac::{Hmac, Mac};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// What the mock processor saw
+    struct Received {
+        request_line: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Received {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// A one-shot HTTP server on a free local port that answers with `status` and `reply`
+    fn mock_processor(status: u16, reply: &'static str) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((k, v)) = line.split_once(':') {
+                    headers.push((k.trim().to_string(), v.trim().to_string()));
+                }
+            }
+            let length = headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, v)| v.parse::<usize>().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reply.len(),
+                reply
+            )
+            .unwrap();
+            tx.send(Received {
+                request_line: request_line.trim_end().to_string(),
+                headers,
+                body: String::from_utf8(body).unwrap(),
+            })
+            .unwrap();
+        });
+        (url, rx)
+    }
+
+    /// Send a call the way the worker's fetch would and return the status and body
+    fn send(call: &ApiCall) -> (u16, String) {
+        let rest = call.url.strip_prefix("http://").unwrap();
+        let (host, path) = rest.split_at(rest.find('/').unwrap());
+        let mut stream = TcpStream::connect(host).unwrap();
+        let mut head = format!("POST {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n", path, host);
+        for (name, value) in &call.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", call.body.len()));
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(call.body.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    fn provider(api_url: String) -> StripeProvider {
+        StripeProvider {
+            api_url,
+            api_key: "sk_test_123".to_string(),
+            webhook_secret: Some("whsec_test".to_string()),
+            publishable_key: None,
+        }
+    }
+
+    fn deposit() -> IntentRequest<'static> {
+        IntentRequest {
+            amount_cents: 2500,
+            currency: "usd",
+            booking_id: "bk_1",
+            kind: PaymentKind::Deposit,
+            description: "Kayak RV-1234 on 2026-07-04".to_string(),
+            receipt_email: Some("pat@example.com"),
+            idempotency_key: "bk_1-deposit-2500-0".to_string(),
+        }
+    }
+
+    #[test]
+    fn creates_an_intent_against_the_processor() {
+        let (url, received) = mock_processor(200, r#"{"id":"pi_1","client_secret":"pi_1_secret_x","amount":2500,"status":"requires_payment_method"}"#);
+        let call = provider(url).intent_call(&deposit());
+
+        let (status, body) = send(&call);
+        let intent = parse_intent(status, &body).unwrap();
+        assert_eq!(intent.id, "pi_1");
+        assert_eq!(intent.client_secret.as_deref(), Some("pi_1_secret_x"));
+        assert_eq!(intent.amount, 2500);
+
+        let seen = received.recv().unwrap();
+        assert_eq!(seen.request_line, "POST /v1/payment_intents HTTP/1.1");
+        assert_eq!(seen.header("Authorization"), Some("Bearer sk_test_123"));
+        assert_eq!(seen.header("Idempotency-Key"), Some("bk_1-deposit-2500-0"));
+        assert_eq!(seen.header("Content-Type"), Some("application/x-www-form-urlencoded"));
+        let form: Vec<&str> = seen.body.split('&').collect();
+        for field in [
+            "amount=2500",
+            "currency=usd",
+            "description=Kayak+RV-1234+on+2026-07-04",
+            "automatic_payment_methods%5Benabled%5D=true",
+            "metadata%5Bbooking_id%5D=bk_1",
+            "metadata%5Bkind%5D=deposit",
+            "receipt_email=pat%40example.com",
+        ] {
+            assert!(form.contains(&field), "missing {} in {}", field, seen.body);
+        }
+    }
+
+    #[test]
+    fn processor_errors_are_returned() {
+        let (url, _received) = mock_processor(402, r#"{"error":{"type":"card_error","message":"Your card was declined."}}"#);
+        let (status, body) = send(&provider(url).intent_call(&deposit()));
+        let error = parse_intent(status, &body).unwrap_err().to_string();
+        assert!(error.contains("402"), "{}", error);
+        assert!(error.contains("declined"), "{}", error);
+    }
+
+    #[test]
+    fn malformed_replies_are_errors() {
+        let (url, _received) = mock_processor(200, r#"{"object":"list"}"#);
+        let (status, body) = send(&provider(url).intent_call(&deposit()));
+        assert!(parse_intent(status, &body).is_err());
+    }
+
+    #[test]
+    fn cancels_an_open_intent() {
+        let (url, received) = mock_processor(200, r#"{"id":"pi_1","amount":2500,"status":"canceled"}"#);
+        let (status, body) = send(&provider(url).cancel_call("pi_1"));
+        assert_eq!(parse_intent(status, &body).unwrap().id, "pi_1");
+
+        let seen = received.recv().unwrap();
+        assert_eq!(seen.request_line, "POST /v1/payment_intents/pi_1/cancel HTTP/1.1");
+        assert_eq!(seen.header("Authorization"), Some("Bearer sk_test_123"));
+        assert!(seen.body.is_empty());
+    }
+
+    #[test]
+    fn paid_intents_cannot_be_canceled() {
+        let (url, _received) = mock_processor(
+            400,
+            r#"{"error":{"type":"invalid_request_error","message":"This PaymentIntent's status is succeeded."}}"#,
+        );
+        let (status, body) = send(&provider(url).cancel_call("pi_1"));
+        assert!(parse_intent(status, &body).is_err());
+    }
+
+    fn signature(secret: &str, timestamp: i64, payload: &str) -> String {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+        let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        format!("t={},v1={}", timestamp, hex)
+    }
+
+    #[test]
+    fn verifies_webhook_signatures() {
+        let provider = provider(String::new());
+        let payload = r#"{"id":"evt_1","type":"payment_intent.succeeded","data":{"object":{"id":"pi_1","amount":2500}}}"#;
+        let now = 1_790_000_000;
+
+        let event = provider.verify_webhook(payload, &signature("whsec_test", now, payload), now + 10).unwrap();
+        assert_eq!(event.id, "evt_1");
+        assert_eq!(event.intent().map(|i| i.id), Some("pi_1".to_string()));
+
+        assert!(provider.verify_webhook(payload, &signature("whsec_other", now, payload), now).is_err());
+        let tampered = payload.replace("2500", "1");
+        assert!(provider.verify_webhook(&tampered, &signature("whsec_test", now, payload), now).is_err());
+        let stale = now + WEBHOOK_TOLERANCE_SECS + 1;
+        assert!(provider.verify_webhook(payload, &signature("whsec_test", now, payload), stale).is_err());
+    }
+}
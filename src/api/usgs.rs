@@ -1,17 +1,18 @@
 //! USGS Water Services API client
 
 use worker::*;
-use crate::models::UsgsResponse;
+use crate::models::{UsgsResponse, FLOW_TREND_PERIOD};
 
 const USGS_API_BASE: &str = "https://waterservices.usgs.gov/nwis/iv";
 
-pub async fn fetch_current_flow(station_id: &str) -> Result<UsgsResponse> {
+/// Latest values of `parameters`, with the trailing readings used for trends
+pub async fn fetch_current_flow(station_id: &str, parameters: &[String]) -> Result<UsgsResponse> {
     let url = format!(
-        "{}?format=json&sites={}&parameterCd=00060,00010&siteStatus=all",
-        USGS_API_BASE, station_id
+        "{}?format=json&sites={}&parameterCd={}&siteStatus=all&period={}",
+        USGS_API_BASE, station_id, parameters.join(","), FLOW_TREND_PERIOD
     );
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("User-Agent", "RiverviewAdventure/5.0")?;
     
     let mut init = RequestInit::new();
@@ -38,7 +39,7 @@ pub async fn fetch_historical_flow(
         USGS_API_BASE, station_id, start_date, end_date
     );
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("User-Agent", "RiverviewAdventure/5.0")?;
     
     let mut init = RequestInit::new();
@@ -26,7 +26,7 @@ pub async fn fetch_tomorrow_io(
 pub async fn fetch_nws_alerts(zone: &str) -> Result<NwsAlertResponse> {
     let url = format!("https://api.weather.gov/alerts/active/zone/{}", zone);
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("User-Agent", "RiverviewAdventure/5.0 (riverviewadventureco@gmail.com)")?;
     headers.set("Accept", "application/geo+json")?;
     
@@ -48,7 +48,7 @@ pub async fn fetch_nws_forecast(lat: &str, lon: &str) -> Result<serde_json::Valu
     // First get the forecast office and grid coordinates
     let points_url = format!("https://api.weather.gov/points/{},{}", lat, lon);
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("User-Agent", "RiverviewAdventure/5.0 (riverviewadventureco@gmail.com)")?;
     headers.set("Accept", "application/geo+json")?;
     
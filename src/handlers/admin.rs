@@ -2,6 +2,7 @@
 
 use worker::*;
 use crate::models::*;
+use crate::handlers::flow::{load_flow_thresholds, load_network};
 use crate::utils::{cache, auth};
 
 // Admin HTML pages
@@ -54,7 +55,7 @@ pub async fn verify_login(mut req: Request, ctx: RouteContext<()>) -> Result<Res
     }
     
     // Generate temporary token for TOTP verification
-    let token = auth::generate_session_token();
+    let token = auth::generate_session_token()?;
     
     // Store token temporarily (5 minutes)
     if let Ok(kv) = ctx.kv("CACHE") {
@@ -111,7 +112,7 @@ pub async fn verify_totp(mut req: Request, ctx: RouteContext<()>) -> Result<Resp
     }
     
     // Generate session token
-    let session_token = auth::generate_session_token();
+    let session_token = auth::generate_session_token()?;
     
     // Store session (24 hours)
     if let Ok(kv) = ctx.kv("CACHE") {
@@ -192,6 +193,15 @@ pub async fn update_services(mut req: Request, ctx: RouteContext<()>) -> Result<
     }
 }
 
+/// GET /api/admin/thresholds - flow limits, per-activity limits, cold-water minimum and hysteresis
+pub async fn get_thresholds(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    json_response(&load_flow_thresholds(&ctx.env).await)
+}
+
 pub async fn update_thresholds(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
     if !verify_session(&req, &ctx).await {
         return Response::error("Unauthorized", 401);
@@ -202,10 +212,267 @@ pub async fn update_thresholds(mut req: Request, ctx: RouteContext<()>) -> Resul
         Err(_) => return Response::error("Invalid thresholds data", 400),
     };
     
+    if let Err(message) = thresholds.validate() {
+        return Response::error(message, 400);
+    }
+    
+    let kv = match ctx.kv("SETTINGS") {
+        Ok(kv) => kv,
+        Err(_) => return Response::error("Settings storage not available", 500),
+    };
+    if let Err(e) = cache::set_cached(&kv, "flow_thresholds", &thresholds, 0).await {
+        return Response::error(format!("Failed to save thresholds: {}", e), 500);
+    }
+
+    // Cached readings, forecasts and conditions were rated against the old limits, and
+    // the previous reading would hold its old status through the hysteresis band
+    clear_flow_caches(&ctx).await;
+
+    json_response(&serde_json::json!({"success": true}))
+}
+
+/// Drop cached flow readings, forecasts and conditions so the next request rates afresh
+async fn clear_flow_caches(ctx: &RouteContext<()>) {
+    if let Ok(cache_kv) = ctx.kv("CACHE") {
+        for prefix in ["flow:", "flow-previous:", "flow-forecast:"] {
+            if let Err(e) = cache::delete_prefix(&cache_kv, prefix).await {
+                console_warn!("Failed to clear {} cache: {}", prefix, e);
+            }
+        }
+        let _ = cache_kv.delete("conditions:all").await;
+    }
+}
+
+/// GET /api/admin/waiver-settings - season waiver reuse rules
+pub async fn get_waiver_settings(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let settings = if let Ok(kv) = ctx.kv("SETTINGS") {
+        cache::get_cached::<WaiverSettings>(&kv, "waiver_settings").await
+            .unwrap_or_default()
+    } else {
+        WaiverSettings::default()
+    };
+    
+    json_response(&settings)
+}
+
+pub async fn update_waiver_settings(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let settings: WaiverSettings = match req.json().await {
+        Ok(s) => s,
+        Err(_) => return Response::error("Invalid waiver settings data", 400),
+    };
+    
+    if let Err(message) = settings.validate() {
+        return Response::error(message, 400);
+    }
+    
+    if let Ok(kv) = ctx.kv("SETTINGS") {
+        match cache::set_cached(&kv, "waiver_settings", &settings, 0).await {
+            Ok(_) => json_response(&serde_json::json!({"success": true})),
+            Err(e) => Response::error(format!("Failed to save waiver settings: {}", e), 500),
+        }
+    } else {
+        Response::error("Settings storage not available", 500)
+    }
+}
+
+/// GET /api/admin/booking-schedule - slot times, durations and capacity per service
+pub async fn get_booking_schedule(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let schedule = if let Ok(kv) = ctx.kv("SETTINGS") {
+        cache::get_cached::<BookingSchedule>(&kv, "booking_schedule").await
+            .unwrap_or_default()
+    } else {
+        BookingSchedule::default()
+    };
+    
+    json_response(&schedule)
+}
+
+pub async fn update_booking_schedule(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let schedule: BookingSchedule = match req.json().await {
+        Ok(s) => s,
+        Err(_) => return Response::error("Invalid booking schedule data", 400),
+    };
+    
+    if let Err(message) = schedule.validate() {
+        return Response::error(message, 400);
+    }
+    
+    if let Ok(kv) = ctx.kv("SETTINGS") {
+        match cache::set_cached(&kv, "booking_schedule", &schedule, 0).await {
+            Ok(_) => json_response(&serde_json::json!({"success": true})),
+            Err(e) => Response::error(format!("Failed to save booking schedule: {}", e), 500),
+        }
+    } else {
+        Response::error("Settings storage not available", 500)
+    }
+}
+
+/// GET /api/admin/shuttle-schedule - tubing shuttle departures, seats and put-ins
+pub async fn get_shuttle_schedule(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let schedule = if let Ok(kv) = ctx.kv("SETTINGS") {
+        cache::get_cached::<ShuttleSchedule>(&kv, "shuttle_schedule").await
+            .unwrap_or_default()
+    } else {
+        ShuttleSchedule::default()
+    };
+    
+    json_response(&schedule)
+}
+
+pub async fn update_shuttle_schedule(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let schedule: ShuttleSchedule = match req.json().await {
+        Ok(s) => s,
+        Err(_) => return Response::error("Invalid shuttle schedule data", 400),
+    };
+    
+    if let Err(message) = schedule.validate() {
+        return Response::error(message, 400);
+    }
+    
+    if let Ok(kv) = ctx.kv("SETTINGS") {
+        match cache::set_cached(&kv, "shuttle_schedule", &schedule, 0).await {
+            Ok(_) => json_response(&serde_json::json!({"success": true})),
+            Err(e) => Response::error(format!("Failed to save shuttle schedule: {}", e), 500),
+        }
+    } else {
+        Response::error("Settings storage not available", 500)
+    }
+}
+
+/// GET /api/admin/service-intervals - days and rentals between routine service per category
+pub async fn get_service_intervals(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let intervals = if let Ok(kv) = ctx.kv("SETTINGS") {
+        cache::get_cached::<ServiceIntervals>(&kv, "service_intervals").await
+            .unwrap_or_default()
+    } else {
+        ServiceIntervals::default()
+    };
+    
+    json_response(&intervals)
+}
+
+pub async fn update_service_intervals(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let intervals: ServiceIntervals = match req.json().await {
+        Ok(i) => i,
+        Err(_) => return Response::error("Invalid service interval data", 400),
+    };
+    
+    if let Err(message) = intervals.validate() {
+        return Response::error(message, 400);
+    }
+    
+    if let Ok(kv) = ctx.kv("SETTINGS") {
+        match cache::set_cached(&kv, "service_intervals", &intervals, 0).await {
+            Ok(_) => json_response(&serde_json::json!({"success": true})),
+            Err(e) => Response::error(format!("Failed to save service intervals: {}", e), 500),
+        }
+    } else {
+        Response::error("Settings storage not available", 500)
+    }
+}
+
+/// GET /api/admin/river-network - watched gauges (just USGS_STATION_ID until configured)
+pub async fn get_river_network(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    json_response(&load_network(&ctx.env).await)
+}
+
+pub async fn update_river_network(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let network: RiverNetwork = match req.json().await {
+        Ok(n) => n,
+        Err(_) => return Response::error("Invalid river network data", 400),
+    };
+    
+    if let Err(message) = network.validate() {
+        return Response::error(message, 400);
+    }
+    
+    let kv = match ctx.kv("SETTINGS") {
+        Ok(kv) => kv,
+        Err(_) => return Response::error("Settings storage not available", 500),
+    };
+    if let Err(e) = cache::set_cached(&kv, "river_network", &network, 0).await {
+        return Response::error(format!("Failed to save river network: {}", e), 500);
+    }
+
+    // Station limits, the primary gauge and the forecast gauge may all have changed
+    clear_flow_caches(&ctx).await;
+
+    json_response(&serde_json::json!({"success": true}))
+}
+
+pub async fn get_price_list(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let list = if let Ok(kv) = ctx.kv("SETTINGS") {
+        cache::get_cached::<PriceList>(&kv, "price_list").await
+            .unwrap_or_default()
+    } else {
+        PriceList::default()
+    };
+    
+    json_response(&list)
+}
+
+pub async fn update_price_list(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let list: PriceList = match req.json().await {
+        Ok(l) => l,
+        Err(_) => return Response::error("Invalid price list data", 400),
+    };
+    
+    if let Err(message) = list.validate() {
+        return Response::error(message, 400);
+    }
+    
     if let Ok(kv) = ctx.kv("SETTINGS") {
-        match cache::set_cached(&kv, "flow_thresholds", &thresholds, 0).await {
+        match cache::set_cached(&kv, "price_list", &list, 0).await {
             Ok(_) => json_response(&serde_json::json!({"success": true})),
-            Err(e) => Response::error(format!("Failed to save thresholds: {}", e), 500),
+            Err(e) => Response::error(format!("Failed to save price list: {}", e), 500),
         }
     } else {
         Response::error("Settings storage not available", 500)
@@ -260,24 +527,22 @@ pub async fn get_analytics(req: Request, ctx: RouteContext<()>) -> Result<Respon
 }
 
 // Helper functions
-async fn verify_session(req: &Request, ctx: &RouteContext<()>) -> bool {
-    let auth_header = match req.headers().get("Authorization").ok().flatten() {
-        Some(h) => h,
-        None => return false,
-    };
+pub(crate) async fn verify_session(req: &Request, ctx: &RouteContext<()>) -> bool {
+    admin_session(req, ctx).await.is_some()
+}
+
+/// Look up the admin session behind the request's bearer token
+pub(crate) async fn admin_session(req: &Request, ctx: &RouteContext<()>) -> Option<AdminSession> {
+    let auth_header = req.headers().get("Authorization").ok().flatten()?;
     
     let token = auth_header.strip_prefix("Bearer ").unwrap_or(&auth_header);
     
-    if let Ok(kv) = ctx.kv("CACHE") {
-        let session: Option<AdminSession> = cache::get_cached(&kv, &format!("session:{}", token)).await;
-        session.is_some()
-    } else {
-        false
-    }
+    let kv = ctx.kv("CACHE").ok()?;
+    cache::get_cached(&kv, &format!("session:{}", token)).await
 }
 
 fn html_response(content: &str) -> Result<Response> {
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "text/html; charset=utf-8")?;
     headers.set("X-Frame-Options", "DENY")?;
     headers.set("X-Content-Type-Options", "nosniff")?;
@@ -287,7 +552,7 @@ fn html_response(content: &str) -> Result<Response> {
 
 fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
     let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "no-store")?;
     Ok(Response::ok(json)?.with_headers(headers))
@@ -0,0 +1,1067 @@
+//! Reservations: public availability, holds and bookings; admin view/modify/cancel
+
+use worker::*;
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::booking_email::{new_reschedule_nonce, send_confirmation, verify_reschedule_token};
+use crate::handlers::fleet::in_service_units;
+use crate::handlers::pricing::load_price_list;
+use crate::handlers::gift_card::{find_card, find_card_by_code, record, LedgerChange};
+use crate::handlers::promo::{email_used, find_promo, redeem, redemption_for, release};
+use crate::handlers::shuttle::{booked_seats, load_shuttle_schedule, plan_seat};
+use crate::handlers::water_safety::current_hazards;
+use crate::handlers::waiver::rate_limited;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::{auth, cache};
+
+/// Confirmation numbers look like `RAC-K7RM2Q`
+const CONFIRMATION_PREFIX: &str = "RAC-";
+
+/// GET /api/book/availability?service=kayak-single&date=YYYY-MM-DD
+pub async fn get_availability(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let url = req.url()?;
+    let mut service = None;
+    let mut date = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "service" => service = BookingService::from_slug(&value),
+            "date" => date = parse_date(&value),
+            _ => {}
+        }
+    }
+
+    let (service, date) = match (service, date) {
+        (Some(s), Some(d)) => (s, d),
+        _ => return error_response("Provide service and date (YYYY-MM-DD)", 400),
+    };
+
+    let schedule = load_schedule(&ctx.env).await;
+    let services = load_services(&ctx.env).await;
+    let status = service.status(&services);
+    let now = local_now(shop_timezone(&ctx.env).await);
+    let today = now.date();
+
+    let service_schedule = schedule.for_service(service).filter(|s| s.enabled);
+    let mut response = AvailabilityResponse {
+        service,
+        label: service.label().to_string(),
+        date: date.format("%Y-%m-%d").to_string(),
+        open: false,
+        message: None,
+        seats_per_unit: service.seats_per_unit(),
+        slots: vec![],
+    };
+
+    let service_schedule = match service_schedule {
+        Some(s) if service_open(status) && date >= today => s,
+        Some(_) if date < today => {
+            response.message = Some("That date has already passed".to_string());
+            return json_response(&response, 200);
+        }
+        Some(_) => {
+            response.message = Some(status.message.clone());
+            return json_response(&response, 200);
+        }
+        None => {
+            response.message = Some(format!("{} is not taking online bookings", service.label()));
+            return json_response(&response, 200);
+        }
+    };
+
+    if service.on_water() {
+        if let Some(hazard) = hazard_on(&current_hazards(&ctx.env).await, service, date) {
+            response.message = Some(format!("Online booking is paused: {}", hazard.reason));
+            return json_response(&response, 200);
+        }
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Booking storage not available", 500),
+    };
+
+    let committed = committed_units(&db, service, &response.date).await?;
+    let capacity = service_schedule.online_capacity(in_service_units(&db, service.fleet_category()).await?);
+    let shuttle = match service.uses_shuttle() {
+        true => Some(load_shuttle_schedule(&ctx.env).await).filter(|s| s.enabled),
+        false => None,
+    };
+    let seats = match &shuttle {
+        Some(_) => booked_seats(&db, &response.date, None).await?,
+        None => vec![],
+    };
+    response.open = true;
+    response.slots = service_schedule
+        .slot_times
+        .iter()
+        .filter_map(|time| service_schedule.window(date, time))
+        // Slots that have already started can't be booked
+        .filter(|window| window.start > now)
+        .map(|window| {
+            let used = committed_in(&committed, &window);
+            let mut available = capacity.saturating_sub(used);
+            let runs: Vec<ShuttleRunAvailability> = match &shuttle {
+                Some(s) => s.runs_for(date, &window).into_iter().map(|r| ShuttleRunAvailability::new(s, r, &seats)).collect(),
+                None => vec![],
+            };
+            if shuttle.is_some() {
+                // Tubers can't go out without a seat to the put-in
+                available = available.min(runs.iter().map(|r| r.available).max().unwrap_or(0));
+            }
+            SlotAvailability {
+                start_time: window.start.format("%H:%M").to_string(),
+                end_time: window.end.format("%H:%M").to_string(),
+                capacity,
+                committed: used,
+                available,
+                shuttle: runs,
+            }
+        })
+        .collect();
+
+    json_response(&response, 200)
+}
+
+/// POST /api/book/hold - reserve units for `hold_minutes` while the customer checks out
+pub async fn create_hold(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Holds take real inventory off the page, so cap them harder than reads
+    if rate_limited(&ctx, &format!("rate:book-hold:{}", client_ip), 20).await {
+        return error_response("Too many booking attempts. Please try again later.", 429);
+    }
+
+    let body: HoldRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return error_response("Invalid booking details", 400),
+    };
+
+    let schedule = load_schedule(&ctx.env).await;
+    let plan = match body.plan(&schedule, local_now(shop_timezone(&ctx.env).await)) {
+        Ok(p) => p,
+        Err(message) => return error_response(&message, 400),
+    };
+
+    let services = load_services(&ctx.env).await;
+    let status = plan.service.status(&services);
+    if !service_open(status) {
+        return error_response(&status.message, 409);
+    }
+
+    if plan.service.on_water() {
+        if let Some(hazard) = hazard_on(&current_hazards(&ctx.env).await, plan.service, plan.date) {
+            return error_response(&format!("Online booking is paused: {}", hazard.reason), 409);
+        }
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Booking storage not available", 500),
+    };
+
+    let run = match plan_seat(&ctx.env, &db, &plan, body.shuttle_run.as_deref(), None).await? {
+        Ok(r) => r,
+        Err(message) => return error_response(&message, 409),
+    };
+
+    let in_service = in_service_units(&db, plan.service.fleet_category()).await?;
+    let capacity = schedule
+        .for_service(plan.service)
+        .map(|s| s.online_capacity(in_service))
+        .unwrap_or(0);
+
+    let now = chrono::Utc::now();
+    let now_str = now.to_rfc3339();
+    let expires_at = (now + chrono::Duration::minutes(schedule.hold_minutes as i64)).to_rfc3339();
+    let id = auth::generate_id("bk")?;
+
+    // Single statement so two checkouts can't both take the last units or shuttle seats
+    let sql = format!(
+        "INSERT INTO bookings (
+            id, service, booking_date, start_time, start_at, end_at, quantity, party_size,
+            status, hold_expires_at, created_at, updated_at, ip_address,
+            shuttle_run, shuttle_departs, shuttle_put_in, shuttle_seats
+         )
+         SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'held', ?9, ?10, ?10, ?11, ?13, ?14, ?15, ?16
+         WHERE (SELECT COALESCE(SUM(quantity), 0) FROM bookings
+                WHERE service = ?2 AND start_at < ?6 AND end_at > ?5 AND {pred}) + ?7 <= ?12
+           AND (?13 IS NULL OR (SELECT COALESCE(SUM(shuttle_seats), 0) FROM bookings
+                WHERE booking_date = ?3 AND shuttle_run = ?13 AND {pred}) + ?16 <= ?17)",
+        pred = committed_booking_predicate("?10")
+    );
+    let result = query!(
+        &db,
+        &sql,
+        &id,
+        &plan.service,
+        &plan.date.format("%Y-%m-%d").to_string(),
+        &plan.start_time,
+        &plan.window.start_at(),
+        &plan.window.end_at(),
+        &plan.quantity,
+        &plan.party_size,
+        &expires_at,
+        &now_str,
+        &client_ip,
+        &capacity,
+        &run.as_ref().map(|r| r.id.clone()),
+        &run.as_ref().map(|r| r.departs.clone()),
+        &run.as_ref().map(|r| r.put_in.clone()),
+        &run.as_ref().map_or(0, |_| plan.party_size),
+        &run.as_ref().map_or(0, |r| r.seats),
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return error_response(
+            "Sorry, that time no longer has enough availability. Please choose another slot.",
+            409,
+        );
+    }
+
+    match find_booking(&db, &id).await? {
+        Some(booking) => json_response(&BookingResponse {
+            success: true,
+            message: format!("Held for {} minutes. Enter your details to confirm.", schedule.hold_minutes),
+            quote: booking.quote(&load_price_list(&ctx.env).await),
+            booking: Some(booking),
+            error: None,
+        }, 200),
+        None => error_response("Could not hold your booking. Please try again.", 500),
+    }
+}
+
+/// POST /api/book - confirm a hold with contact details and issue a confirmation number
+pub async fn confirm_booking(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let body: BookingRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return error_response("Invalid booking details", 400),
+    };
+
+    if let Err(message) = body.validate() {
+        return error_response(&message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Booking storage not available", 500),
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let notes = body.notes.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let email = body.email.trim().to_lowercase();
+    let prices = load_price_list(&ctx.env).await;
+
+    let promo_raw = body.promo_code.as_deref().filter(|c| !c.trim().is_empty());
+    let card_raw = body.gift_card_code.as_deref().filter(|c| !c.trim().is_empty());
+    let hold = if promo_raw.is_some() || card_raw.is_some() {
+        match find_booking(&db, body.hold_id.trim()).await? {
+            Some(b) if b.status == BookingStatus::Held => Some(b),
+            _ => return error_response("Your hold has expired. Please choose a time again.", 409),
+        }
+    } else {
+        None
+    };
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+
+    // Check the gift card up front so a bad code doesn't claim a promo use
+    let mut card = None;
+    if let (Some(raw), Some(hold)) = (card_raw, &hold) {
+        let found = match normalize_gift_card_code(raw) {
+            Some(code) => find_card_by_code(&db, &code).await?,
+            None => None,
+        };
+        let found = match found {
+            Some(c) => c,
+            None => return error_response("We couldn't find that gift card", 400),
+        };
+        if let Some(reason) = found.unusable(today) {
+            return error_response(reason, 400);
+        }
+        if hold.quote(&prices).is_none() {
+            return error_response("Gift cards can't be applied to this booking online", 400);
+        }
+        card = Some(found);
+    }
+
+    // Claim the promo before confirming; the use is given back if confirming fails
+    let mut promo = None;
+    if let (Some(raw), Some(hold)) = (promo_raw, &hold) {
+        let code = match normalize_promo_code(raw) {
+            Some(c) => c,
+            None => return error_response("That promo code is not valid", 400),
+        };
+        let found = match find_promo(&db, &code).await? {
+            Some(p) => p,
+            None => return error_response("That promo code is not valid", 400),
+        };
+        let used = found.once_per_email && email_used(&db, &code, &email).await?;
+        if let Err(message) = found.check(hold.service, today, used) {
+            return error_response(&message, 400);
+        }
+        let quote = match hold.quote(&prices) {
+            Some(q) => q.with_promo(&found),
+            None => return error_response("That promo code is not valid", 400),
+        };
+        match redeem(&db, &found, &hold.id, &email, quote.subtotal, quote.promo_discount).await? {
+            Some(redemption_id) => promo = Some((code, quote.promo_discount, redemption_id)),
+            None => return error_response("That promo code is no longer available", 409),
+        }
+    }
+    let (promo_code, promo_discount) = match &promo {
+        Some((code, discount, _)) => (Some(code.clone()), *discount),
+        None => (None, 0.0),
+    };
+
+    // Then take what the card covers of the total after the promo
+    let mut card_debit = None;
+    if let (Some(found), Some(hold)) = (&card, &hold) {
+        let total = hold.quote(&prices).map(|q| q.total - promo_discount).unwrap_or(0.0);
+        let cents = found.balance_cents.min(to_cents(total));
+        if cents > 0 {
+            let entry = record(&db, &found.id, LedgerChange {
+                entry_type: LedgerEntryType::Redeem,
+                amount_cents: -cents,
+                booking_id: Some(&hold.id),
+                reference: None,
+                note: Some("Online booking"),
+                created_by: "online",
+            }).await?;
+            if entry.is_none() {
+                if let Some((_, _, redemption_id)) = &promo {
+                    release(&db, redemption_id).await?;
+                }
+                return error_response("That gift card no longer has enough balance. Please try again.", 409);
+            }
+            card_debit = Some((found.id.clone(), cents));
+        }
+    }
+    let (gift_card_id, gift_card_amount) = match &card_debit {
+        Some((id, cents)) => (Some(id.clone()), from_cents(*cents)),
+        None => (None, 0.0),
+    };
+
+    // Save the price now; later price list edits don't change what this customer owes
+    let priced = match &hold {
+        Some(h) => Some(h.clone()),
+        None => find_booking(&db, body.hold_id.trim()).await?.filter(|b| b.status == BookingStatus::Held),
+    };
+    let confirmed_quote = priced
+        .map(|b| Booking {
+            promo_code: promo_code.clone(),
+            promo_discount,
+            gift_card_id: gift_card_id.clone(),
+            gift_card_amount,
+            ..b
+        })
+        .and_then(|b| b.live_quote(&prices))
+        .and_then(|q| serde_json::to_string(&q).ok());
+
+    let failed = |message: &'static str, status: u16| {
+        let db = &db;
+        let promo = &promo;
+        let card_debit = &card_debit;
+        let hold_id = body.hold_id.trim();
+        async move {
+            if let Some((_, _, redemption_id)) = promo {
+                release(db, redemption_id).await?;
+            }
+            if let Some((card_id, cents)) = card_debit {
+                record(db, card_id, LedgerChange {
+                    entry_type: LedgerEntryType::Refund,
+                    amount_cents: *cents,
+                    booking_id: Some(hold_id),
+                    reference: None,
+                    note: Some("Booking was not confirmed"),
+                    created_by: "online",
+                }).await?;
+            }
+            error_response(message, status)
+        }
+    };
+
+    // Retry on the (unlikely) confirmation number collision
+    for _ in 0..3 {
+        let confirmation = format!(
+            "{}{}",
+            CONFIRMATION_PREFIX,
+            auth::generate_code(GROUP_CODE_ALPHABET, 6)?
+        );
+        let result = query!(
+            &db,
+            "UPDATE bookings SET status = 'confirmed', confirmation_number = ?1, customer_name = ?2,
+                customer_email = ?3, customer_phone = ?4, notes = ?5, hold_expires_at = NULL, updated_at = ?6,
+                promo_code = ?8, promo_discount = ?9, gift_card_id = ?10, gift_card_amount = ?11, confirmed_quote = ?12,
+                reschedule_nonce = ?13
+             WHERE id = ?7 AND status = 'held' AND hold_expires_at > ?6",
+            &confirmation,
+            &body.name.trim(),
+            &email,
+            &body.phone.trim(),
+            &notes,
+            &now,
+            &body.hold_id.trim(),
+            &promo_code,
+            &promo_discount,
+            &gift_card_id,
+            &gift_card_amount,
+            &confirmed_quote,
+            &new_reschedule_nonce()?,
+        )?
+        .run()
+        .await;
+
+        let changes = match result {
+            Ok(r) => r.meta()?.and_then(|m| m.changes).unwrap_or(0),
+            Err(e) if e.to_string().contains("UNIQUE") => continue,
+            Err(e) => {
+                failed("Could not confirm your booking. Please try again.", 500).await?;
+                return Err(e);
+            }
+        };
+
+        if changes == 0 {
+            return match find_booking(&db, body.hold_id.trim()).await? {
+                Some(b) if b.status == BookingStatus::Confirmed => failed("This booking is already confirmed.", 409).await,
+                _ => failed("Your hold has expired. Please choose a time again.", 409).await,
+            };
+        }
+
+        let booking = match find_booking(&db, body.hold_id.trim()).await? {
+            Some(b) => b,
+            None => return error_response("Could not load your booking", 500),
+        };
+        let quote = booking.quote(&prices);
+        send_confirmation(&ctx.env, &booking, quote.as_ref()).await;
+
+        return json_response(&BookingResponse {
+            success: true,
+            message: format!(
+                "You're booked! Confirmation number {}.",
+                booking.confirmation_number.as_deref().unwrap_or_default()
+            ),
+            quote,
+            booking: Some(booking),
+            error: None,
+        }, 200);
+    }
+
+    failed("Could not confirm your booking. Please try again.", 500).await
+}
+
+/// GET /api/book/reschedule?token= - the booking a reschedule link is for
+pub async fn get_reschedule(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let url = req.url()?;
+    let token = url.query_pairs()
+        .find(|(k, _)| k == "token")
+        .map(|(_, v)| v.to_string())
+        .unwrap_or_default();
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Booking storage not available", 500),
+    };
+
+    match reschedulable(&ctx.env, &db, &token).await? {
+        Ok(booking) => json_response(&BookingResponse {
+            success: true,
+            message: format!(
+                "Rescheduling {} on {} at {}. Choose a new date and time.",
+                booking.confirmation_number.as_deref().unwrap_or_default(),
+                booking.booking_date,
+                booking.start_time
+            ),
+            quote: None,
+            booking: Some(booking),
+            error: None,
+        }, 200),
+        Err(message) => error_response(message, 404),
+    }
+}
+
+/// POST /api/book/reschedule - move a booking to another published slot
+pub async fn reschedule_booking(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let body: RescheduleRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return error_response("Invalid reschedule request", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Booking storage not available", 500),
+    };
+
+    let booking = match reschedulable(&ctx.env, &db, &body.token).await? {
+        Ok(b) => b,
+        Err(message) => return error_response(message, 404),
+    };
+
+    // Same rules as a new online booking, for the same party
+    let schedule = load_schedule(&ctx.env).await;
+    let plan = HoldRequest {
+        service: booking.service.slug().to_string(),
+        date: body.date,
+        start_time: body.start_time,
+        party_size: booking.party_size,
+        quantity: Some(booking.quantity),
+        shuttle_run: None,
+    }
+    .plan(&schedule, local_now(shop_timezone(&ctx.env).await));
+    let plan = match plan {
+        Ok(p) => p,
+        Err(message) => return error_response(&message, 400),
+    };
+
+    let services = load_services(&ctx.env).await;
+    let status = plan.service.status(&services);
+    if !service_open(status) {
+        return error_response(&status.message, 409);
+    }
+    if plan.service.on_water() {
+        if let Some(hazard) = hazard_on(&current_hazards(&ctx.env).await, plan.service, plan.date) {
+            return error_response(&format!("Online booking is paused for that date: {}", hazard.reason), 409);
+        }
+    }
+
+    let run = match plan_seat(&ctx.env, &db, &plan, body.shuttle_run.as_deref(), Some(&booking.id)).await? {
+        Ok(r) => r,
+        Err(message) => return error_response(&message, 409),
+    };
+
+    let in_service = in_service_units(&db, plan.service.fleet_category()).await?;
+    let capacity = schedule
+        .for_service(plan.service)
+        .map(|s| s.online_capacity(in_service))
+        .unwrap_or(0);
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let sql = format!(
+        "UPDATE bookings SET booking_date = ?1, start_time = ?2, start_at = ?3, end_at = ?4, status = 'confirmed',
+            at_risk_reason = NULL, at_risk_at = NULL, at_risk_key = NULL, risk_cleared_at = NULL,
+            risk_cleared_key = NULL, reminder_sent_at = NULL, updated_at = ?5,
+            shuttle_run = ?10, shuttle_departs = ?11, shuttle_put_in = ?12, shuttle_seats = ?13,
+            reschedule_nonce = ?15
+         WHERE id = ?6 AND status IN ('confirmed', 'at_risk') AND reschedule_nonce = ?16
+           AND (SELECT COALESCE(SUM(quantity), 0) FROM bookings
+                WHERE id != ?6 AND service = ?7 AND start_at < ?4 AND end_at > ?3 AND {pred}) + ?8 <= ?9
+           AND (?10 IS NULL OR (SELECT COALESCE(SUM(shuttle_seats), 0) FROM bookings
+                WHERE id != ?6 AND booking_date = ?1 AND shuttle_run = ?10 AND {pred}) + ?13 <= ?14)",
+        pred = committed_booking_predicate("?5")
+    );
+    let result = query!(
+        &db,
+        &sql,
+        &plan.date.format("%Y-%m-%d").to_string(),
+        &plan.start_time,
+        &plan.window.start_at(),
+        &plan.window.end_at(),
+        &now,
+        &booking.id,
+        &booking.service,
+        &booking.quantity,
+        &capacity,
+        &run.as_ref().map(|r| r.id.clone()),
+        &run.as_ref().map(|r| r.departs.clone()),
+        &run.as_ref().map(|r| r.put_in.clone()),
+        &run.as_ref().map_or(0, |_| booking.party_size),
+        &run.as_ref().map_or(0, |r| r.seats),
+        &new_reschedule_nonce()?,
+        &booking.reschedule_nonce,
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return error_response(
+            "Sorry, that time doesn't have enough availability. Please choose another slot.",
+            409,
+        );
+    }
+
+    let booking = match find_booking(&db, &booking.id).await? {
+        Some(b) => b,
+        None => return error_response("Could not load your booking", 500),
+    };
+    let quote = booking.quote(&load_price_list(&ctx.env).await);
+    send_confirmation(&ctx.env, &booking, quote.as_ref()).await;
+
+    json_response(&BookingResponse {
+        success: true,
+        message: format!(
+            "You're rescheduled to {} at {}. Confirmation number {}.",
+            booking.booking_date,
+            booking.start_time,
+            booking.confirmation_number.as_deref().unwrap_or_default()
+        ),
+        quote,
+        booking: Some(booking),
+        error: None,
+    }, 200)
+}
+
+/// The booking behind a reschedule token, if it can still be moved
+async fn reschedulable(env: &Env, db: &D1Database, token: &str) -> Result<std::result::Result<Booking, &'static str>> {
+    let token = match verify_reschedule_token(env, token, local_now(shop_timezone(env).await)) {
+        Some(t) => t,
+        None => return Ok(Err("This reschedule link is not valid or has expired")),
+    };
+    Ok(match find_booking(db, &token.booking_id).await? {
+        // A link from before the last move has a stale nonce
+        Some(b) if b.reschedule_nonce.as_deref() != Some(token.nonce.as_str()) => {
+            Err("This reschedule link has already been used")
+        }
+        Some(b) if matches!(b.status, BookingStatus::Confirmed | BookingStatus::AtRisk) => Ok(b),
+        Some(_) => Err("This booking can no longer be rescheduled online"),
+        None => Err("This reschedule link is not valid or has expired"),
+    })
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+#[derive(Deserialize)]
+struct CountRow {
+    total: i64,
+}
+
+/// GET /api/admin/bookings?q=&date=&date_from=&date_to=&service=&status=&page=&per_page=
+pub async fn list_bookings(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let query = BookingSearchQuery::from_pairs(
+        pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+        local_now(shop_timezone(&ctx.env).await).date(),
+    );
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let (where_clause, params) = query.where_clause();
+    let bindings: Vec<JsValue> = params.iter().map(|p| JsValue::from(p.as_str())).collect();
+
+    let total = db.prepare(format!("SELECT COUNT(*) AS total FROM bookings {}", where_clause))
+        .bind(&bindings)?
+        .first::<CountRow>(None)
+        .await?
+        .map(|r| r.total)
+        .unwrap_or(0);
+
+    let bookings = db.prepare(format!(
+            "SELECT * FROM bookings {} ORDER BY start_at, customer_name LIMIT {} OFFSET {}",
+            where_clause,
+            query.per_page,
+            query.offset()
+        ))
+        .bind(&bindings)?
+        .all()
+        .await?
+        .results::<Booking>()?;
+
+    json_response(&serde_json::json!({
+        "bookings": bookings,
+        "total": total,
+        "page": query.page,
+        "per_page": query.per_page,
+        "total_pages": (total as u32).div_ceil(query.per_page),
+    }), 200)
+}
+
+/// GET /api/admin/bookings/:id
+pub async fn get_booking(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing booking id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    match find_booking(&db, &id).await? {
+        Some(booking) => json_response(&booking, 200),
+        None => Response::error("Booking not found", 404),
+    }
+}
+
+/// POST /api/admin/bookings/:id - change date, time, size or contact details
+pub async fn update_booking(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing booking id", 400),
+    };
+
+    let update: BookingUpdate = match req.json().await {
+        Ok(u) => u,
+        Err(_) => return Response::error("Invalid booking update", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let booking = match find_booking(&db, &id).await? {
+        Some(b) if matches!(b.status, BookingStatus::Confirmed | BookingStatus::AtRisk) => b,
+        Some(_) => return Response::error("Only confirmed bookings can be modified", 409),
+        None => return Response::error("Booking not found", 404),
+    };
+
+    let schedule = load_schedule(&ctx.env).await;
+    let service_schedule = match schedule.for_service(booking.service) {
+        Some(s) => s,
+        None => return Response::error("Service has no schedule", 409),
+    };
+
+    let date = match parse_date(update.date.as_deref().unwrap_or(&booking.booking_date)) {
+        Some(d) => d,
+        None => return Response::error("Invalid date", 400),
+    };
+    // Staff may move a party to any start time, not just published slots
+    let start = match parse_time(update.start_time.as_deref().unwrap_or(&booking.start_time)) {
+        Some(t) => date.and_time(t),
+        None => return Response::error("Invalid start time (HH:MM)", 400),
+    };
+    let window = TimeWindow { start, end: start + service_schedule.duration() };
+    if window.end.date() != date {
+        return Response::error("Booking would run past midnight", 400);
+    }
+
+    let party_size = update.party_size.unwrap_or(booking.party_size);
+    let quantity = update.quantity.unwrap_or_else(|| booking.service.units_for(party_size).max(booking.quantity));
+    if party_size == 0 || quantity == 0 || quantity < booking.service.units_for(party_size) {
+        return Response::error("Not enough units for the party size", 400);
+    }
+
+    let pick = |new: &Option<String>, old: &Option<String>| -> Option<String> {
+        match new {
+            Some(v) if v.trim().is_empty() => None,
+            Some(v) => Some(v.trim().to_string()),
+            None => old.clone(),
+        }
+    };
+    // Staff can book past the online limit, but not past the boats and bikes we have
+    let capacity = in_service_units(&db, booking.service.fleet_category()).await?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    // A seated tuber keeps their run if it still leaves in the new window with room for
+    // the party, otherwise takes the earliest one that does; staff pick others with /shuttle
+    let run = match &booking.shuttle_run {
+        Some(current) => {
+            let plan = HoldPlan {
+                service: booking.service,
+                date,
+                start_time: window.start.format("%H:%M").to_string(),
+                window,
+                quantity,
+                party_size,
+            };
+            let run = match plan_seat(&ctx.env, &db, &plan, Some(current), Some(&id)).await? {
+                Ok(run) => Ok(run),
+                Err(_) => plan_seat(&ctx.env, &db, &plan, None, Some(&id)).await?,
+            };
+            match run {
+                Ok(run) => run,
+                Err(message) => return Response::error(message, 409),
+            }
+        }
+        None => None,
+    };
+
+    // Moving an at-risk trip to another day resolves it, and any move retires old reschedule links
+    let new_date = date.format("%Y-%m-%d").to_string();
+    let (status, at_risk_reason, at_risk_at) = if new_date != booking.booking_date {
+        (BookingStatus::Confirmed, None, None)
+    } else {
+        (booking.status, booking.at_risk_reason.clone(), booking.at_risk_at.clone())
+    };
+
+    // A bigger or longer trip is re-priced at today's prices; a move keeps the confirmed price
+    let resized = Booking {
+        booking_date: new_date.clone(),
+        start_at: window.start_at(),
+        end_at: window.end_at(),
+        quantity,
+        party_size,
+        confirmed_quote: None,
+        ..booking.clone()
+    };
+    let confirmed_quote = if quantity != booking.quantity || party_size != booking.party_size || resized.hours() != booking.hours() {
+        resized.live_quote(&load_price_list(&ctx.env).await)
+    } else {
+        booking.confirmed_quote.clone()
+    };
+    let confirmed_quote = confirmed_quote.and_then(|q| serde_json::to_string(&q).ok());
+
+    let sql = format!(
+        "UPDATE bookings SET booking_date = ?1, start_time = ?2, start_at = ?3, end_at = ?4, quantity = ?5,
+            party_size = ?6, customer_name = ?7, customer_email = ?8, customer_phone = ?9, notes = ?10,
+            updated_at = ?11, status = ?15, at_risk_reason = ?16, at_risk_at = ?17, confirmed_quote = ?18,
+            reminder_sent_at = CASE WHEN booking_date = ?1 THEN reminder_sent_at END,
+            at_risk_key = CASE WHEN booking_date = ?1 THEN at_risk_key END,
+            risk_cleared_at = CASE WHEN booking_date = ?1 THEN risk_cleared_at END,
+            risk_cleared_key = CASE WHEN booking_date = ?1 THEN risk_cleared_key END,
+            reschedule_nonce = CASE WHEN start_at = ?3 THEN reschedule_nonce ELSE ?19 END,
+            shuttle_run = ?20, shuttle_departs = ?21, shuttle_put_in = ?22, shuttle_seats = ?23
+         WHERE id = ?12 AND status IN ('confirmed', 'at_risk')
+           AND (SELECT COALESCE(SUM(quantity), 0) FROM bookings
+                WHERE id != ?12 AND service = ?13 AND start_at < ?4 AND end_at > ?3 AND {pred}) + ?5 <= ?14
+           AND (?20 IS NULL OR (SELECT COALESCE(SUM(shuttle_seats), 0) FROM bookings
+                WHERE id != ?12 AND booking_date = ?1 AND shuttle_run = ?20 AND {pred}) + ?23 <= ?24)",
+        pred = committed_booking_predicate("?11")
+    );
+    let result = query!(
+        &db,
+        &sql,
+        &new_date,
+        &window.start.format("%H:%M").to_string(),
+        &window.start_at(),
+        &window.end_at(),
+        &quantity,
+        &party_size,
+        &pick(&update.customer_name, &booking.customer_name),
+        &pick(&update.customer_email, &booking.customer_email).map(|e| e.to_lowercase()),
+        &pick(&update.customer_phone, &booking.customer_phone),
+        &pick(&update.notes, &booking.notes),
+        &now,
+        &id,
+        &booking.service,
+        &capacity,
+        &status,
+        &at_risk_reason,
+        &at_risk_at,
+        &confirmed_quote,
+        &new_reschedule_nonce()?,
+        &run.as_ref().map(|r| r.id.clone()),
+        &run.as_ref().map(|r| r.departs.clone()),
+        &run.as_ref().map(|r| r.put_in.clone()),
+        &run.as_ref().map_or(0, |_| party_size),
+        &run.as_ref().map_or(0, |r| r.seats),
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return Response::error("Not enough availability for that change", 409);
+    }
+
+    match find_booking(&db, &id).await? {
+        Some(b) => json_response(&serde_json::json!({"success": true, "booking": b}), 200),
+        None => Response::error("Booking not found", 404),
+    }
+}
+
+/// POST /api/admin/bookings/:id/cancel - body `{"reason": "..."}` is optional
+pub async fn cancel_booking(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing booking id", 400),
+    };
+
+    let body: CancelBookingRequest = req.json().await.unwrap_or_default();
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    // A voided card can't take its share back, so staff must refund it another way first
+    let open = find_booking(&db, &id).await?.filter(|b| b.status != BookingStatus::Cancelled);
+    if let (Some(booking), false) = (&open, body.refund_elsewhere) {
+        if let (Some(card_id), true) = (&booking.gift_card_id, booking.gift_card_amount > 0.0) {
+            if let Some(card) = find_card(&db, card_id).await?.filter(|c| c.status == GiftCardStatus::Void) {
+                return Response::error(format!(
+                    "Gift card ending {} has been voided, so the ${:.2} paid with it can't go back on the card. \
+                     Refund it another way, then cancel with refund_elsewhere.",
+                    card.last4, booking.gift_card_amount
+                ), 409);
+            }
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let result = query!(
+        &db,
+        "UPDATE bookings SET status = 'cancelled', cancelled_at = ?1, cancelled_by = ?2, cancel_reason = ?3,
+            hold_expires_at = NULL, updated_at = ?1
+         WHERE id = ?4 AND status != 'cancelled'",
+        &now,
+        &session.username,
+        &body.reason.as_deref().map(str::trim).filter(|r| !r.is_empty()),
+        &id,
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return match find_booking(&db, &id).await? {
+            Some(_) => Response::error("Booking is already cancelled", 409),
+            None => Response::error("Booking not found", 404),
+        };
+    }
+
+    let booking = match find_booking(&db, &id).await? {
+        Some(b) => b,
+        None => return Response::error("Booking not found", 404),
+    };
+
+    // Give the promo use back so the customer can rebook with the same code
+    if let Some(redemption_id) = redemption_for(&db, &booking.id).await? {
+        release(&db, &redemption_id).await?;
+    }
+
+    // Put anything paid by gift card back on the card; what a voided card can't take is
+    // reported as refund_due
+    let mut gift_card_refund = None;
+    let mut refund_due = 0.0;
+    if let (Some(card_id), true) = (&booking.gift_card_id, booking.gift_card_amount > 0.0) {
+        gift_card_refund = record(&db, card_id, LedgerChange {
+            entry_type: LedgerEntryType::Refund,
+            amount_cents: to_cents(booking.gift_card_amount),
+            booking_id: Some(&booking.id),
+            reference: booking.confirmation_number.as_deref(),
+            note: Some("Booking cancelled"),
+            created_by: &session.username,
+        }).await?;
+        if gift_card_refund.is_none() {
+            refund_due = booking.gift_card_amount;
+        }
+    }
+
+    json_response(&serde_json::json!({
+        "success": true,
+        "booking": booking,
+        "gift_card_refund": gift_card_refund,
+        "refund_due": refund_due,
+    }), 200)
+}
+
+/// POST /api/admin/bookings/:id/clear-risk - staff spoke with the customer and the trip stands
+pub async fn clear_risk(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing booking id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    // at_risk_reason/at_risk_at stay as history; the cleared key keeps the sweep from
+    // re-flagging it for the same hazard, but not for a new one
+    let result = query!(
+        &db,
+        "UPDATE bookings SET status = 'confirmed', risk_cleared_at = ?1, risk_cleared_key = at_risk_key, updated_at = ?1
+         WHERE id = ?2 AND status = 'at_risk'",
+        &chrono::Utc::now().to_rfc3339(),
+        &id,
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return match find_booking(&db, &id).await? {
+            Some(_) => Response::error("Booking is not at risk", 409),
+            None => Response::error("Booking not found", 404),
+        };
+    }
+
+    match find_booking(&db, &id).await? {
+        Some(b) => json_response(&serde_json::json!({"success": true, "booking": b}), 200),
+        None => Response::error("Booking not found", 404),
+    }
+}
+
+/// Units held or confirmed for a service on a date
+pub(crate) async fn committed_units(
+    db: &D1Database,
+    service: BookingService,
+    date: &str,
+) -> Result<Vec<CommittedUnits>> {
+    let sql = format!(
+        "SELECT start_at, end_at, quantity FROM bookings
+         WHERE service = ?1 AND booking_date = ?2 AND {}",
+        committed_booking_predicate("?3")
+    );
+    query!(
+        db,
+        &sql,
+        &service,
+        &date,
+        &chrono::Utc::now().to_rfc3339(),
+    )?
+    .all()
+    .await?
+    .results::<CommittedUnits>()
+}
+
+pub(crate) async fn load_schedule(env: &Env) -> BookingSchedule {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<BookingSchedule>(&kv, "booking_schedule").await.unwrap_or_default(),
+        Err(_) => BookingSchedule::default(),
+    }
+}
+
+pub(crate) async fn load_services(env: &Env) -> ServicesConfig {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<ServicesConfig>(&kv, "services").await.unwrap_or_default(),
+        Err(_) => ServicesConfig::default(),
+    }
+}
+
+pub(crate) async fn find_booking(db: &D1Database, id: &str) -> Result<Option<Booking>> {
+    query!(db, "SELECT * FROM bookings WHERE id = ?1", &id)?
+        .first::<Booking>(None)
+        .await
+}
+
+fn error_response(message: &str, status: u16) -> Result<Response> {
+    json_response(&BookingResponse {
+        success: false,
+        message: message.to_string(),
+        booking: None,
+        quote: None,
+        error: Some(message.to_string()),
+    }, status)
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
@@ -0,0 +1,437 @@
+//! Booking confirmation and day-before reminder emails
+
+use chrono::{Datelike, Timelike};
+use worker::*;
+use crate::api::email::{escape_html, EmailConfig};
+use crate::api::weather::fetch_nws_forecast;
+use crate::handlers::booking::load_services;
+use crate::handlers::flow::current_flow;
+use crate::handlers::shuttle::load_shuttle_schedule;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::auth;
+
+/// Reschedule links carry `RACB2:<booking id>:<nonce>:<trip start>` signed with
+/// BOOKING_TOKEN_SECRET. The link dies when the trip starts or the booking moves.
+const RESCHEDULE_TOKEN_PREFIX: &str = "RACB2:";
+/// Hex digits in a booking's reschedule nonce
+const RESCHEDULE_NONCE_LEN: usize = 16;
+/// Reminders for tomorrow's trips start going out at this local hour
+const REMINDER_SEND_HOUR: u32 = 9;
+/// Reminders sent per cron run
+const REMINDER_BATCH: u32 = 50;
+
+/// What tomorrow looks like, fetched once per reminder run
+struct Outlook {
+    flow: Option<FlowData>,
+    forecast: Option<serde_json::Value>,
+    sun: SunData,
+    services: ServicesConfig,
+    shuttle: ShuttleSchedule,
+}
+
+impl Outlook {
+    async fn load(env: &Env, date: chrono::NaiveDate) -> Self {
+        let lat = env.var("LOCATION_LAT").map(|v| v.to_string()).unwrap_or_else(|_| "43.2722".to_string());
+        let lon = env.var("LOCATION_LON").map(|v| v.to_string()).unwrap_or_else(|_| "-89.7208".to_string());
+
+        let flow = match current_flow(env).await {
+            Ok(f) => Some(f),
+            Err(e) => {
+                console_error!("Flow for reminders failed: {}", e);
+                None
+            }
+        };
+        let forecast = match fetch_nws_forecast(&lat, &lon).await {
+            Ok(f) => Some(f),
+            Err(e) => {
+                console_error!("Forecast for reminders failed: {}", e);
+                None
+            }
+        };
+        let sun = SunData::calculate(
+            date.year(),
+            date.month(),
+            date.day(),
+            lat.parse().unwrap_or(43.2722),
+            lon.parse().unwrap_or(-89.7208),
+            utc_offset_hours(date, shop_timezone(env).await),
+        );
+
+        Self {
+            flow,
+            forecast,
+            sun,
+            services: load_services(env).await,
+            shuttle: load_shuttle_schedule(env).await,
+        }
+    }
+
+    /// River status for the booked activity, or the overall status for other services
+    fn river_status(&self, booking: &Booking) -> Option<&FlowStatus> {
+        self.flow.as_ref().map(|f| {
+            f.activities
+                .iter()
+                .find(|a| booking.service.river_activity() == Some(a.activity))
+                .map_or(&f.status, |a| &a.status)
+        })
+    }
+
+    /// High water for the booked activity is a reason to offer another day on the river
+    fn unsettled(&self, booking: &Booking) -> bool {
+        let status = self.river_status(booking);
+        booking.status == BookingStatus::AtRisk
+            || (booking.service.on_water()
+                && status.is_some_and(|s| matches!(s, FlowStatus::Caution | FlowStatus::Danger)))
+    }
+}
+
+/// Email the customer their confirmation. Failures are logged, not returned.
+pub(crate) async fn send_confirmation(env: &Env, booking: &Booking, quote: Option<&PriceQuote>) {
+    let (Some(config), Some(to)) = (EmailConfig::from_env(env), booking.customer_email.as_deref()) else {
+        return;
+    };
+    let site_url = env.var("SITE_URL").map(|v| v.to_string()).unwrap_or_default();
+    let shuttle = shuttle_line(booking, &load_shuttle_schedule(env).await);
+    let (subject, html, text) = confirmation_email(booking, quote, shuttle.as_deref(), &site_url);
+    if let Err(e) = config.send(to, &subject, &html, &text).await {
+        console_error!("Failed to send booking confirmation for {}: {}", booking.id, e);
+    }
+}
+
+/// Cron trigger: email tomorrow's customers once, from REMINDER_SEND_HOUR local time
+pub async fn send_reminders(env: &Env) -> Result<u32> {
+    let local_now = local_now(shop_timezone(env).await);
+    if local_now.hour() < REMINDER_SEND_HOUR {
+        return Ok(0);
+    }
+    let config = match EmailConfig::from_env(env) {
+        Some(c) => c,
+        None => return Ok(0),
+    };
+
+    let tomorrow = local_now.date() + chrono::Duration::days(1);
+    let db = env.d1("DB_BOOKINGS")?;
+    let bookings = query!(
+        &db,
+        "SELECT * FROM bookings
+         WHERE booking_date = ?1 AND status IN ('confirmed', 'at_risk')
+           AND reminder_sent_at IS NULL AND customer_email IS NOT NULL
+         ORDER BY start_at
+         LIMIT ?2",
+        &tomorrow.format("%Y-%m-%d").to_string(),
+        &REMINDER_BATCH,
+    )?
+    .all()
+    .await?
+    .results::<Booking>()?;
+    if bookings.is_empty() {
+        return Ok(0);
+    }
+
+    let outlook = Outlook::load(env, tomorrow).await;
+    let site_url = env.var("SITE_URL").map(|v| v.to_string()).unwrap_or_default();
+    let mut sent = 0;
+    for booking in &bookings {
+        // Claim the booking first so overlapping runs don't send twice
+        let claimed = query!(
+            &db,
+            "UPDATE bookings SET reminder_sent_at = ?1 WHERE id = ?2 AND reminder_sent_at IS NULL",
+            &chrono::Utc::now().to_rfc3339(),
+            &booking.id,
+        )?
+        .run()
+        .await?;
+        if claimed.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+            continue;
+        }
+
+        let reschedule = outlook.unsettled(booking).then(|| reschedule_url(env, &site_url, booking)).flatten();
+        let (subject, html, text) = reminder_email(booking, &outlook, reschedule.as_deref(), &site_url);
+        let to = booking.customer_email.as_deref().unwrap_or_default();
+        match config.send(to, &subject, &html, &text).await {
+            Ok(()) => sent += 1,
+            Err(e) => {
+                console_error!("Failed to send reminder for {}: {}", booking.id, e);
+                // Try again on the next run
+                query!(&db, "UPDATE bookings SET reminder_sent_at = NULL WHERE id = ?1", &booking.id)?
+                    .run()
+                    .await?;
+            }
+        }
+    }
+    Ok(sent)
+}
+
+/// What a valid reschedule link names
+pub(crate) struct RescheduleToken {
+    pub booking_id: String,
+    /// Must still match the booking's `reschedule_nonce`
+    pub nonce: String,
+}
+
+/// A fresh value for `reschedule_nonce`
+pub(crate) fn new_reschedule_nonce() -> Result<String> {
+    auth::generate_code(b"0123456789abcdef", RESCHEDULE_NONCE_LEN)
+}
+
+/// The booking and nonce from a reschedule link, if the signature is ours and the
+/// trip hasn't started by shop-local `now`
+pub(crate) fn verify_reschedule_token(env: &Env, token: &str, now: chrono::NaiveDateTime) -> Option<RescheduleToken> {
+    let secret = env.secret("BOOKING_TOKEN_SECRET").ok()?.to_string();
+    let payload = auth::verify_token(&secret, token)?;
+    let mut parts = payload.strip_prefix(RESCHEDULE_TOKEN_PREFIX)?.splitn(3, ':');
+    let (booking_id, nonce, expires) = (parts.next()?, parts.next()?, parts.next()?);
+    let expires = chrono::NaiveDateTime::parse_from_str(expires, "%Y-%m-%dT%H:%M").ok()?;
+    if now >= expires || nonce.is_empty() {
+        return None;
+    }
+    Some(RescheduleToken {
+        booking_id: booking_id.to_string(),
+        nonce: nonce.to_string(),
+    })
+}
+
+/// `/book?reschedule=<token>`; None when BOOKING_TOKEN_SECRET is not configured or the
+/// booking has no nonce yet
+fn reschedule_url(env: &Env, site_url: &str, booking: &Booking) -> Option<String> {
+    let secret = env.secret("BOOKING_TOKEN_SECRET").ok()?.to_string();
+    let nonce = booking.reschedule_nonce.as_deref()?;
+    let token = auth::sign_token(
+        &secret,
+        &format!("{}{}:{}:{}", RESCHEDULE_TOKEN_PREFIX, booking.id, nonce, booking.start_at),
+    );
+    Url::parse_with_params(&format!("{}/book", site_url.trim_end_matches('/')), &[("reschedule", token)])
+        .ok()
+        .map(|u| u.to_string())
+}
+
+/// Where and when a tuber's shuttle leaves
+fn shuttle_line(booking: &Booking, schedule: &ShuttleSchedule) -> Option<String> {
+    let departs = booking.shuttle_departs.as_deref()?;
+    let put_in = booking.shuttle_put_in.as_deref().unwrap_or_default();
+    let put_in = schedule.put_in(put_in).map(|p| p.name.as_str()).unwrap_or(put_in);
+    Some(format!(
+        "Your shuttle to {} departs at {}. Please check in at least 15 minutes before; it can't wait for late riders.",
+        put_in, departs
+    ))
+}
+
+fn confirmation_email(booking: &Booking, quote: Option<&PriceQuote>, shuttle: Option<&str>, site_url: &str) -> (String, String, String) {
+    let confirmation = booking.confirmation_number.as_deref().unwrap_or_default();
+    let name = booking.customer_name.as_deref().unwrap_or("there");
+    let subject = format!(
+        "Booking confirmed: {} on {} ({})",
+        booking.service.label(),
+        booking.booking_date,
+        confirmation
+    );
+
+    let mut html = format!(
+        "<p>Hi {},</p>\
+         <p>You're booked with Riverview Adventure Company:</p>\
+         <p><strong>{}</strong><br>{} at {}<br>Party of {} ({} {})</p>\
+         <p>Confirmation number: <strong>{}</strong></p>",
+        escape_html(name),
+        escape_html(booking.service.label()),
+        escape_html(&booking.booking_date),
+        escape_html(&booking.start_time),
+        booking.party_size,
+        booking.quantity,
+        if booking.quantity == 1 { "unit" } else { "units" },
+        escape_html(confirmation),
+    );
+    let mut text = format!(
+        "Hi {},\n\nYou're booked with Riverview Adventure Company:\n\n{}\n{} at {}\nParty of {} ({} {})\n\n\
+         Confirmation number: {}\n",
+        name,
+        booking.service.label(),
+        booking.booking_date,
+        booking.start_time,
+        booking.party_size,
+        booking.quantity,
+        if booking.quantity == 1 { "unit" } else { "units" },
+        confirmation,
+    );
+
+    if let Some(line) = shuttle {
+        html.push_str(&format!("<p>{}</p>", escape_html(line)));
+        text.push_str(&format!("\n{}\n", line));
+    }
+
+    if let Some(quote) = quote {
+        let mut lines = vec![format!("Total: ${:.2}", quote.total)];
+        if quote.gift_card > 0.0 {
+            lines.push(format!("Paid by gift card: ${:.2}", quote.gift_card));
+        }
+        if booking.amount_paid > 0.0 {
+            lines.push(format!("Paid online: ${:.2}", booking.amount_paid));
+        }
+        lines.push(format!("Due at check-in: ${:.2}", (quote.amount_due - booking.amount_paid).max(0.0)));
+        html.push_str(&format!("<p>{}</p>", lines.iter().map(|l| escape_html(l)).collect::<Vec<_>>().join("<br>")));
+        text.push_str(&format!("\n{}\n", lines.join("\n")));
+    }
+
+    let waiver_url = format!("{}/waiver", site_url.trim_end_matches('/'));
+    html.push_str(&format!(
+        "<p>Everyone in your party needs a signed waiver. Save time at the launch: \
+         <a href=\"{0}\">{0}</a></p>\
+         <p>We'll email you the day before with river conditions and the forecast.<br>\
+         Questions? Call (608) 515-3456.</p>",
+        escape_html(&waiver_url),
+    ));
+    text.push_str(&format!(
+        "\nEveryone in your party needs a signed waiver. Save time at the launch: {}\n\n\
+         We'll email you the day before with river conditions and the forecast.\nQuestions? Call (608) 515-3456.\n",
+        waiver_url,
+    ));
+
+    (subject, html, text)
+}
+
+fn reminder_email(booking: &Booking, outlook: &Outlook, reschedule_url: Option<&str>, site_url: &str) -> (String, String, String) {
+    let confirmation = booking.confirmation_number.as_deref().unwrap_or_default();
+    let name = booking.customer_name.as_deref().unwrap_or("there");
+    let unsettled = outlook.unsettled(booking);
+    let subject = if unsettled {
+        format!("Conditions update for your {} tomorrow", booking.service.label())
+    } else {
+        format!("See you tomorrow: {} at {}", booking.service.label(), booking.start_time)
+    };
+
+    let mut html = format!(
+        "<p>Hi {},</p>\
+         <p>A reminder that you're booked for <strong>{}</strong> tomorrow, {} at {} (party of {}). \
+         Confirmation number {}.</p>",
+        escape_html(name),
+        escape_html(booking.service.label()),
+        escape_html(&booking.booking_date),
+        escape_html(&booking.start_time),
+        booking.party_size,
+        escape_html(confirmation),
+    );
+    let mut text = format!(
+        "Hi {},\n\nA reminder that you're booked for {} tomorrow, {} at {} (party of {}). Confirmation number {}.\n",
+        name,
+        booking.service.label(),
+        booking.booking_date,
+        booking.start_time,
+        booking.party_size,
+        confirmation,
+    );
+
+    if let Some(line) = shuttle_line(booking, &outlook.shuttle) {
+        html.push_str(&format!("<p>{}</p>", escape_html(&line)));
+        text.push_str(&format!("\n{}\n", line));
+    }
+
+    let status = booking.service.status(&outlook.services);
+    if status.status != ServiceState::Open {
+        html.push_str(&format!(
+            "<p><span style=\"color:{}\">&#9679;</span> <strong>{}:</strong> {}</p>",
+            status.status.color(),
+            escape_html(&status.name),
+            escape_html(&status.message),
+        ));
+        text.push_str(&format!("\n{}: {}\n", status.name, status.message));
+    }
+
+    html.push_str("<h3>Conditions</h3><ul>");
+    text.push_str("\nConditions\n");
+    if booking.service.on_water() {
+        match (&outlook.flow, outlook.river_status(booking)) {
+            (Some(flow), Some(status)) => {
+                let temp = flow.water_temp_f.map(|t| format!(", water {:.0}&deg;F", t)).unwrap_or_default();
+                let trend = flow.trend.map(|t| format!(" and {}", t.label().to_lowercase())).unwrap_or_default();
+                html.push_str(&format!(
+                    "<li><span style=\"color:{}\">&#9679;</span> River flow {:.0} cfs{}{}: {}</li>",
+                    status.color(),
+                    flow.flow_cfs,
+                    trend,
+                    temp,
+                    escape_html(status.message()),
+                ));
+                text.push_str(&format!(
+                    "- River flow {:.0} cfs{}{}: {}\n",
+                    flow.flow_cfs,
+                    trend,
+                    flow.water_temp_f.map(|t| format!(", water {:.0}F", t)).unwrap_or_default(),
+                    status.message(),
+                ));
+            }
+            _ => {
+                html.push_str("<li>River flow is unavailable right now; check the site before you leave.</li>");
+                text.push_str("- River flow is unavailable right now; check the site before you leave.\n");
+            }
+        }
+    }
+
+    let end_hour = booking.end_at.get(11..13).and_then(|h| h.parse::<u32>().ok()).unwrap_or(18);
+    let start_hour = booking.start_at.get(11..13).and_then(|h| h.parse::<u32>().ok()).unwrap_or(9);
+    let forecast = outlook
+        .forecast
+        .as_ref()
+        .and_then(|f| ForecastSummary::from_nws(f, &booking.booking_date, start_hour, end_hour));
+    if let Some(f) = forecast {
+        let line = format!(
+            "{}, {:.0}-{:.0}&deg;{}, {:.0}% chance of rain, wind {}",
+            escape_html(&f.conditions),
+            f.low,
+            f.high,
+            escape_html(&f.unit),
+            f.precip_chance,
+            escape_html(&f.wind),
+        );
+        html.push_str(&format!("<li>Forecast during your trip: {}</li>", line));
+        text.push_str(&format!(
+            "- Forecast during your trip: {}, {:.0}-{:.0}{}, {:.0}% chance of rain, wind {}\n",
+            f.conditions, f.low, f.high, f.unit, f.precip_chance, f.wind,
+        ));
+    }
+    html.push_str(&format!(
+        "<li>Sunrise {} / sunset {}</li></ul>",
+        escape_html(&outlook.sun.sunrise),
+        escape_html(&outlook.sun.sunset),
+    ));
+    text.push_str(&format!("- Sunrise {} / sunset {}\n", outlook.sun.sunrise, outlook.sun.sunset));
+
+    if unsettled {
+        let reason = booking
+            .at_risk_reason
+            .clone()
+            .unwrap_or_else(|| "The river is running higher than usual".to_string());
+        match reschedule_url {
+            Some(url) => {
+                html.push_str(&format!(
+                    "<p><strong>{}.</strong> If you'd rather go another day, you can \
+                     <a href=\"{}\">pick a new date and time</a>.</p>",
+                    escape_html(&reason),
+                    escape_html(url),
+                ));
+                text.push_str(&format!(
+                    "\n{}. If you'd rather go another day, pick a new date and time: {}\n",
+                    reason, url,
+                ));
+            }
+            None => {
+                html.push_str(&format!(
+                    "<p><strong>{}.</strong> Call (608) 515-3456 if you'd rather go another day.</p>",
+                    escape_html(&reason),
+                ));
+                text.push_str(&format!("\n{}. Call (608) 515-3456 if you'd rather go another day.\n", reason));
+            }
+        }
+    }
+
+    let waiver_url = format!("{}/waiver", site_url.trim_end_matches('/'));
+    html.push_str(&format!(
+        "<p>Haven't signed your waiver yet? <a href=\"{0}\">{0}</a><br>Questions? Call (608) 515-3456.</p>",
+        escape_html(&waiver_url),
+    ));
+    text.push_str(&format!(
+        "\nHaven't signed your waiver yet? {}\nQuestions? Call (608) 515-3456.\n",
+        waiver_url,
+    ));
+
+    (subject, html, text)
+}
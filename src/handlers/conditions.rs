@@ -2,7 +2,9 @@
 
 use worker::*;
 use serde::{Serialize, Deserialize};
-use crate::api::{usgs, weather as weather_api};
+use crate::api::weather as weather_api;
+use crate::handlers::flow::current_flow;
+use crate::handlers::settings::shop_timezone;
 use crate::models::*;
 use crate::utils::cache;
 use chrono::Utc;
@@ -19,9 +21,6 @@ pub struct ConditionsResponse {
 }
 
 pub async fn get_all_conditions(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let station_id = ctx.var("USGS_STATION_ID")
-        .map(|v| v.to_string())
-        .unwrap_or_else(|_| "05406000".to_string());
     let lat = ctx.var("LOCATION_LAT")
         .map(|v| v.to_string())
         .unwrap_or_else(|_| "43.2722".to_string());
@@ -41,49 +40,8 @@ pub async fn get_all_conditions(_req: Request, ctx: RouteContext<()>) -> Result<
         }
     }
     
-    // Fetch flow data
-    let flow_data = match usgs::fetch_current_flow(&station_id).await {
-        Ok(usgs_data) => {
-            let mut flow_cfs: Option<f64> = None;
-            let mut water_temp: Option<f64> = None;
-            let mut timestamp = String::new();
-            let mut station_name = String::new();
-            
-            for ts in &usgs_data.value.time_series {
-                station_name = ts.source_info.site_name.clone();
-                
-                if let Some(var_code) = ts.variable.variable_code.first() {
-                    if let Some(values) = ts.values.first() {
-                        if let Some(data_value) = values.value.first() {
-                            let value: f64 = data_value.value.parse().unwrap_or(0.0);
-                            timestamp = data_value.date_time.clone();
-                            
-                            match var_code.value.as_str() {
-                                "00060" => flow_cfs = Some(value),
-                                "00010" => water_temp = Some(value * 9.0 / 5.0 + 32.0),
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-            }
-            
-            flow_cfs.map(|cfs| {
-                let thresholds = FlowThresholds::default();
-                FlowData {
-                    flow_cfs: cfs,
-                    water_temp_f: water_temp,
-                    timestamp,
-                    station_id: station_id.clone(),
-                    station_name,
-                    status: FlowStatus::from_cfs(cfs, &thresholds),
-                    change_4h: None,
-                    change_12h: None,
-                }
-            })
-        }
-        Err(_) => None,
-    };
+    // Fetch flow data (shares the flow endpoint's cache)
+    let flow_data = current_flow(&ctx.env).await.ok();
     
     // Fetch weather data
     let weather_data = if let Ok(api_key) = ctx.secret("TOMORROW_IO_API_KEY") {
@@ -144,7 +102,8 @@ pub async fn get_all_conditions(_req: Request, ctx: RouteContext<()>) -> Result<
     let lon_f: f64 = lon.parse().unwrap_or(-89.7208);
     
     let moon = MoonData::calculate(year, month, day);
-    let sun = SunData::calculate(year, month, day, lat_f, lon_f);
+    let offset = utc_offset_hours(now.date_naive(), shop_timezone(&ctx.env).await);
+    let sun = SunData::calculate(year, month, day, lat_f, lon_f, offset);
     
     // Get services from KV or use defaults
     let services = if let Ok(kv) = ctx.kv("SETTINGS") {
@@ -174,7 +133,7 @@ pub async fn get_all_conditions(_req: Request, ctx: RouteContext<()>) -> Result<
 
 fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
     let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
@@ -107,7 +107,7 @@ pub async fn submit_form(mut req: Request, ctx: RouteContext<()>) -> Result<Resp
 
 fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
     let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
     Ok(Response::ok(json)?.with_headers(headers))
@@ -0,0 +1,359 @@
+//! Rental fleet: unit counts, tracked assets and availability for a time window
+
+use worker::*;
+use serde::Deserialize;
+use crate::handlers::admin::verify_session;
+use crate::handlers::maintenance::open_records;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::auth;
+
+#[derive(Deserialize)]
+struct FleetCountRow {
+    category: FleetCategory,
+    total_units: u32,
+    identified_assets: u32,
+    out_of_service: u32,
+}
+
+#[derive(Deserialize)]
+struct CommittedRow {
+    service: BookingService,
+    committed: u32,
+}
+
+/// GET /api/fleet/availability?date=YYYY-MM-DD&start=HH:MM&end=HH:MM&category=
+/// Defaults to the whole of today.
+pub async fn get_availability(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let url = req.url()?;
+    let mut date = local_now(shop_timezone(&ctx.env).await).date();
+    let mut start = chrono::NaiveTime::MIN;
+    let mut end = chrono::NaiveTime::from_hms_opt(23, 59, 0).unwrap_or(chrono::NaiveTime::MIN);
+    let mut category = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "date" => match parse_date(&value) {
+                Some(d) => date = d,
+                None => return Response::error("Invalid date (YYYY-MM-DD)", 400),
+            },
+            "start" => match parse_time(&value) {
+                Some(t) => start = t,
+                None => return Response::error("Invalid start time (HH:MM)", 400),
+            },
+            "end" => match parse_time(&value) {
+                Some(t) => end = t,
+                None => return Response::error("Invalid end time (HH:MM)", 400),
+            },
+            "category" => category = FleetCategory::from_slug(&value),
+            _ => {}
+        }
+    }
+    if end <= start {
+        return Response::error("End time must be after start time", 400);
+    }
+    let window = TimeWindow { start: date.and_time(start), end: date.and_time(end) };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let fleet = fleet_summaries(&db).await?;
+    let sql = format!(
+        "SELECT service, COALESCE(SUM(quantity), 0) AS committed FROM bookings
+         WHERE start_at < ?2 AND end_at > ?1 AND {}
+         GROUP BY service",
+        committed_booking_predicate("?3")
+    );
+    let committed = query!(
+        &db,
+        &sql,
+        &window.start_at(),
+        &window.end_at(),
+        &chrono::Utc::now().to_rfc3339(),
+    )?
+    .all()
+    .await?
+    .results::<CommittedRow>()?;
+
+    let availability: Vec<FleetAvailability> = fleet
+        .into_iter()
+        .filter(|f| category.is_none_or(|c| c == f.category))
+        .map(|f| {
+            let used = committed
+                .iter()
+                .filter(|row| row.service.fleet_category() == f.category)
+                .map(|row| row.committed)
+                .sum();
+            FleetAvailability {
+                category: f.category,
+                label: f.label,
+                in_service: f.in_service,
+                committed: used,
+                available: f.in_service.saturating_sub(used),
+            }
+        })
+        .collect();
+
+    json_response(&serde_json::json!({
+        "start": window.start_at(),
+        "end": window.end_at(),
+        "fleet": availability,
+    }))
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+/// GET /api/admin/fleet?category= - unit counts and every tracked asset
+pub async fn get_fleet(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let category = url
+        .query_pairs()
+        .find(|(k, _)| k == "category")
+        .and_then(|(_, v)| FleetCategory::from_slug(&v));
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let summaries = fleet_summaries(&db).await?;
+    let assets = match category {
+        Some(c) => query!(&db, "SELECT * FROM fleet_assets WHERE category = ?1 ORDER BY name", &c)?
+            .all()
+            .await?,
+        None => db.prepare("SELECT * FROM fleet_assets ORDER BY category, name")
+            .all()
+            .await?,
+    }
+    .results::<FleetAsset>()?;
+
+    json_response(&serde_json::json!({
+        "categories": summaries,
+        "assets": assets,
+    }))
+}
+
+/// POST /api/admin/fleet/categories/:category - set how many units we own
+pub async fn update_category(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let category = match ctx.param("category").and_then(|c| FleetCategory::from_slug(c)) {
+        Some(c) => c,
+        None => return Response::error("Unknown fleet category", 404),
+    };
+
+    let body: UpdateFleetCategoryRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid fleet data", 400),
+    };
+
+    if body.total_units > 1000 {
+        return Response::error("Unit count must be 1000 or fewer", 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    query!(
+        &db,
+        "INSERT INTO fleet_categories (category, total_units, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT (category) DO UPDATE SET total_units = excluded.total_units, updated_at = excluded.updated_at",
+        &category,
+        &body.total_units,
+        &chrono::Utc::now().to_rfc3339(),
+    )?
+    .run()
+    .await?;
+
+    let summary = fleet_summaries(&db).await?.into_iter().find(|f| f.category == category);
+    json_response(&serde_json::json!({"success": true, "category": summary}))
+}
+
+/// POST /api/admin/fleet/assets - register a numbered unit
+pub async fn create_asset(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let body: CreateFleetAssetRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid asset data", 400),
+    };
+
+    if let Err(message) = body.validate() {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let id = auth::generate_id("asset")?;
+    let now = chrono::Utc::now().to_rfc3339();
+    query!(
+        &db,
+        "INSERT INTO fleet_assets (id, category, name, serial, notes, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+        &id,
+        &body.category,
+        &body.name.trim(),
+        &clean(&body.serial),
+        &clean(&body.notes),
+        &now,
+    )?
+    .run()
+    .await?;
+
+    match find_asset(&db, &id).await? {
+        Some(asset) => json_response(&serde_json::json!({"success": true, "asset": asset})),
+        None => Response::error("Could not save asset", 500),
+    }
+}
+
+/// POST /api/admin/fleet/assets/:id - rename, or take in and out of service
+pub async fn update_asset(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing asset id", 400),
+    };
+
+    let update: UpdateFleetAssetRequest = match req.json().await {
+        Ok(u) => u,
+        Err(_) => return Response::error("Invalid asset update", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let mut asset = match find_asset(&db, &id).await? {
+        Some(a) => a,
+        None => return Response::error("Asset not found", 404),
+    };
+
+    // Open maintenance holds an asset out until the work is signed off
+    if update.out_of_service == Some(false) && asset.out_of_service && open_records(&db, &id).await? > 0 {
+        return Response::error("Close the open maintenance record to return this asset to service", 409);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Some(name) = update.name.as_deref().map(str::trim) {
+        if name.is_empty() || name.len() > 80 {
+            return Response::error("Asset name is required (80 characters max)", 400);
+        }
+        asset.name = name.to_string();
+    }
+    if update.serial.is_some() {
+        asset.serial = clean(&update.serial);
+    }
+    if update.notes.is_some() {
+        asset.notes = clean(&update.notes);
+    }
+    match update.out_of_service {
+        Some(true) if !asset.out_of_service => {
+            asset.out_of_service = true;
+            asset.out_of_service_since = Some(now.clone());
+            asset.out_of_service_reason = clean(&update.out_of_service_reason);
+        }
+        Some(false) => {
+            asset.out_of_service = false;
+            asset.out_of_service_since = None;
+            asset.out_of_service_reason = None;
+        }
+        _ if asset.out_of_service && update.out_of_service_reason.is_some() => {
+            asset.out_of_service_reason = clean(&update.out_of_service_reason);
+        }
+        _ => {}
+    }
+
+    query!(
+        &db,
+        "UPDATE fleet_assets SET name = ?1, serial = ?2, notes = ?3, out_of_service = ?4,
+            out_of_service_reason = ?5, out_of_service_since = ?6, updated_at = ?7,
+            out_for_maintenance = CASE WHEN ?4 = 0 THEN 0 ELSE out_for_maintenance END
+         WHERE id = ?8",
+        &asset.name,
+        &asset.serial,
+        &asset.notes,
+        &(asset.out_of_service as i32),
+        &asset.out_of_service_reason,
+        &asset.out_of_service_since,
+        &now,
+        &id,
+    )?
+    .run()
+    .await?;
+
+    match find_asset(&db, &id).await? {
+        Some(asset) => json_response(&serde_json::json!({"success": true, "asset": asset})),
+        None => Response::error("Asset not found", 404),
+    }
+}
+
+/// Fleet size per category, with zero for categories never configured
+pub(crate) async fn fleet_summaries(db: &D1Database) -> Result<Vec<FleetSummary>> {
+    let rows = db.prepare(
+            "SELECT c.category, c.total_units,
+                    (SELECT COUNT(*) FROM fleet_assets a WHERE a.category = c.category) AS identified_assets,
+                    (SELECT COUNT(*) FROM fleet_assets a WHERE a.category = c.category AND a.out_of_service = 1)
+                        AS out_of_service
+             FROM fleet_categories c",
+        )
+        .all()
+        .await?
+        .results::<FleetCountRow>()?;
+
+    Ok(FleetCategory::ALL
+        .iter()
+        .map(|&category| match rows.iter().find(|r| r.category == category) {
+            Some(r) => FleetSummary::new(category, r.total_units, r.identified_assets, r.out_of_service),
+            None => FleetSummary::new(category, 0, 0, 0),
+        })
+        .collect())
+}
+
+/// Units of a category that can go out on the water or trail
+pub(crate) async fn in_service_units(db: &D1Database, category: FleetCategory) -> Result<u32> {
+    Ok(fleet_summaries(db)
+        .await?
+        .into_iter()
+        .find(|f| f.category == category)
+        .map(|f| f.in_service)
+        .unwrap_or(0))
+}
+
+pub(crate) async fn find_asset(db: &D1Database, id: &str) -> Result<Option<FleetAsset>> {
+    query!(db, "SELECT * FROM fleet_assets WHERE id = ?1", &id)?
+        .first::<FleetAsset>(None)
+        .await
+}
+
+fn clean(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers))
+}
@@ -1,79 +1,170 @@
 //! River flow data handler
 
 use worker::*;
-use crate::api::usgs;
-use crate::models::{FlowData, FlowStatus, FlowThresholds};
+use crate::api::{nwps, usgs};
+use crate::handlers::settings::shop_timezone;
+use crate::models::{
+    usgs_parameters, FlowData, FlowForecast, FlowThresholds, NetworkSummary, RiverNetwork, RiverStation, StationFlow,
+};
 use crate::utils::cache;
 
-pub async fn get_flow_data(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let station_id = ctx.var("USGS_STATION_ID")
+/// GET /api/flow?station= - latest reading for a network station (the primary by default)
+pub async fn get_flow_data(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let url = req.url()?;
+    let requested = url
+        .query_pairs()
+        .find(|(k, _)| k == "station")
+        .map(|(_, v)| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+
+    let result = match requested {
+        Some(id) => {
+            let network = load_network(&ctx.env).await;
+            match network.station(&id) {
+                Some(station) => station_flow(&ctx.env, station).await,
+                None => return Response::error("Unknown station", 404),
+            }
+        }
+        None => current_flow(&ctx.env).await,
+    };
+
+    match result {
+        Ok(flow_data) => json_response(&flow_data),
+        Err(e) => Response::error(format!("Failed to fetch flow data: {}", e), 500),
+    }
+}
+
+/// GET /api/flow/network - every watched station, with warnings for sharp upstream rises
+pub async fn get_network(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let network = load_network(&ctx.env).await;
+
+    let mut stations = vec![];
+    for station in &network.stations {
+        stations.push(match station_flow(&ctx.env, station).await {
+            Ok(flow) => StationFlow {
+                rising_sharply: station.rising_sharply(&flow),
+                station: station.clone(),
+                flow: Some(flow),
+                error: None,
+            },
+            Err(e) => StationFlow {
+                station: station.clone(),
+                flow: None,
+                error: Some(e.to_string()),
+                rising_sharply: false,
+            },
+        });
+    }
+
+    json_response(&NetworkSummary::build(&network, stations, chrono::Utc::now().to_rfc3339()))
+}
+
+/// GET /api/flow/forecast - NWS river forecast for the primary gauge, rated per day
+pub async fn get_forecast(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let network = load_network(&ctx.env).await;
+    let primary = network.primary();
+    // NWPS takes the NWS location id or the USGS site number
+    let gauge_id = ctx.var("NWPS_GAUGE_ID")
         .map(|v| v.to_string())
-        .unwrap_or_else(|_| "05406000".to_string());
-    
-    // Try to get from cache first
-    let cache_key = format!("flow:{}", station_id);
-    
+        .ok()
+        .or_else(|| primary.map(|p| p.id.clone()))
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    let gauge_id = match gauge_id {
+        Some(id) => id,
+        None => return Response::error("River forecast is not configured", 503),
+    };
+    let saved = load_flow_thresholds(&ctx.env).await;
+    let thresholds = primary.and_then(|p| p.flow_thresholds(&saved)).unwrap_or(saved);
+
+    let cache_key = format!("flow-forecast:{}", gauge_id);
     if let Ok(kv) = ctx.kv("CACHE") {
-        if let Some(cached) = cache::get_cached::<FlowData>(&kv, &cache_key).await {
+        if let Some(cached) = cache::get_cached::<FlowForecast>(&kv, &cache_key).await {
             return json_response(&cached);
         }
     }
-    
-    // Fetch fresh data
-    match usgs::fetch_current_flow(&station_id).await {
-        Ok(usgs_data) => {
-            let mut flow_cfs: Option<f64> = None;
-            let mut water_temp: Option<f64> = None;
-            let mut timestamp = String::new();
-            let mut station_name = String::new();
-            
-            for ts in &usgs_data.value.time_series {
-                station_name = ts.source_info.site_name.clone();
-                
-                if let Some(var_code) = ts.variable.variable_code.first() {
-                    if let Some(values) = ts.values.first() {
-                        if let Some(data_value) = values.value.first() {
-                            let value: f64 = data_value.value.parse().unwrap_or(0.0);
-                            timestamp = data_value.date_time.clone();
-                            
-                            match var_code.value.as_str() {
-                                "00060" => flow_cfs = Some(value), // Discharge
-                                "00010" => water_temp = Some(value * 9.0 / 5.0 + 32.0), // Temp C to F
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-            }
-            
-            let cfs = flow_cfs.unwrap_or(0.0);
-            let thresholds = FlowThresholds::default();
-            
-            let flow_data = FlowData {
-                flow_cfs: cfs,
-                water_temp_f: water_temp,
-                timestamp,
-                station_id: station_id.clone(),
-                station_name,
-                status: FlowStatus::from_cfs(cfs, &thresholds),
-                change_4h: None,
-                change_12h: None,
-            };
-            
-            // Cache for 5 minutes
-            if let Ok(kv) = ctx.kv("CACHE") {
-                let _ = cache::set_cached(&kv, &cache_key, &flow_data, 300).await;
-            }
-            
-            json_response(&flow_data)
+
+    let forecast = match nwps::fetch_stageflow_forecast(&gauge_id).await {
+        Ok(body) => match FlowForecast::parse(&gauge_id, &body, &thresholds, shop_timezone(&ctx.env).await) {
+            Ok(f) => f,
+            Err(e) => return Response::error(e, 502),
+        },
+        Err(e) => return Response::error(format!("Failed to fetch river forecast: {}", e), 502),
+    };
+
+    // Forecasts are issued a few times a day
+    if let Ok(kv) = ctx.kv("CACHE") {
+        let _ = cache::set_cached(&kv, &cache_key, &forecast, 1800).await;
+    }
+
+    json_response(&forecast)
+}
+
+/// Latest reading for the primary station, cached for 5 minutes
+pub(crate) async fn current_flow(env: &Env) -> Result<FlowData> {
+    let network = load_network(env).await;
+    match network.primary() {
+        Some(primary) => station_flow(env, primary).await,
+        None => Err(Error::from("No primary station configured")),
+    }
+}
+
+/// Latest reading for one station, cached for 5 minutes
+pub(crate) async fn station_flow(env: &Env, station: &RiverStation) -> Result<FlowData> {
+    // Try to get from cache first
+    let cache_key = format!("flow:{}", station.id);
+
+    if let Ok(kv) = env.kv("CACHE") {
+        if let Some(cached) = cache::get_cached::<FlowData>(&kv, &cache_key).await {
+            return Ok(cached);
         }
-        Err(e) => Response::error(format!("Failed to fetch flow data: {}", e), 500),
     }
+
+    // Fetch fresh data
+    let parameters = usgs_parameters(env.var("USGS_PARAMETERS").map(|v| v.to_string()).ok().as_deref());
+    let usgs_data = usgs::fetch_current_flow(&station.id, &parameters).await?;
+    let thresholds = station.flow_thresholds(&load_flow_thresholds(env).await);
+    // The last reading outlives the 5 minute cache so statuses can hold through the hysteresis band
+    let previous_key = format!("flow-previous:{}", station.id);
+    let previous = match env.kv("CACHE") {
+        Ok(kv) => cache::get_cached::<FlowData>(&kv, &previous_key).await,
+        Err(_) => None,
+    };
+    let flow_data = FlowData::from_usgs(&usgs_data, &station.id, thresholds.as_ref(), previous.as_ref())
+        .ok_or_else(|| Error::from("No discharge reading from USGS"))?;
+
+    // Cache for 5 minutes
+    if let Ok(kv) = env.kv("CACHE") {
+        let _ = cache::set_cached(&kv, &cache_key, &flow_data, 300).await;
+        let _ = cache::set_cached(&kv, &previous_key, &flow_data, 21600).await;
+    }
+
+    Ok(flow_data)
+}
+
+/// Limits saved in admin (SETTINGS KV `flow_thresholds`), or the defaults
+pub(crate) async fn load_flow_thresholds(env: &Env) -> FlowThresholds {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<FlowThresholds>(&kv, "flow_thresholds").await.unwrap_or_default(),
+        Err(_) => FlowThresholds::default(),
+    }
+}
+
+/// Stations from SETTINGS KV `river_network`, or just USGS_STATION_ID
+pub(crate) async fn load_network(env: &Env) -> RiverNetwork {
+    let station_id = env.var("USGS_STATION_ID")
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| "05406000".to_string());
+    let network = match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<RiverNetwork>(&kv, "river_network").await.unwrap_or_default(),
+        Err(_) => RiverNetwork::default(),
+    };
+    network.or_primary(&station_id)
 }
 
 fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
     let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
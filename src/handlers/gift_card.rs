@@ -0,0 +1,603 @@
+//! Gift cards: public balance check, admin issue/void/adjust, walk-up redemption and the ledger
+
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use worker::*;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::settings::shop_timezone;
+use crate::handlers::waiver::rate_limited;
+use crate::models::*;
+use crate::utils::auth;
+
+#[derive(Deserialize)]
+struct BalanceRow {
+    balance_cents: i64,
+}
+
+#[derive(Deserialize)]
+struct LedgerTotalRow {
+    entry_type: LedgerEntryType,
+    entries: u32,
+    amount_cents: i64,
+}
+
+/// One balance change to record against a card
+pub(crate) struct LedgerChange<'a> {
+    pub entry_type: LedgerEntryType,
+    /// Negative for spending
+    pub amount_cents: i64,
+    pub booking_id: Option<&'a str>,
+    pub reference: Option<&'a str>,
+    pub note: Option<&'a str>,
+    pub created_by: &'a str,
+}
+
+/// POST /api/gift-cards/balance - look up a card's remaining balance
+pub async fn check_balance(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let not_found = |message: &str, status: u16| json_response(&GiftCardBalanceResponse {
+        found: false,
+        message: message.to_string(),
+        last4: None,
+        balance: None,
+        expires_on: None,
+    }, status);
+
+    // Codes are unguessable, but don't let anyone try
+    if rate_limited(&ctx, &format!("rate:gift-balance:{}", client_ip), 10).await {
+        return not_found("Too many attempts. Please try again later.", 429);
+    }
+
+    let body: GiftCardBalanceRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid balance request", 400),
+    };
+
+    let code = match normalize_gift_card_code(&body.code) {
+        Some(c) => c,
+        None => return not_found("We couldn't find that gift card. Check the code and try again.", 200),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    let card = match find_card_by_code(&db, &code).await? {
+        Some(c) => c,
+        None => return not_found("We couldn't find that gift card. Check the code and try again.", 200),
+    };
+
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    json_response(&GiftCardBalanceResponse {
+        found: true,
+        message: match card.unusable(today) {
+            Some(reason) => reason.to_string(),
+            None => format!("Balance: ${:.2}", from_cents(card.balance_cents)),
+        },
+        balance: Some(if card.status == GiftCardStatus::Void { 0.0 } else { from_cents(card.balance_cents) }),
+        last4: Some(card.last4),
+        expires_on: card.expires_on,
+    }, 200)
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+/// GET /api/admin/gift-cards?q=&status= - q matches a full code, the last four, a name or an email
+pub async fn list_gift_cards(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let mut search = None;
+    let mut status = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "q" if !value.trim().is_empty() => search = Some(value.trim().to_string()),
+            "status" if value == "active" || value == "void" => status = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut params: Vec<String> = Vec::new();
+    if let Some(q) = &search {
+        match normalize_gift_card_code(q) {
+            Some(code) => {
+                params.push(auth::sha256_hex(code.as_bytes()));
+                conditions.push(format!("code_hash = ?{}", params.len()));
+            }
+            None => {
+                params.push(q.to_uppercase());
+                let last4 = params.len();
+                params.push(format!("%{}%", q.to_lowercase()));
+                let like = params.len();
+                conditions.push(format!(
+                    "(last4 = ?{0} OR lower(recipient_name) LIKE ?{1} OR lower(recipient_email) LIKE ?{1}
+                      OR lower(purchaser_name) LIKE ?{1} OR lower(purchaser_email) LIKE ?{1})",
+                    last4, like
+                ));
+            }
+        }
+    }
+    if let Some(s) = status {
+        params.push(s);
+        conditions.push(format!("status = ?{}", params.len()));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let bindings: Vec<JsValue> = params.iter().map(|p| JsValue::from(p.as_str())).collect();
+    let cards = db.prepare(format!(
+            "SELECT * FROM gift_cards {} ORDER BY issued_at DESC LIMIT 200",
+            where_clause
+        ))
+        .bind(&bindings)?
+        .all()
+        .await?
+        .results::<GiftCard>()?;
+
+    json_response(&serde_json::json!({ "gift_cards": cards }), 200)
+}
+
+/// POST /api/admin/gift-cards - issue a card; the full code is only ever returned here
+pub async fn issue_gift_card(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let body: IssueGiftCardRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid gift card data", 400),
+    };
+
+    if let Err(message) = body.validate(local_now(shop_timezone(&ctx.env).await).date()) {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    let clean = |v: &Option<String>| v.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+    let email = |v: &Option<String>| clean(v).map(|e| e.to_lowercase());
+    let cents = to_cents(body.amount);
+    let now = chrono::Utc::now().to_rfc3339();
+
+    // Retry on the (vanishingly unlikely) code collision
+    for _ in 0..3 {
+        let id = auth::generate_id("gc")?;
+        let code = auth::generate_code(GROUP_CODE_ALPHABET, GIFT_CARD_CODE_LENGTH)?;
+        let reference = clean(&body.reference);
+        let statements = vec![
+            query!(
+                &db,
+                "INSERT INTO gift_cards
+                    (id, code_hash, last4, initial_cents, balance_cents, status, recipient_name, recipient_email,
+                     purchaser_name, purchaser_email, message, expires_on, issued_by, issued_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4, 'active', ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                &id,
+                &auth::sha256_hex(code.as_bytes()),
+                &code[GIFT_CARD_CODE_LENGTH - 4..],
+                &cents,
+                &clean(&body.recipient_name),
+                &email(&body.recipient_email),
+                &clean(&body.purchaser_name),
+                &email(&body.purchaser_email),
+                &clean(&body.message),
+                &clean(&body.expires_on),
+                &session.username,
+                &now,
+            )?,
+            entry_statement(&db, &id, LedgerChange {
+                entry_type: LedgerEntryType::Issue,
+                amount_cents: cents,
+                booking_id: None,
+                reference: reference.as_deref(),
+                note: None,
+                created_by: &session.username,
+            })?,
+        ];
+
+        match db.batch(statements).await {
+            Ok(_) => {}
+            Err(e) if e.to_string().contains("UNIQUE") => continue,
+            Err(e) => return Err(e),
+        }
+
+        return match find_card(&db, &id).await? {
+            Some(card) => json_response(&serde_json::json!({
+                "success": true,
+                "code": format_gift_card_code(&code),
+                "gift_card": card,
+            }), 200),
+            None => Response::error("Could not issue gift card", 500),
+        };
+    }
+
+    Response::error("Could not issue gift card. Please try again.", 500)
+}
+
+/// GET /api/admin/gift-cards/:id - the card and its full ledger
+pub async fn get_gift_card(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing gift card id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    let card = match find_card(&db, &id).await? {
+        Some(c) => c,
+        None => return Response::error("Gift card not found", 404),
+    };
+
+    let ledger = query!(
+        &db,
+        "SELECT * FROM gift_card_ledger WHERE card_id = ?1 ORDER BY created_at, rowid",
+        &id,
+    )?
+    .all()
+    .await?
+    .results::<GiftCardLedgerEntry>()?;
+
+    // The stored balance should always equal the sum of the ledger
+    let ledger_cents: i64 = ledger.iter().map(|e| e.amount_cents).sum();
+
+    json_response(&serde_json::json!({
+        "gift_card": card,
+        "ledger": ledger,
+        "ledger_balance_cents": ledger_cents,
+        "balanced": ledger_cents == card.balance_cents,
+    }), 200)
+}
+
+/// POST /api/admin/gift-cards/:id/adjust - add or remove value with a note
+pub async fn adjust_gift_card(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing gift card id", 400),
+    };
+
+    let body: AdjustGiftCardRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid adjustment", 400),
+    };
+
+    let cents = to_cents(body.amount);
+    if cents == 0 || cents.abs() > MAX_GIFT_CARD_CENTS {
+        return Response::error(format!("Adjustments must be between $0.01 and ${}", MAX_GIFT_CARD_CENTS / 100), 400);
+    }
+    let note = body.note.trim();
+    if note.is_empty() {
+        return Response::error("Please give a reason for the adjustment", 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    match find_card(&db, &id).await? {
+        Some(c) if c.status == GiftCardStatus::Void => return Response::error("This gift card has been voided", 409),
+        Some(_) => {}
+        None => return Response::error("Gift card not found", 404),
+    }
+
+    let entry = record(&db, &id, LedgerChange {
+        entry_type: LedgerEntryType::Adjust,
+        amount_cents: cents,
+        booking_id: None,
+        reference: None,
+        note: Some(note),
+        created_by: &session.username,
+    }).await?;
+
+    match entry {
+        Some(entry) => json_response(&serde_json::json!({"success": true, "entry": entry}), 200),
+        None => Response::error("The card's balance is lower than that adjustment", 409),
+    }
+}
+
+/// POST /api/admin/gift-cards/:id/void - cancel the card and write off its balance
+pub async fn void_gift_card(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing gift card id", 400),
+    };
+
+    let body: VoidGiftCardRequest = req.json().await.unwrap_or_default();
+    let reason = body.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    let card = match find_card(&db, &id).await? {
+        Some(c) if c.status == GiftCardStatus::Void => return Response::error("This gift card is already void", 409),
+        Some(c) => c,
+        None => return Response::error("Gift card not found", 404),
+    };
+
+    // Only void the balance we read, so a sale in between isn't lost from the ledger
+    let now = chrono::Utc::now().to_rfc3339();
+    let statements = vec![
+        query!(
+            &db,
+            "UPDATE gift_cards SET status = 'void', balance_cents = 0, voided_at = ?1, voided_by = ?2, void_reason = ?3
+             WHERE id = ?4 AND status = 'active' AND balance_cents = ?5",
+            &now,
+            &session.username,
+            &reason,
+            &id,
+            &card.balance_cents,
+        )?,
+        entry_statement(&db, &id, LedgerChange {
+            entry_type: LedgerEntryType::Void,
+            amount_cents: -card.balance_cents,
+            booking_id: None,
+            reference: None,
+            note: reason,
+            created_by: &session.username,
+        })?,
+    ];
+    let results = db.batch(statements).await?;
+    let changed = match results.first() {
+        Some(r) => r.meta()?.and_then(|m| m.changes).unwrap_or(0),
+        None => 0,
+    };
+    if changed == 0 {
+        return Response::error("The card changed while voiding. Please try again.", 409);
+    }
+
+    match find_card(&db, &id).await? {
+        Some(card) => json_response(&serde_json::json!({"success": true, "gift_card": card}), 200),
+        None => Response::error("Gift card not found", 404),
+    }
+}
+
+/// POST /api/admin/gift-cards/redeem - spend a card on a walk-up sale at the counter
+pub async fn redeem_walk_up(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let body: RedeemGiftCardRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid redemption", 400),
+    };
+
+    let cents = to_cents(body.amount);
+    if cents <= 0 {
+        return Response::error("Enter the amount to charge to the card", 400);
+    }
+    let code = match normalize_gift_card_code(&body.code) {
+        Some(c) => c,
+        None => return Response::error("Gift card not found", 404),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    let card = match find_card_by_code(&db, &code).await? {
+        Some(c) => c,
+        None => return Response::error("Gift card not found", 404),
+    };
+    if let Some(reason) = card.unusable(local_now(shop_timezone(&ctx.env).await).date()) {
+        return Response::error(reason, 409);
+    }
+
+    let entry = record(&db, &card.id, LedgerChange {
+        entry_type: LedgerEntryType::Redeem,
+        amount_cents: -cents,
+        booking_id: None,
+        reference: body.reference.as_deref().map(str::trim).filter(|r| !r.is_empty()),
+        note: Some("Walk-up sale"),
+        created_by: &session.username,
+    }).await?;
+
+    match entry {
+        Some(entry) => json_response(&serde_json::json!({
+            "success": true,
+            "charged": from_cents(cents),
+            "balance": from_cents(entry.balance_after_cents),
+            "entry": entry,
+        }), 200),
+        None => Response::error(
+            format!("The card only has ${:.2} left", from_cents(card.balance_cents)),
+            409,
+        ),
+    }
+}
+
+/// GET /api/admin/gift-cards/ledger?date_from=&date_to= - every entry in the range (default
+/// last 30 days), totals by type and the outstanding balance across active cards
+pub async fn ledger_report(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    let mut date_from = today - chrono::Duration::days(30);
+    let mut date_to = today;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "date_from" => if let Some(d) = parse_date(&value) { date_from = d; },
+            "date_to" => if let Some(d) = parse_date(&value) { date_to = d; },
+            _ => {}
+        }
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Gift card storage not available", 500),
+    };
+
+    // created_at is RFC 3339, so compare the date prefix
+    let from = date_from.format("%Y-%m-%d").to_string();
+    let to = date_to.format("%Y-%m-%d").to_string();
+    let entries = query!(
+        &db,
+        "SELECT * FROM gift_card_ledger
+         WHERE substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2
+         ORDER BY created_at DESC, rowid DESC",
+        &from,
+        &to,
+    )?
+    .all()
+    .await?
+    .results::<GiftCardLedgerEntry>()?;
+
+    let totals = query!(
+        &db,
+        "SELECT entry_type, COUNT(*) AS entries, COALESCE(SUM(amount_cents), 0) AS amount_cents
+         FROM gift_card_ledger
+         WHERE substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2
+         GROUP BY entry_type",
+        &from,
+        &to,
+    )?
+    .all()
+    .await?
+    .results::<LedgerTotalRow>()?;
+
+    let outstanding = query!(
+        &db,
+        "SELECT COALESCE(SUM(balance_cents), 0) AS balance_cents FROM gift_cards WHERE status = 'active'",
+    )?
+    .first::<BalanceRow>(None)
+    .await?
+    .map(|r| r.balance_cents)
+    .unwrap_or(0);
+
+    let totals: Vec<_> = totals
+        .iter()
+        .map(|t| serde_json::json!({
+            "entry_type": t.entry_type,
+            "entries": t.entries,
+            "amount": from_cents(t.amount_cents),
+        }))
+        .collect();
+
+    json_response(&serde_json::json!({
+        "date_from": from,
+        "date_to": to,
+        "outstanding_balance": from_cents(outstanding),
+        "totals": totals,
+        "entries": entries,
+    }), 200)
+}
+
+pub(crate) async fn find_card(db: &D1Database, id: &str) -> Result<Option<GiftCard>> {
+    query!(db, "SELECT * FROM gift_cards WHERE id = ?1", &id)?
+        .first::<GiftCard>(None)
+        .await
+}
+
+/// Cards are looked up by the hash of their normalized code
+pub(crate) async fn find_card_by_code(db: &D1Database, code: &str) -> Result<Option<GiftCard>> {
+    query!(
+        db,
+        "SELECT * FROM gift_cards WHERE code_hash = ?1",
+        &auth::sha256_hex(code.as_bytes()),
+    )?
+    .first::<GiftCard>(None)
+    .await
+}
+
+/// Move a card's balance and write the ledger entry in one batch. Every change re-checks the
+/// status in the same statement, so a voided card never takes credit, and spending re-checks
+/// the balance so two sales can't overdraw a card. Returns None when the card is voided or
+/// can't cover the change.
+pub(crate) async fn record(
+    db: &D1Database,
+    card_id: &str,
+    change: LedgerChange<'_>,
+) -> Result<Option<GiftCardLedgerEntry>> {
+    let statements = vec![
+        query!(
+            db,
+            "UPDATE gift_cards SET balance_cents = balance_cents + ?2
+             WHERE id = ?1 AND status = 'active' AND (?2 >= 0 OR balance_cents + ?2 >= 0)",
+            &card_id,
+            &change.amount_cents,
+        )?,
+        entry_statement(db, card_id, change)?,
+    ];
+    let results = db.batch(statements).await?;
+    match results.get(1) {
+        Some(r) => Ok(r.results::<GiftCardLedgerEntry>()?.into_iter().next()),
+        None => Ok(None),
+    }
+}
+
+/// Ledger entry for the card change batched just before it. It is only written when that
+/// statement changed the card, and takes balance_after_cents from the card as it now stands.
+fn entry_statement(db: &D1Database, card_id: &str, change: LedgerChange<'_>) -> Result<D1PreparedStatement> {
+    query!(
+        db,
+        "INSERT INTO gift_card_ledger
+            (id, card_id, entry_type, amount_cents, balance_after_cents, booking_id, reference, note, created_by, created_at)
+         SELECT ?1, id, ?3, ?4, balance_cents, ?5, ?6, ?7, ?8, ?9
+         FROM gift_cards WHERE id = ?2 AND changes() = 1
+         RETURNING *",
+        &auth::generate_id("gcl")?,
+        &card_id,
+        &change.entry_type,
+        &change.amount_cents,
+        &change.booking_id,
+        &change.reference,
+        &change.note,
+        &change.created_by,
+        &chrono::Utc::now().to_rfc3339(),
+    )
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
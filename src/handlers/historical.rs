@@ -3,7 +3,6 @@
 use worker::*;
 use chrono::{Utc, Duration};
 use crate::api::usgs;
-use crate::models::FlowData;
 
 pub async fn get_period_data(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
     let period = ctx.param("period").map(|s| s.as_str()).unwrap_or("yesterday");
@@ -63,7 +62,7 @@ pub async fn get_period_data(_req: Request, ctx: RouteContext<()>) -> Result<Res
             });
             
             let json = serde_json::to_string(&response).map_err(|e| Error::from(e.to_string()))?;
-            let mut headers = Headers::new();
+            let headers = Headers::new();
             headers.set("Content-Type", "application/json")?;
             headers.set("Cache-Control", "public, max-age=3600")?;
             headers.set("Access-Control-Allow-Origin", "*")?;
@@ -0,0 +1,353 @@
+//! Asset maintenance log: open and close work records, and the service-due report
+
+use worker::*;
+use serde::Deserialize;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::fleet::find_asset;
+use crate::handlers::store::load_repairs;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::{auth, cache};
+
+#[derive(Deserialize)]
+struct OpenRow {
+    open_records: u32,
+}
+
+/// GET /api/admin/fleet/assets/:id/maintenance - an asset and its log, newest first
+pub async fn asset_log(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing asset id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let asset = match find_asset(&db, &id).await? {
+        Some(a) => a,
+        None => return Response::error("Asset not found", 404),
+    };
+
+    let records = query!(
+        &db,
+        "SELECT * FROM asset_maintenance WHERE asset_id = ?1 ORDER BY opened_on DESC, created_at DESC",
+        &id,
+    )?
+    .all()
+    .await?
+    .results::<MaintenanceRecord>()?;
+
+    let catalog = load_repairs(&ctx.env).await;
+    let total_cost: f64 = records.iter().map(|r| r.cost).sum();
+    json_response(&serde_json::json!({
+        "asset": asset,
+        "total_cost": round_cents(total_cost),
+        "records": records.into_iter().map(|r| MaintenanceEntry::new(r, &catalog)).collect::<Vec<_>>(),
+    }))
+}
+
+/// POST /api/admin/fleet/assets/:id/maintenance - open a record (taking the asset out of
+/// service) or log work already finished
+pub async fn create_record(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let asset_id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing asset id", 400),
+    };
+
+    let body: CreateMaintenanceRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid maintenance record", 400),
+    };
+
+    let catalog = load_repairs(&ctx.env).await;
+    if let Err(message) = body.validate(&catalog) {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    if find_asset(&db, &asset_id).await?.is_none() {
+        return Response::error("Asset not found", 404);
+    }
+
+    let today = local_now(shop_timezone(&ctx.env).await).date().format("%Y-%m-%d").to_string();
+    let date = body.date.as_deref().and_then(parse_date).map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or(today);
+    let id = auth::generate_id("mnt")?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let work_done = body.work_done.trim().to_string();
+
+    let mut statements = vec![query!(
+        &db,
+        "INSERT INTO asset_maintenance (id, asset_id, status, opened_on, completed_on, work_done, repair_service_ids,
+            parts, cost, mechanic, scheduled_service, created_by, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?13)",
+        &id,
+        &asset_id,
+        &if body.completed { MaintenanceStatus::Completed } else { MaintenanceStatus::Open },
+        &date,
+        &body.completed.then(|| date.clone()),
+        &work_done,
+        &service_ids_json(&body.repair_service_ids),
+        &clean(&body.parts),
+        &body.cost.map(round_cents).unwrap_or(0.0),
+        &clean(&body.mechanic),
+        &(body.scheduled_service as i32),
+        &session.username,
+        &now,
+    )?];
+    if !body.completed {
+        // An asset already pulled by staff keeps their reason; the log only claims
+        // assets it takes out itself
+        statements.push(query!(
+            &db,
+            "UPDATE fleet_assets SET
+                out_for_maintenance = CASE WHEN out_of_service = 0 THEN 1 ELSE out_for_maintenance END,
+                out_of_service_reason = CASE WHEN out_of_service = 0 THEN ?1 ELSE out_of_service_reason END,
+                out_of_service_since = CASE WHEN out_of_service = 0 THEN ?2 ELSE out_of_service_since END,
+                out_of_service = 1, updated_at = ?2
+             WHERE id = ?3",
+            &format!("Maintenance: {}", truncate(&work_done, 120)),
+            &now,
+            &asset_id,
+        )?);
+    }
+    db.batch(statements).await?;
+
+    record_response(&db, &ctx.env, &id).await
+}
+
+/// GET /api/admin/maintenance?status=open|completed - the shop queue (open by default)
+pub async fn list_records(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let status = match url.query_pairs().find(|(k, _)| k == "status").map(|(_, v)| v.to_string()).as_deref() {
+        None | Some("open") => MaintenanceStatus::Open,
+        Some("completed") => MaintenanceStatus::Completed,
+        Some(_) => return Response::error("Status must be open or completed", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let records = query!(
+        &db,
+        "SELECT * FROM asset_maintenance WHERE status = ?1 ORDER BY opened_on DESC, created_at DESC LIMIT 200",
+        &status,
+    )?
+    .all()
+    .await?
+    .results::<MaintenanceRecord>()?;
+
+    let catalog = load_repairs(&ctx.env).await;
+    json_response(&serde_json::json!({
+        "status": status,
+        "records": records.into_iter().map(|r| MaintenanceEntry::new(r, &catalog)).collect::<Vec<_>>(),
+    }))
+}
+
+/// POST /api/admin/maintenance/:id - edit a record; `complete` closes it and returns the
+/// asset to service once nothing else is open on it
+pub async fn update_record(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing maintenance id", 400),
+    };
+
+    let update: UpdateMaintenanceRequest = match req.json().await {
+        Ok(u) => u,
+        Err(_) => return Response::error("Invalid maintenance update", 400),
+    };
+
+    let catalog = load_repairs(&ctx.env).await;
+    if let Err(message) = update.validate(&catalog) {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let record = match find_record(&db, &id).await? {
+        Some(r) => r,
+        None => return Response::error("Maintenance record not found", 404),
+    };
+    if update.complete && record.status == MaintenanceStatus::Completed {
+        return Response::error("This record is already completed", 409);
+    }
+
+    let (status, completed_on) = if update.complete {
+        let today = local_now(shop_timezone(&ctx.env).await).date().format("%Y-%m-%d").to_string();
+        let day = update.completed_on.as_deref().and_then(parse_date).map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or(today);
+        if day < record.opened_on {
+            return Response::error("Completion date is before the record was opened", 400);
+        }
+        (MaintenanceStatus::Completed, Some(day))
+    } else {
+        (record.status, record.completed_on.clone())
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let pick = |new: &Option<String>, old: &Option<String>| match new {
+        Some(_) => clean(new),
+        None => old.clone(),
+    };
+    let mut statements = vec![query!(
+        &db,
+        "UPDATE asset_maintenance SET work_done = ?1, repair_service_ids = ?2, parts = ?3, cost = ?4, mechanic = ?5,
+            scheduled_service = ?6, status = ?7, completed_on = ?8, updated_at = ?9
+         WHERE id = ?10",
+        &update.work_done.as_deref().map(str::trim).unwrap_or(&record.work_done),
+        &match &update.repair_service_ids {
+            Some(ids) => service_ids_json(ids),
+            None => record.repair_service_ids.clone(),
+        },
+        &pick(&update.parts, &record.parts),
+        &update.cost.map(round_cents).unwrap_or(record.cost),
+        &pick(&update.mechanic, &record.mechanic),
+        &(update.scheduled_service.unwrap_or(record.scheduled_service) as i32),
+        &status,
+        &completed_on,
+        &now,
+        &id,
+    )?];
+    if update.complete {
+        statements.push(query!(
+            &db,
+            "UPDATE fleet_assets SET out_of_service = 0, out_of_service_reason = NULL, out_of_service_since = NULL,
+                out_for_maintenance = 0, updated_at = ?1
+             WHERE id = ?2 AND out_for_maintenance = 1
+               AND NOT EXISTS (SELECT 1 FROM asset_maintenance WHERE asset_id = ?2 AND status = 'open')",
+            &now,
+            &record.asset_id,
+        )?);
+    }
+    db.batch(statements).await?;
+
+    record_response(&db, &ctx.env, &id).await
+}
+
+/// GET /api/admin/maintenance/overdue - assets past (or within a week of) their service interval
+pub async fn overdue_report(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let rows = db.prepare(
+            "SELECT s.*,
+                    (SELECT COUNT(*) FROM rental_assets ra JOIN rentals r ON r.id = ra.rental_id
+                     WHERE ra.asset_id = s.id AND r.checked_out_at >= s.since) AS rentals_since
+             FROM (
+                SELECT a.id, a.name, a.category, a.out_of_service, m.last_service_on,
+                       COALESCE(m.last_service_on, substr(a.created_at, 1, 10)) AS since,
+                       (SELECT COUNT(*) FROM asset_maintenance o WHERE o.asset_id = a.id AND o.status = 'open')
+                           AS open_records
+                FROM fleet_assets a
+                LEFT JOIN (SELECT asset_id, MAX(completed_on) AS last_service_on FROM asset_maintenance
+                           WHERE status = 'completed' AND scheduled_service = 1 GROUP BY asset_id) m
+                       ON m.asset_id = a.id
+             ) s",
+        )
+        .all()
+        .await?
+        .results::<AssetServiceRow>()?;
+
+    let intervals = load_service_intervals(&ctx.env).await;
+    json_response(&ServiceReport::build(&rows, &intervals, local_now(shop_timezone(&ctx.env).await).date()))
+}
+
+/// Open maintenance records on an asset
+pub(crate) async fn open_records(db: &D1Database, asset_id: &str) -> Result<u32> {
+    Ok(query!(
+        db,
+        "SELECT COUNT(*) AS open_records FROM asset_maintenance WHERE asset_id = ?1 AND status = 'open'",
+        &asset_id,
+    )?
+    .first::<OpenRow>(None)
+    .await?
+    .map(|r| r.open_records)
+    .unwrap_or(0))
+}
+
+pub(crate) async fn load_service_intervals(env: &Env) -> ServiceIntervals {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<ServiceIntervals>(&kv, "service_intervals").await.unwrap_or_default(),
+        Err(_) => ServiceIntervals::default(),
+    }
+}
+
+async fn find_record(db: &D1Database, id: &str) -> Result<Option<MaintenanceRecord>> {
+    query!(db, "SELECT * FROM asset_maintenance WHERE id = ?1", &id)?
+        .first::<MaintenanceRecord>(None)
+        .await
+}
+
+async fn record_response(db: &D1Database, env: &Env, id: &str) -> Result<Response> {
+    let record = match find_record(db, id).await? {
+        Some(r) => r,
+        None => return Response::error("Could not save maintenance record", 500),
+    };
+    let asset = find_asset(db, &record.asset_id).await?;
+    json_response(&serde_json::json!({
+        "success": true,
+        "record": MaintenanceEntry::new(record, &load_repairs(env).await),
+        "asset": asset,
+    }))
+}
+
+fn service_ids_json(ids: &[String]) -> Option<String> {
+    if ids.is_empty() {
+        None
+    } else {
+        serde_json::to_string(ids).ok()
+    }
+}
+
+fn truncate(value: &str, max: usize) -> String {
+    match value.char_indices().nth(max) {
+        Some((i, _)) => format!("{}...", &value[..i]),
+        None => value.to_string(),
+    }
+}
+
+fn clean(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers))
+}
@@ -12,3 +12,19 @@ pub mod store;
 pub mod settings;
 pub mod admin;
 pub mod contact;
+pub mod waiver;
+pub mod waiver_group;
+pub mod waiver_pdf;
+pub mod waiver_text;
+pub mod booking;
+pub mod booking_email;
+pub mod fleet;
+pub mod pricing;
+pub mod promo;
+pub mod gift_card;
+pub mod payment;
+pub mod shuttle;
+pub mod rental;
+pub mod maintenance;
+pub mod work_order;
+pub mod water_safety;
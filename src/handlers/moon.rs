@@ -2,7 +2,8 @@
 
 use worker::*;
 use chrono::Utc;
-use crate::models::{MoonData, SunData};
+use crate::handlers::settings::shop_timezone;
+use crate::models::{utc_offset_hours, MoonData, SunData};
 
 #[derive(serde::Serialize)]
 struct MoonResponse {
@@ -24,12 +25,13 @@ pub async fn get_moon_phase(_req: Request, ctx: RouteContext<()>) -> Result<Resp
     let day = now.format("%d").to_string().parse().unwrap_or(1);
     
     let moon = MoonData::calculate(year, month, day);
-    let sun = SunData::calculate(year, month, day, lat, lon);
+    let offset = utc_offset_hours(now.date_naive(), shop_timezone(&ctx.env).await);
+    let sun = SunData::calculate(year, month, day, lat, lon, offset);
     
     let response = MoonResponse { moon, sun };
     
     let json = serde_json::to_string(&response).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=3600")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
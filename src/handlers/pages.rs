@@ -3,7 +3,7 @@
 use worker::*;
 
 fn html_response(content: &str) -> Result<Response> {
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "text/html; charset=utf-8")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     Ok(Response::ok(content)?.with_headers(headers))
@@ -41,6 +41,14 @@ pub async fn waiver(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
     html_response(include_str!("../../static/waiver.html"))
 }
 
+pub async fn book(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
+    html_response(include_str!("../../static/book.html"))
+}
+
+pub async fn repairs(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
+    html_response(include_str!("../../static/repairs.html"))
+}
+
 pub async fn how_it_works(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
     html_response(include_str!("../../static/howitworks.html"))
 }
@@ -0,0 +1,378 @@
+//! Booking payments: card intents for deposits and prepayment, and the processor webhook
+
+use serde::Deserialize;
+use worker::*;
+use crate::api::payments::{payment_provider, IntentRequest, PaymentProvider};
+use crate::handlers::admin::verify_session;
+use crate::handlers::booking::find_booking;
+use crate::handlers::pricing::load_price_list;
+use crate::handlers::waiver::rate_limited;
+use crate::models::*;
+use crate::utils::auth;
+
+const CURRENCY: &str = "usd";
+/// An intent left open this long is taken as abandoned and canceled when the customer
+/// starts a different payment
+const PENDING_PAYMENT_TTL_MINUTES: i64 = 60;
+
+#[derive(Deserialize)]
+struct PaidRow {
+    paid_cents: i64,
+}
+
+#[derive(Deserialize)]
+struct PendingRow {
+    id: String,
+    intent_id: String,
+    kind: PaymentKind,
+    amount_cents: i64,
+    created_at: String,
+}
+
+#[derive(Deserialize)]
+struct CountRow {
+    total: u32,
+}
+
+#[derive(Deserialize)]
+struct IdRow {
+    id: String,
+}
+
+/// POST /api/payments/intent - start a deposit or full payment on a confirmed booking
+pub async fn create_payment(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let failed = |message: &str, status: u16| json_response(&PaymentIntentResponse {
+        success: false,
+        message: message.to_string(),
+        payment_id: None,
+        client_secret: None,
+        publishable_key: None,
+        amount: None,
+    }, status);
+
+    // Confirmation numbers are short; don't let them be walked
+    if rate_limited(&ctx, &format!("rate:payment:{}", client_ip), 20).await {
+        return failed("Too many attempts. Please try again later.", 429);
+    }
+
+    let body: CreatePaymentRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return failed("Invalid payment request", 400),
+    };
+
+    let provider = match payment_provider(&ctx.env) {
+        Some(p) => p,
+        None => return failed("Online payment is not available. Please pay at check-in.", 503),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return failed("Booking storage not available", 500),
+    };
+
+    let booking = query!(
+        &db,
+        "SELECT * FROM bookings WHERE confirmation_number = ?1 AND customer_email = ?2
+           AND status IN ('confirmed', 'at_risk')",
+        &body.confirmation_number.trim().to_uppercase(),
+        &body.email.trim().to_lowercase(),
+    )?
+    .first::<Booking>(None)
+    .await?;
+    let booking = match booking {
+        Some(b) => b,
+        None => return failed("We couldn't find a booking with that confirmation number and email", 404),
+    };
+
+    let prices = load_price_list(&ctx.env).await;
+    let quote = match booking.quote(&prices) {
+        Some(q) => q,
+        None => return failed("This booking can't be paid online. Please pay at check-in.", 400),
+    };
+    let paid = paid_cents(&db, &booking.id).await?;
+    let amount = match payment_amount(&quote, prices.deposit_percent, paid, body.kind) {
+        Ok(a) => a,
+        Err(message) => return failed(&message, 409),
+    };
+
+    // Only one open intent per booking; a deposit and a full payment both left open
+    // could each be paid. The same request again gets its own intent back below.
+    let pending = query!(
+        &db,
+        "SELECT id, intent_id, kind, amount_cents, created_at FROM payments WHERE booking_id = ?1 AND status = 'pending'",
+        &booking.id,
+    )?
+    .all()
+    .await?
+    .results::<PendingRow>()?;
+    let stale_before = chrono::Utc::now() - chrono::Duration::minutes(PENDING_PAYMENT_TTL_MINUTES);
+    for open in &pending {
+        let stale = chrono::DateTime::parse_from_rfc3339(&open.created_at).map_or(true, |at| at < stale_before);
+        if !stale {
+            if open.kind != body.kind || open.amount_cents != amount {
+                return failed("A payment on this booking is already in progress. Please finish it or contact us.", 409);
+            }
+            continue;
+        }
+        // Abandoned: cancel it at the processor so it can't be paid alongside the new one.
+        // This fails if the customer paid it after all, and the webhook will record that.
+        if let Err(e) = provider.cancel_intent(&open.intent_id).await {
+            console_warn!("Could not cancel stale intent {}: {}", open.intent_id, e);
+            return failed("A payment on this booking is already in progress. Please finish it or contact us.", 409);
+        }
+        query!(
+            &db,
+            "UPDATE payments SET status = 'canceled', updated_at = ?1 WHERE id = ?2 AND status = 'pending'",
+            &chrono::Utc::now().to_rfc3339(),
+            &open.id,
+        )?
+        .run()
+        .await?;
+    }
+    // Part of the idempotency key, so a retry after a cancel gets a fresh intent
+    let canceled = query!(
+        &db,
+        "SELECT COUNT(*) AS total FROM payments WHERE booking_id = ?1 AND status = 'canceled'",
+        &booking.id,
+    )?
+    .first::<CountRow>(None)
+    .await?
+    .map(|r| r.total)
+    .unwrap_or(0);
+
+    let intent = provider.create_intent(&IntentRequest {
+        amount_cents: amount,
+        currency: CURRENCY,
+        booking_id: &booking.id,
+        kind: body.kind,
+        description: format!(
+            "{} {} on {}",
+            booking.service.label(),
+            booking.confirmation_number.as_deref().unwrap_or_default(),
+            booking.booking_date
+        ),
+        receipt_email: booking.customer_email.as_deref(),
+        // A double-click gets the same intent back rather than a second charge
+        idempotency_key: format!("{}-{}-{}-{}-{}", booking.id, body.kind.slug(), amount, paid, canceled),
+    }).await;
+    let intent = match intent {
+        Ok(i) => i,
+        Err(e) => {
+            console_error!("Payment intent for {} failed: {}", booking.id, e);
+            return failed("Could not start the payment. Please try again.", 502);
+        }
+    };
+
+    // Re-checked in the insert so two requests racing past the check above can't both
+    // record an open intent
+    let now = chrono::Utc::now().to_rfc3339();
+    query!(
+        &db,
+        "INSERT INTO payments (id, booking_id, provider, intent_id, kind, amount_cents, currency, status, created_at, updated_at)
+         SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, 'pending', ?8, ?8
+         WHERE NOT EXISTS (SELECT 1 FROM payments WHERE booking_id = ?2 AND status = 'pending' AND intent_id != ?4)
+         ON CONFLICT (intent_id) DO NOTHING",
+        &auth::generate_id("pay")?,
+        &booking.id,
+        &provider.name(),
+        &intent.id,
+        &body.kind,
+        &intent.amount,
+        &CURRENCY,
+        &now,
+    )?
+    .run()
+    .await?;
+
+    let payment_id = query!(&db, "SELECT id FROM payments WHERE intent_id = ?1", &intent.id)?
+        .first::<IdRow>(None)
+        .await?
+        .map(|r| r.id);
+    if payment_id.is_none() {
+        return failed("A payment on this booking is already in progress. Please finish it or contact us.", 409);
+    }
+
+    json_response(&PaymentIntentResponse {
+        success: true,
+        message: format!("Pay ${:.2} to complete your {}", from_cents(intent.amount), match body.kind {
+            PaymentKind::Deposit => "deposit",
+            PaymentKind::Full => "booking",
+        }),
+        payment_id,
+        client_secret: intent.client_secret,
+        publishable_key: provider.publishable_key().map(str::to_string),
+        amount: Some(from_cents(intent.amount)),
+    }, 200)
+}
+
+/// POST /api/payments/webhook - signed event from the processor; redeliveries are no-ops
+pub async fn handle_webhook(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let provider = match payment_provider(&ctx.env) {
+        Some(p) => p,
+        None => return Response::error("Payments are not configured", 503),
+    };
+
+    let signature = match req.headers().get("Stripe-Signature").ok().flatten() {
+        Some(s) => s,
+        None => return Response::error("Missing signature", 400),
+    };
+    let payload = req.text().await?;
+
+    let event = match provider.verify_webhook(&payload, &signature, chrono::Utc::now().timestamp()) {
+        Ok(e) => e,
+        Err(reason) => {
+            console_warn!("Rejected payment webhook: {}", reason);
+            return Response::error("Invalid signature", 400);
+        }
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let seen = query!(&db, "SELECT event_id FROM payment_events WHERE event_id = ?1", &event.id)?
+        .first::<serde_json::Value>(None)
+        .await?
+        .is_some();
+    if seen {
+        return json_response(&serde_json::json!({"received": true, "duplicate": true}), 200);
+    }
+
+    let intent = event.intent();
+    let status = match event.event_type.as_str() {
+        "payment_intent.succeeded" => Some(PaymentStatus::Succeeded),
+        "payment_intent.payment_failed" => Some(PaymentStatus::Failed),
+        "payment_intent.canceled" => Some(PaymentStatus::Canceled),
+        _ => None,
+    };
+
+    // Applying an event sets state rather than adding to it, so a race with a
+    // redelivery can't double-count a payment. Succeeded is final: a late failure or
+    // cancel event for the same intent doesn't undo a charge that went through.
+    if let (Some(intent), Some(status)) = (&intent, status) {
+        let now = chrono::Utc::now().to_rfc3339();
+        query!(
+            &db,
+            "UPDATE payments SET status = ?1, updated_at = ?2 WHERE intent_id = ?3 AND status != 'succeeded'",
+            &status,
+            &now,
+            &intent.id,
+        )?
+        .run()
+        .await?;
+
+        let booking_id = query!(&db, "SELECT booking_id AS id FROM payments WHERE intent_id = ?1", &intent.id)?
+            .first::<IdRow>(None)
+            .await?;
+        if let Some(row) = booking_id {
+            refresh_booking_payment(&db, &ctx.env, &row.id).await?;
+        }
+    }
+
+    query!(
+        &db,
+        "INSERT INTO payment_events (event_id, event_type, intent_id, received_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (event_id) DO NOTHING",
+        &event.id,
+        &event.event_type,
+        &intent.as_ref().map(|i| i.id.clone()),
+        &chrono::Utc::now().to_rfc3339(),
+    )?
+    .run()
+    .await?;
+
+    json_response(&serde_json::json!({"received": true}), 200)
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+/// GET /api/admin/bookings/:id/payments
+pub async fn list_payments(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing booking id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let booking = match find_booking(&db, &id).await? {
+        Some(b) => b,
+        None => return Response::error("Booking not found", 404),
+    };
+
+    let payments = query!(&db, "SELECT * FROM payments WHERE booking_id = ?1 ORDER BY created_at", &id)?
+        .all()
+        .await?
+        .results::<Payment>()?;
+
+    json_response(&serde_json::json!({
+        "amount_paid": booking.amount_paid,
+        "payment_status": booking.payment_status,
+        "quote": booking.quote(&load_price_list(&ctx.env).await),
+        "payments": payments,
+    }), 200)
+}
+
+async fn paid_cents(db: &D1Database, booking_id: &str) -> Result<i64> {
+    Ok(query!(
+        db,
+        "SELECT COALESCE(SUM(amount_cents), 0) AS paid_cents FROM payments
+         WHERE booking_id = ?1 AND status = 'succeeded'",
+        &booking_id,
+    )?
+    .first::<PaidRow>(None)
+    .await?
+    .map(|r| r.paid_cents)
+    .unwrap_or(0))
+}
+
+/// Recompute a booking's paid total and status from its succeeded payments
+async fn refresh_booking_payment(db: &D1Database, env: &Env, booking_id: &str) -> Result<()> {
+    let booking = match find_booking(db, booking_id).await? {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let paid = paid_cents(db, booking_id).await?;
+    let due = booking
+        .quote(&load_price_list(env).await)
+        .map(|q| to_cents(q.amount_due))
+        .unwrap_or(paid);
+
+    query!(
+        db,
+        "UPDATE bookings SET amount_paid = ?1, payment_status = ?2, updated_at = ?3 WHERE id = ?4",
+        &from_cents(paid),
+        &BookingPaymentStatus::for_amounts(paid, due),
+        &chrono::Utc::now().to_rfc3339(),
+        &booking_id,
+    )?
+    .run()
+    .await?;
+    Ok(())
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
@@ -0,0 +1,26 @@
+//! Public price list
+
+use worker::*;
+use crate::handlers::settings::shop_timezone;
+use crate::models::{local_now, PriceList, PricingResponse};
+use crate::utils::cache;
+
+/// GET /api/pricing
+pub async fn get_pricing(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let list = load_price_list(&ctx.env).await;
+    let pricing = PricingResponse::new(&list, local_now(shop_timezone(&ctx.env).await).date());
+
+    let json = serde_json::to_string(&pricing).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "public, max-age=300")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers))
+}
+
+pub(crate) async fn load_price_list(env: &Env) -> PriceList {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<PriceList>(&kv, "price_list").await.unwrap_or_default(),
+        Err(_) => PriceList::default(),
+    }
+}
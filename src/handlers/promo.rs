@@ -0,0 +1,437 @@
+//! Promo codes: public validation, admin CRUD and redemption reporting
+
+use worker::*;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::booking::load_schedule;
+use crate::handlers::pricing::load_price_list;
+use crate::handlers::settings::shop_timezone;
+use crate::handlers::waiver::rate_limited;
+use crate::models::*;
+use crate::utils::auth;
+
+/// Redemption count joined onto each promo row
+const PROMO_COLUMNS: &str =
+    "p.*, (SELECT COUNT(*) FROM promo_redemptions r WHERE r.code = p.code) AS uses";
+
+/// POST /api/promo/validate - check a code and return the discounted total
+pub async fn validate_promo(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Slows down guessing codes
+    if rate_limited(&ctx, &format!("rate:promo:{}", client_ip), 30).await {
+        return json_response(&ValidatePromoResponse {
+            valid: false,
+            message: "Too many attempts. Please try again later.".to_string(),
+            quote: None,
+        }, 429);
+    }
+
+    let body: ValidatePromoRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid promo request", 400),
+    };
+
+    let invalid = |message: &str| json_response(&ValidatePromoResponse {
+        valid: false,
+        message: message.to_string(),
+        quote: None,
+    }, 200);
+
+    let service = match BookingService::from_slug(&body.service) {
+        Some(s) => s,
+        None => return invalid("Please choose a service"),
+    };
+    let date = match parse_date(&body.date) {
+        Some(d) => d,
+        None => return invalid("Please choose a valid date"),
+    };
+    if body.party_size == 0 || body.party_size > MAX_BOOKING_PARTY {
+        return invalid("Please enter your party size");
+    }
+    let code = match normalize_promo_code(&body.code) {
+        Some(c) => c,
+        None => return invalid("That promo code is not valid"),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let promo = match find_promo(&db, &code).await? {
+        Some(p) => p,
+        None => return invalid("That promo code is not valid"),
+    };
+
+    let email_used = match body.email.as_deref().filter(|e| e.contains('@')) {
+        Some(email) if promo.once_per_email => email_used(&db, &code, email).await?,
+        _ => false,
+    };
+    if let Err(message) = promo.check(service, local_now(shop_timezone(&ctx.env).await).date(), email_used) {
+        return invalid(&message);
+    }
+
+    let hours = load_schedule(&ctx.env)
+        .await
+        .for_service(service)
+        .map(|s| s.duration_minutes as f64 / 60.0)
+        .unwrap_or(0.0);
+    let quantity = body.quantity.unwrap_or_else(|| service.units_for(body.party_size));
+    let quote = load_price_list(&ctx.env)
+        .await
+        .quote(service, date, body.party_size, quantity, hours)
+        .map(|q| q.with_promo(&promo));
+
+    json_response(&ValidatePromoResponse {
+        valid: true,
+        message: match &quote {
+            Some(q) => format!("{} applied: you save ${:.2}", code, q.promo_discount),
+            None => format!("{} applied", code),
+        },
+        quote,
+    }, 200)
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+/// GET /api/admin/promos
+pub async fn list_promos(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let promos = db.prepare(format!(
+            "SELECT {} FROM promo_codes p ORDER BY p.active DESC, p.created_at DESC",
+            PROMO_COLUMNS
+        ))
+        .all()
+        .await?
+        .results::<PromoCode>()?;
+
+    json_response(&serde_json::json!({ "promos": promos }), 200)
+}
+
+/// POST /api/admin/promos - create a code
+pub async fn create_promo(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let body: PromoCodeRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid promo code data", 400),
+    };
+
+    let code = match normalize_promo_code(&body.code) {
+        Some(c) => c,
+        None => return Response::error("Codes are 3-32 letters, numbers, dashes or underscores", 400),
+    };
+    if let Err(message) = body.validate() {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    if find_promo(&db, &code).await?.is_some() {
+        return Response::error("That code already exists", 409);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let (valid_from, valid_to) = body.window();
+    let services = serde_json::to_string(&body.services).map_err(|e| Error::from(e.to_string()))?;
+    query!(
+        &db,
+        "INSERT INTO promo_codes
+            (code, description, discount_type, amount, services, valid_from, valid_to, max_uses,
+             once_per_email, active, created_by, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)",
+        &code,
+        &body.description.trim(),
+        &body.discount_type,
+        &body.amount,
+        &services,
+        &valid_from,
+        &valid_to,
+        &body.max_uses,
+        &(body.once_per_email as i32),
+        &(body.active as i32),
+        &session.username,
+        &now,
+    )?
+    .run()
+    .await?;
+
+    match find_promo(&db, &code).await? {
+        Some(promo) => json_response(&serde_json::json!({"success": true, "promo": promo}), 200),
+        None => Response::error("Could not save promo code", 500),
+    }
+}
+
+/// GET /api/admin/promos/:code - the code and every redemption
+pub async fn get_promo(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let code = match ctx.param("code").and_then(|c| normalize_promo_code(c)) {
+        Some(c) => c,
+        None => return Response::error("Promo code not found", 404),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let promo = match find_promo(&db, &code).await? {
+        Some(p) => p,
+        None => return Response::error("Promo code not found", 404),
+    };
+
+    let redemptions = query!(
+        &db,
+        "SELECT * FROM promo_redemptions WHERE code = ?1 ORDER BY redeemed_at DESC",
+        &code,
+    )?
+    .all()
+    .await?
+    .results::<PromoRedemption>()?;
+
+    json_response(&serde_json::json!({
+        "promo": promo,
+        "redemptions": redemptions,
+    }), 200)
+}
+
+/// POST /api/admin/promos/:code - replace the code's terms
+pub async fn update_promo(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let code = match ctx.param("code").and_then(|c| normalize_promo_code(c)) {
+        Some(c) => c,
+        None => return Response::error("Promo code not found", 404),
+    };
+
+    let body: PromoCodeRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid promo code data", 400),
+    };
+
+    if let Err(message) = body.validate() {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let (valid_from, valid_to) = body.window();
+    let services = serde_json::to_string(&body.services).map_err(|e| Error::from(e.to_string()))?;
+    let result = query!(
+        &db,
+        "UPDATE promo_codes SET description = ?1, discount_type = ?2, amount = ?3, services = ?4,
+            valid_from = ?5, valid_to = ?6, max_uses = ?7, once_per_email = ?8, active = ?9, updated_at = ?10
+         WHERE code = ?11",
+        &body.description.trim(),
+        &body.discount_type,
+        &body.amount,
+        &services,
+        &valid_from,
+        &valid_to,
+        &body.max_uses,
+        &(body.once_per_email as i32),
+        &(body.active as i32),
+        &chrono::Utc::now().to_rfc3339(),
+        &code,
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return Response::error("Promo code not found", 404);
+    }
+
+    match find_promo(&db, &code).await? {
+        Some(promo) => json_response(&serde_json::json!({"success": true, "promo": promo}), 200),
+        None => Response::error("Promo code not found", 404),
+    }
+}
+
+/// POST /api/admin/promos/:code/delete - only unused codes; deactivate the rest
+pub async fn delete_promo(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let code = match ctx.param("code").and_then(|c| normalize_promo_code(c)) {
+        Some(c) => c,
+        None => return Response::error("Promo code not found", 404),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    match find_promo(&db, &code).await? {
+        Some(p) if p.uses > 0 => {
+            return Response::error("This code has been redeemed; deactivate it to keep the history", 409);
+        }
+        Some(_) => {}
+        None => return Response::error("Promo code not found", 404),
+    }
+
+    query!(&db, "DELETE FROM promo_codes WHERE code = ?1", &code)?
+        .run()
+        .await?;
+
+    json_response(&serde_json::json!({"success": true}), 200)
+}
+
+/// GET /api/admin/promos/report?date_from=&date_to= - redemptions per code (default last 30 days)
+pub async fn promo_report(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    let mut date_from = today - chrono::Duration::days(30);
+    let mut date_to = today;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "date_from" => if let Some(d) = parse_date(&value) { date_from = d; },
+            "date_to" => if let Some(d) = parse_date(&value) { date_to = d; },
+            _ => {}
+        }
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    // redeemed_at is RFC 3339, so compare the date prefix
+    let from = date_from.format("%Y-%m-%d").to_string();
+    let to = date_to.format("%Y-%m-%d").to_string();
+    let codes = query!(
+        &db,
+        "SELECT code, COUNT(*) AS redemptions, COUNT(DISTINCT email) AS customers,
+                COALESCE(SUM(subtotal), 0) AS total_subtotal, COALESCE(SUM(discount), 0) AS total_discount
+         FROM promo_redemptions
+         WHERE substr(redeemed_at, 1, 10) >= ?1 AND substr(redeemed_at, 1, 10) <= ?2
+         GROUP BY code
+         ORDER BY redemptions DESC",
+        &from,
+        &to,
+    )?
+    .all()
+    .await?
+    .results::<PromoReportRow>()?;
+
+    let redemptions: u32 = codes.iter().map(|c| c.redemptions).sum();
+    let total_discount = round_cents(codes.iter().map(|c| c.total_discount).sum());
+
+    json_response(&serde_json::json!({
+        "date_from": from,
+        "date_to": to,
+        "redemptions": redemptions,
+        "total_discount": total_discount,
+        "codes": codes,
+    }), 200)
+}
+
+pub(crate) async fn find_promo(db: &D1Database, code: &str) -> Result<Option<PromoCode>> {
+    let sql = format!("SELECT {} FROM promo_codes p WHERE p.code = ?1", PROMO_COLUMNS);
+    query!(db, &sql, &code)?
+        .first::<PromoCode>(None)
+        .await
+}
+
+pub(crate) async fn email_used(db: &D1Database, code: &str, email: &str) -> Result<bool> {
+    Ok(query!(
+        db,
+        "SELECT id FROM promo_redemptions WHERE code = ?1 AND email = ?2 LIMIT 1",
+        &code,
+        &email.trim().to_lowercase(),
+    )?
+    .first::<serde_json::Value>(None)
+    .await?
+    .is_some())
+}
+
+/// Claim one use of a code for a booking. A single statement re-checks the cap and
+/// the per-email rule, so two checkouts can't both take the last use. Returns the
+/// redemption id, or None when the code is no longer available.
+pub(crate) async fn redeem(
+    db: &D1Database,
+    promo: &PromoCode,
+    booking_id: &str,
+    email: &str,
+    subtotal: f64,
+    discount: f64,
+) -> Result<Option<String>> {
+    let id = auth::generate_id("promo")?;
+    let result = query!(
+        db,
+        "INSERT INTO promo_redemptions (id, code, booking_id, email, subtotal, discount, redeemed_at)
+         SELECT ?1, p.code, ?3, ?4, ?5, ?6, ?7 FROM promo_codes p
+         WHERE p.code = ?2 AND p.active = 1
+           AND (p.max_uses IS NULL OR (SELECT COUNT(*) FROM promo_redemptions r WHERE r.code = p.code) < p.max_uses)
+           AND (p.once_per_email = 0 OR NOT EXISTS
+                (SELECT 1 FROM promo_redemptions r WHERE r.code = p.code AND r.email = ?4))",
+        &id,
+        &promo.code,
+        &booking_id,
+        &email.trim().to_lowercase(),
+        &subtotal,
+        &discount,
+        &chrono::Utc::now().to_rfc3339(),
+    )?
+    .run()
+    .await?;
+
+    Ok((result.meta()?.and_then(|m| m.changes).unwrap_or(0) > 0).then_some(id))
+}
+
+/// Give a use back when the booking it was claimed for didn't go through
+/// The redemption recorded for a booking, if it used a code
+pub(crate) async fn redemption_for(db: &D1Database, booking_id: &str) -> Result<Option<String>> {
+    query!(db, "SELECT id FROM promo_redemptions WHERE booking_id = ?1 LIMIT 1", &booking_id)?
+        .first::<String>(Some("id"))
+        .await
+}
+
+pub(crate) async fn release(db: &D1Database, redemption_id: &str) -> Result<()> {
+    query!(db, "DELETE FROM promo_redemptions WHERE id = ?1", &redemption_id)?
+        .run()
+        .await?;
+    Ok(())
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
@@ -0,0 +1,393 @@
+//! Equipment check-out and check-in, late fees, what's out now and end-of-day reconciliation
+
+use worker::*;
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::booking::find_booking;
+use crate::handlers::pricing::load_price_list;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::auth;
+
+#[derive(Deserialize)]
+struct AssetIdRow {
+    asset_id: String,
+}
+
+#[derive(Deserialize)]
+struct IdRow {
+    id: String,
+}
+
+/// POST /api/admin/rentals - check equipment out to a customer
+pub async fn check_out(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let body: RentalCheckOut = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid check-out", 400),
+    };
+    let asset_ids = match body.asset_ids() {
+        Ok(ids) => ids,
+        Err(message) => return Response::error(message, 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let booking = match clean(&body.booking_id) {
+        Some(id) => match find_booking(&db, &id).await? {
+            Some(b) if matches!(b.status, BookingStatus::Confirmed | BookingStatus::AtRisk) => Some(b),
+            Some(_) => return Response::error("That booking isn't confirmed", 409),
+            None => return Response::error("Booking not found", 404),
+        },
+        None => None,
+    };
+
+    let customer_name = match clean(&body.customer_name).or_else(|| booking.as_ref().and_then(|b| b.customer_name.clone())) {
+        Some(n) => n,
+        None => return Response::error("Customer name is required", 400),
+    };
+    let customer_phone = clean(&body.customer_phone).or_else(|| booking.as_ref().and_then(|b| b.customer_phone.clone()));
+    let customer_email = clean(&body.customer_email)
+        .or_else(|| booking.as_ref().and_then(|b| b.customer_email.clone()))
+        .map(|e| e.to_lowercase());
+
+    let now = local_now(shop_timezone(&ctx.env).await);
+    let expected = match clean(&body.expected_return).or_else(|| booking.as_ref().map(|b| b.end_at.clone())) {
+        Some(value) => match parse_rental_time(&value) {
+            Some(t) => t,
+            None => return Response::error("Invalid expected return (YYYY-MM-DDTHH:MM)", 400),
+        },
+        None => return Response::error("Expected return time is required", 400),
+    };
+    if expected <= now {
+        return Response::error("Expected return must be in the future", 400);
+    }
+
+    let assets = assets_by_id(&db, &asset_ids).await?;
+    let missing: Vec<&str> = asset_ids
+        .iter()
+        .filter(|id| !assets.iter().any(|a| &a.id == *id))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Response::error(format!("Unknown assets: {}", missing.join(", ")), 404);
+    }
+    if let Some(asset) = assets.iter().find(|a| a.out_of_service) {
+        return Response::error(format!("{} is out of service", asset.name), 409);
+    }
+    let already_out = assets_out(&db, &asset_ids).await?;
+    if let Some(asset) = assets.iter().find(|a| already_out.contains(&a.id)) {
+        return Response::error(format!("{} is already checked out", asset.name), 409);
+    }
+
+    let id = auth::generate_id("rent")?;
+    let stamp = chrono::Utc::now().to_rfc3339();
+    let mut statements = vec![query!(
+        &db,
+        "INSERT INTO rentals (id, booking_id, customer_name, customer_phone, customer_email, checked_out_at,
+            expected_return_at, status, checked_out_by, notes, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'out', ?8, ?9, ?10, ?10)",
+        &id,
+        &booking.as_ref().map(|b| b.id.clone()),
+        &customer_name,
+        &customer_phone,
+        &customer_email,
+        &now.format(RENTAL_TIME_FORMAT).to_string(),
+        &expected.format(RENTAL_TIME_FORMAT).to_string(),
+        &session.username,
+        &clean(&body.notes),
+        &stamp,
+    )?];
+    for asset in &assets {
+        statements.push(query!(
+            &db,
+            "INSERT INTO rental_assets (rental_id, asset_id, asset_name, category) VALUES (?1, ?2, ?3, ?4)",
+            &id,
+            &asset.id,
+            &asset.name,
+            &asset.category,
+        )?);
+    }
+
+    // One transaction: the open-asset index fails the whole check-out if another
+    // counter took one of these units in the meantime
+    if let Err(e) = db.batch(statements).await {
+        console_warn!("Check-out {} failed: {}", id, e);
+        return Response::error("One of those assets was just checked out. Please refresh and try again.", 409);
+    }
+
+    match rental_detail(&db, &id).await? {
+        Some(detail) => json_response(&serde_json::json!({"success": true, "rental": detail}), 200),
+        None => Response::error("Could not save the check-out", 500),
+    }
+}
+
+/// POST /api/admin/rentals/:id/checkin - return some or all assets and charge any late fee
+pub async fn check_in(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing rental id", 400),
+    };
+
+    let body: RentalCheckIn = req.json().await.unwrap_or_default();
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let detail = match rental_detail(&db, &id).await? {
+        Some(d) => d,
+        None => return Response::error("Rental not found", 404),
+    };
+
+    let out: Vec<&RentalAsset> = detail.assets.iter().filter(|a| a.returned_at.is_none()).collect();
+    if out.is_empty() {
+        return Response::error("Everything on this rental is already back", 409);
+    }
+    let returning: Vec<&RentalAsset> = if body.asset_ids.is_empty() {
+        out
+    } else {
+        if let Some(unknown) = body.asset_ids.iter().find(|id| !out.iter().any(|a| &a.asset_id == *id)) {
+            return Response::error(format!("{} isn't out on this rental", unknown), 400);
+        }
+        out.into_iter().filter(|a| body.asset_ids.contains(&a.asset_id)).collect()
+    };
+
+    let now = local_now(shop_timezone(&ctx.env).await);
+    let returned_at = now.format(RENTAL_TIME_FORMAT).to_string();
+    let late = late_minutes(&detail.rental.expected_return_at, now);
+    let prices = load_price_list(&ctx.env).await;
+    let note = clean(&body.note);
+
+    let mut statements = vec![];
+    let mut charged = 0.0;
+    for asset in &returning {
+        let fee = prices.late_fee(asset.category, late);
+        charged += fee;
+        statements.push(query!(
+            &db,
+            "UPDATE rental_assets SET returned_at = ?1, late_minutes = ?2, late_fee = ?3, return_note = ?4
+             WHERE rental_id = ?5 AND asset_id = ?6 AND returned_at IS NULL",
+            &returned_at,
+            &late,
+            &fee,
+            &note,
+            &id,
+            &asset.asset_id,
+        )?);
+    }
+    statements.push(query!(
+        &db,
+        "UPDATE rentals SET
+            late_fee = (SELECT COALESCE(SUM(late_fee), 0) FROM rental_assets WHERE rental_id = ?1),
+            status = CASE WHEN EXISTS (SELECT 1 FROM rental_assets WHERE rental_id = ?1 AND returned_at IS NULL)
+                          THEN 'out' ELSE 'returned' END,
+            checked_in_at = CASE WHEN EXISTS (SELECT 1 FROM rental_assets WHERE rental_id = ?1 AND returned_at IS NULL)
+                                 THEN NULL ELSE ?2 END,
+            checked_in_by = ?3, updated_at = ?4
+         WHERE id = ?1",
+        &id,
+        &returned_at,
+        &session.username,
+        &chrono::Utc::now().to_rfc3339(),
+    )?);
+    db.batch(statements).await?;
+
+    let detail = match rental_detail(&db, &id).await? {
+        Some(d) => d,
+        None => return Response::error("Rental not found", 404),
+    };
+    let message = if late > 0 && charged > 0.0 {
+        format!("{} returned {} minutes late. Late fee ${:.2}.", returning.len(), late, round_cents(charged))
+    } else {
+        format!("{} returned.", returning.len())
+    };
+    json_response(&serde_json::json!({
+        "success": true,
+        "message": message,
+        "late_fee": round_cents(charged),
+        "rental": detail,
+    }), 200)
+}
+
+/// GET /api/admin/rentals/:id
+pub async fn get_rental(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing rental id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    match rental_detail(&db, &id).await? {
+        Some(detail) => json_response(&detail, 200),
+        None => Response::error("Rental not found", 404),
+    }
+}
+
+/// GET /api/admin/rentals/out - every asset on the water or trail right now, most overdue first
+pub async fn currently_out(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let lines = db.prepare(format!("{} WHERE ra.returned_at IS NULL", ASSET_LINE_SELECT))
+        .all()
+        .await?
+        .results::<RentalAssetLine>()?;
+
+    let now = local_now(shop_timezone(&ctx.env).await);
+    let prices = load_price_list(&ctx.env).await;
+    let assets = lines
+        .into_iter()
+        .map(|line| {
+            let overdue = late_minutes(&line.expected_return_at, now);
+            OutAsset {
+                late_fee_now: prices.late_fee(line.category, overdue),
+                overdue_minutes: overdue,
+                line,
+            }
+        })
+        .collect();
+
+    json_response(&CurrentlyOut::new(now, assets), 200)
+}
+
+/// GET /api/admin/rentals/reconciliation?date=YYYY-MM-DD - defaults to today
+pub async fn reconciliation(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let date = match url.query_pairs().find(|(k, _)| k == "date") {
+        Some((_, value)) => match parse_date(&value) {
+            Some(d) => d,
+            None => return Response::error("Invalid date (YYYY-MM-DD)", 400),
+        },
+        None => local_now(shop_timezone(&ctx.env).await).date(),
+    };
+    let day = date.format("%Y-%m-%d").to_string();
+    let next_day = (date + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let lines = query!(
+        &db,
+        &format!("{} WHERE r.checked_out_at >= ?1 AND r.checked_out_at < ?2 ORDER BY r.checked_out_at", ASSET_LINE_SELECT),
+        &day,
+        &next_day,
+    )?
+    .all()
+    .await?
+    .results::<RentalAssetLine>()?;
+
+    let bookings = query!(
+        &db,
+        "SELECT * FROM bookings WHERE booking_date = ?1 AND status IN ('confirmed', 'at_risk') ORDER BY start_at",
+        &day,
+    )?
+    .all()
+    .await?
+    .results::<Booking>()?;
+
+    let claimed: Vec<String> = query!(
+        &db,
+        "SELECT DISTINCT r.booking_id AS id FROM rentals r JOIN bookings b ON b.id = r.booking_id
+         WHERE b.booking_date = ?1",
+        &day,
+    )?
+    .all()
+    .await?
+    .results::<IdRow>()?
+    .into_iter()
+    .map(|r| r.id)
+    .collect();
+
+    json_response(&DailyReconciliation::build(date, lines, &bookings, &claimed), 200)
+}
+
+const ASSET_LINE_SELECT: &str =
+    "SELECT ra.*, r.customer_name, r.customer_phone, r.checked_out_at, r.expected_return_at
+     FROM rental_assets ra JOIN rentals r ON r.id = ra.rental_id";
+
+async fn rental_detail(db: &D1Database, id: &str) -> Result<Option<RentalDetail>> {
+    let rental = match query!(db, "SELECT * FROM rentals WHERE id = ?1", &id)?.first::<Rental>(None).await? {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let assets = query!(db, "SELECT * FROM rental_assets WHERE rental_id = ?1 ORDER BY asset_name", &id)?
+        .all()
+        .await?
+        .results::<RentalAsset>()?;
+    Ok(Some(RentalDetail { rental, assets }))
+}
+
+async fn assets_by_id(db: &D1Database, ids: &[String]) -> Result<Vec<FleetAsset>> {
+    let placeholders: Vec<String> = (1..=ids.len()).map(|n| format!("?{}", n)).collect();
+    let bindings: Vec<JsValue> = ids.iter().map(|id| JsValue::from(id.as_str())).collect();
+    db.prepare(format!("SELECT * FROM fleet_assets WHERE id IN ({})", placeholders.join(", ")))
+        .bind(&bindings)?
+        .all()
+        .await?
+        .results::<FleetAsset>()
+}
+
+/// Which of `ids` are on an open rental
+async fn assets_out(db: &D1Database, ids: &[String]) -> Result<Vec<String>> {
+    let placeholders: Vec<String> = (1..=ids.len()).map(|n| format!("?{}", n)).collect();
+    let bindings: Vec<JsValue> = ids.iter().map(|id| JsValue::from(id.as_str())).collect();
+    Ok(db.prepare(format!(
+            "SELECT asset_id FROM rental_assets WHERE returned_at IS NULL AND asset_id IN ({})",
+            placeholders.join(", ")
+        ))
+        .bind(&bindings)?
+        .all()
+        .await?
+        .results::<AssetIdRow>()?
+        .into_iter()
+        .map(|r| r.asset_id)
+        .collect())
+}
+
+fn clean(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
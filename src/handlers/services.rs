@@ -14,7 +14,7 @@ pub async fn get_services(_req: Request, ctx: RouteContext<()>) -> Result<Respon
     };
     
     let json = serde_json::to_string(&services).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=60")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
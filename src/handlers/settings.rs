@@ -16,9 +16,18 @@ pub async fn get_public_settings(_req: Request, ctx: RouteContext<()>) -> Result
     let public_settings = PublicSettings::from(&settings);
     
     let json = serde_json::to_string(&public_settings).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
     Ok(Response::ok(json)?.with_headers(headers))
 }
+
+/// Time zone for booking, rental and reminder times (`site_settings.general.timezone`)
+pub(crate) async fn shop_timezone(env: &Env) -> chrono_tz::Tz {
+    let settings = match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<SiteSettings>(&kv, "site_settings").await.unwrap_or_default(),
+        Err(_) => SiteSettings::default(),
+    };
+    settings.general.tz()
+}
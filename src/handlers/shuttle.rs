@@ -0,0 +1,195 @@
+//! Tubing shuttle: seat planning for bookings, per-departure manifests and seat moves
+
+use worker::*;
+use crate::handlers::admin::verify_session;
+use crate::handlers::booking::find_booking;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::cache;
+
+/// GET /api/admin/shuttle/manifest?date=YYYY-MM-DD - who rides each departure (defaults to today)
+pub async fn get_manifest(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let date = match url.query_pairs().find(|(k, _)| k == "date") {
+        Some((_, value)) => match parse_date(&value) {
+            Some(d) => d,
+            None => return Response::error("Invalid date (YYYY-MM-DD)", 400),
+        },
+        None => local_now(shop_timezone(&ctx.env).await).date(),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let bookings = query!(
+        &db,
+        "SELECT * FROM bookings
+         WHERE booking_date = ?1 AND service = ?2 AND status IN ('confirmed', 'at_risk')
+         ORDER BY start_time, customer_name",
+        &date.format("%Y-%m-%d").to_string(),
+        &BookingService::Tubing,
+    )?
+    .all()
+    .await?
+    .results::<Booking>()?;
+
+    let schedule = load_shuttle_schedule(&ctx.env).await;
+    json_response(&ShuttleManifest::build(&schedule, date, &bookings), 200)
+}
+
+/// POST /api/admin/bookings/:id/shuttle - seat a tubing party on another run, within its seats
+pub async fn assign_shuttle(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing booking id", 400),
+    };
+
+    let body: ShuttleAssignment = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid shuttle assignment", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let booking = match find_booking(&db, &id).await? {
+        Some(b) if !b.service.uses_shuttle() => return Response::error("Only tubing bookings ride the shuttle", 409),
+        Some(b) if matches!(b.status, BookingStatus::Confirmed | BookingStatus::AtRisk) => b,
+        Some(_) => return Response::error("Only confirmed bookings can be seated", 409),
+        None => return Response::error("Booking not found", 404),
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let run_id = match body.run.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+        Some(r) => r.to_string(),
+        None => {
+            query!(
+                &db,
+                "UPDATE bookings SET shuttle_run = NULL, shuttle_departs = NULL, shuttle_put_in = NULL,
+                    shuttle_seats = 0, updated_at = ?1
+                 WHERE id = ?2",
+                &now,
+                &id,
+            )?
+            .run()
+            .await?;
+            return match find_booking(&db, &id).await? {
+                Some(b) => json_response(&serde_json::json!({"success": true, "booking": b}), 200),
+                None => Response::error("Booking not found", 404),
+            };
+        }
+    };
+
+    let schedule = load_shuttle_schedule(&ctx.env).await;
+    let date = match parse_date(&booking.booking_date) {
+        Some(d) => d,
+        None => local_now(shop_timezone(&ctx.env).await).date(),
+    };
+    // Staff may seat a party on any run that day, not just those in its window
+    let run = match schedule.run(&run_id).filter(|r| r.runs_on(date)) {
+        Some(r) => r,
+        None => return Response::error("That run doesn't operate on the booking's date", 400),
+    };
+
+    let sql = format!(
+        "UPDATE bookings SET shuttle_run = ?1, shuttle_departs = ?2, shuttle_put_in = ?3, shuttle_seats = ?4,
+            updated_at = ?5
+         WHERE id = ?6
+           AND (SELECT COALESCE(SUM(shuttle_seats), 0) FROM bookings
+                WHERE id != ?6 AND booking_date = ?7 AND shuttle_run = ?1 AND {}) + ?4 <= ?8",
+        committed_booking_predicate("?5")
+    );
+    let result = query!(
+        &db,
+        &sql,
+        &run.id,
+        &run.departs,
+        &run.put_in,
+        &booking.party_size,
+        &now,
+        &id,
+        &booking.booking_date,
+        &run.seats,
+    )?
+    .run()
+    .await?;
+
+    if result.meta()?.and_then(|m| m.changes).unwrap_or(0) == 0 {
+        return Response::error(format!("The {} shuttle doesn't have {} seats left", run.departs, booking.party_size), 409);
+    }
+
+    match find_booking(&db, &id).await? {
+        Some(b) => json_response(&serde_json::json!({"success": true, "booking": b}), 200),
+        None => Response::error("Booking not found", 404),
+    }
+}
+
+/// The run a new or moved tubing booking rides, or `None` for services (or a schedule)
+/// without a shuttle. `exclude` leaves a booking's own seats out of the count.
+pub(crate) async fn plan_seat(
+    env: &Env,
+    db: &D1Database,
+    plan: &HoldPlan,
+    requested: Option<&str>,
+    exclude: Option<&str>,
+) -> Result<std::result::Result<Option<ShuttleRun>, String>> {
+    if !plan.service.uses_shuttle() {
+        return Ok(Ok(None));
+    }
+    let schedule = load_shuttle_schedule(env).await;
+    if !schedule.enabled {
+        return Ok(Ok(None));
+    }
+    let booked = booked_seats(db, &plan.date.format("%Y-%m-%d").to_string(), exclude).await?;
+    Ok(schedule
+        .choose(plan.date, &plan.window, requested, plan.party_size, &booked)
+        .map(|run| Some(run.clone())))
+}
+
+/// Seats held or confirmed per run on a date
+pub(crate) async fn booked_seats(db: &D1Database, date: &str, exclude: Option<&str>) -> Result<Vec<RunSeats>> {
+    let sql = format!(
+        "SELECT shuttle_run, SUM(shuttle_seats) AS seats FROM bookings
+         WHERE booking_date = ?1 AND shuttle_run IS NOT NULL AND id != COALESCE(?2, '') AND {}
+         GROUP BY shuttle_run",
+        committed_booking_predicate("?3")
+    );
+    query!(
+        db,
+        &sql,
+        &date,
+        &exclude,
+        &chrono::Utc::now().to_rfc3339(),
+    )?
+    .all()
+    .await?
+    .results::<RunSeats>()
+}
+
+pub(crate) async fn load_shuttle_schedule(env: &Env) -> ShuttleSchedule {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<ShuttleSchedule>(&kv, "shuttle_schedule").await.unwrap_or_default(),
+        Err(_) => ShuttleSchedule::default(),
+    }
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
@@ -12,7 +12,7 @@ pub async fn serve_css(_req: Request, ctx: RouteContext<()>) -> Result<Response>
         _ => return Response::error("Not Found", 404),
     };
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "text/css; charset=utf-8")?;
     headers.set("Cache-Control", "public, max-age=86400")?;
     Ok(Response::ok(content)?.with_headers(headers))
@@ -29,7 +29,7 @@ pub async fn serve_js(_req: Request, ctx: RouteContext<()>) -> Result<Response>
         _ => return Response::error("Not Found", 404),
     };
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/javascript; charset=utf-8")?;
     headers.set("Cache-Control", "public, max-age=86400")?;
     Ok(Response::ok(content)?.with_headers(headers))
@@ -43,7 +43,7 @@ pub async fn serve_admin_css(_req: Request, ctx: RouteContext<()>) -> Result<Res
         _ => return Response::error("Not Found", 404),
     };
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "text/css; charset=utf-8")?;
     headers.set("Cache-Control", "public, max-age=3600")?;
     Ok(Response::ok(content)?.with_headers(headers))
@@ -57,7 +57,7 @@ pub async fn serve_admin_js(_req: Request, ctx: RouteContext<()>) -> Result<Resp
         _ => return Response::error("Not Found", 404),
     };
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/javascript; charset=utf-8")?;
     headers.set("Cache-Control", "public, max-age=3600")?;
     Ok(Response::ok(content)?.with_headers(headers))
@@ -70,7 +70,7 @@ pub async fn serve_image(_req: Request, _ctx: RouteContext<()>) -> Result<Respon
 }
 
 pub async fn serve_favicon(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "image/x-icon")?;
     headers.set("Cache-Control", "public, max-age=604800")?;
     // Return a simple placeholder or redirect to CDN
@@ -84,7 +84,7 @@ Allow: /
 Sitemap: https://riverviewadventurecompany.com/sitemap.xml
 "#;
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "text/plain")?;
     headers.set("Cache-Control", "public, max-age=86400")?;
     Ok(Response::ok(content)?.with_headers(headers))
@@ -129,6 +129,12 @@ pub async fn serve_sitemap(_req: Request, _ctx: RouteContext<()>) -> Result<Resp
         <changefreq>weekly</changefreq>
         <priority>0.8</priority>
     </url>
+    <url>
+        <loc>https://riverviewadventurecompany.com/repairs</loc>
+        <lastmod>2026-10-18</lastmod>
+        <changefreq>monthly</changefreq>
+        <priority>0.7</priority>
+    </url>
     <url>
         <loc>https://riverviewadventurecompany.com/contact</loc>
         <lastmod>2026-01-04</lastmod>
@@ -143,7 +149,7 @@ pub async fn serve_sitemap(_req: Request, _ctx: RouteContext<()>) -> Result<Resp
     </url>
 </urlset>"#;
     
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/xml")?;
     headers.set("Cache-Control", "public, max-age=3600")?;
     Ok(Response::ok(content)?.with_headers(headers))
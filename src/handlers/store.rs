@@ -1,20 +1,20 @@
 //! Store handler for e-bikes and repair services
 
 use worker::*;
-use crate::models::{default_velotric_products, default_repair_services};
+use crate::models::{default_velotric_products, default_repair_services, RepairService};
 use crate::utils::cache;
 
 pub async fn get_bikes(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
     // Try to get from KV settings (for custom pricing)
     let bikes = if let Ok(kv) = ctx.kv("SETTINGS") {
         cache::get_cached(&kv, "bikes").await
-            .unwrap_or_else(|| default_velotric_products())
+            .unwrap_or_else(default_velotric_products)
     } else {
         default_velotric_products()
     };
     
     let json = serde_json::to_string(&bikes).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
@@ -22,18 +22,20 @@ pub async fn get_bikes(_req: Request, ctx: RouteContext<()>) -> Result<Response>
 }
 
 pub async fn get_repairs(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    // Try to get from KV settings (for custom pricing)
-    let repairs = if let Ok(kv) = ctx.kv("SETTINGS") {
-        cache::get_cached(&kv, "repairs").await
-            .unwrap_or_else(|| default_repair_services())
-    } else {
-        default_repair_services()
-    };
+    let repairs = load_repairs(&ctx.env).await;
     
     let json = serde_json::to_string(&repairs).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
     Ok(Response::ok(json)?.with_headers(headers))
 }
+
+/// Repair catalog, from KV settings (for custom pricing) or the defaults
+pub(crate) async fn load_repairs(env: &Env) -> Vec<RepairService> {
+    match env.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached(&kv, "repairs").await.unwrap_or_else(default_repair_services),
+        Err(_) => default_repair_services(),
+    }
+}
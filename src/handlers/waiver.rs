@@ -0,0 +1,662 @@
+//! Liability waiver submission and admin check-in handlers
+
+use worker::*;
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::settings::shop_timezone;
+use crate::handlers::waiver_group::check_group_code;
+use crate::handlers::waiver_text::active_text_version;
+use crate::api::email::{escape_html, EmailConfig};
+use crate::models::*;
+use crate::utils::{auth, cache, qr};
+
+pub async fn submit_waiver(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+    let user_agent = req.headers()
+        .get("User-Agent")
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    
+    // Rate limiting - groups often sign from the same phone or hotspot, so allow more than contact
+    if rate_limited(&ctx, &format!("rate:waiver:{}", client_ip), 30).await {
+        return error_response("Too many submissions. Please try again later.", 429);
+    }
+    
+    let submission: WaiverSubmission = match req.json().await {
+        Ok(s) => s,
+        Err(_) => return error_response("Invalid waiver data. Please fill in all required fields.", 400),
+    };
+    
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    if let Err(message) = submission.validate(today) {
+        return error_response(&message, 400);
+    }
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Waiver storage not available", 500),
+    };
+    
+    // The signer must have been shown the agreement currently in force. A missing
+    // version id means the page fell back to its built-in copy of version 1.
+    let text_version = active_text_version(&db, today).await?;
+    let saw_current_text = match submission.text_version_id {
+        Some(id) => id == text_version.id,
+        None => text_version.content_hash == WaiverTextVersion::builtin().content_hash,
+    };
+    if !saw_current_text {
+        return error_response(
+            "The release agreement has been updated. Please reload the page and review it before signing.",
+            409,
+        );
+    }
+    
+    if let Some(code) = submission.group_code.as_deref().filter(|c| !c.trim().is_empty()) {
+        if let Err(message) = check_group_code(&db, code, &submission.rental_type, &submission.adventure_date).await? {
+            return error_response(&message, 400);
+        }
+    }
+    
+    let record = WaiverRecord::from_submission(
+        auth::generate_id("wv")?,
+        submission,
+        &text_version,
+        client_ip,
+        user_agent,
+    );
+    
+    if let Err(e) = insert_waiver(&db, &record).await {
+        console_error!("Failed to store waiver: {}", e);
+        return error_response("Could not save your waiver. Please try again.", 500);
+    }
+    
+    let flagged: Vec<&str> = record.minors.iter()
+        .filter(|m| m.below_minimum_age)
+        .map(|m| m.name.as_str())
+        .collect();
+    let message = if flagged.is_empty() {
+        "Waiver signed. You're all set for your adventure!".to_string()
+    } else {
+        format!(
+            "Waiver signed. {} below our minimum age for this activity; staff will confirm at check-in.",
+            flagged.join(", ") + if flagged.len() == 1 { " is" } else { " are" }
+        )
+    };
+    
+    signed_response(&ctx, &record, message).await
+}
+
+async fn insert_waiver(db: &D1Database, record: &WaiverRecord) -> Result<()> {
+    let minors_json = serde_json::to_string(&record.minors).map_err(|e| Error::from(e.to_string()))?;
+    
+    query!(
+        db,
+        "INSERT INTO waivers (
+            id, rental_type, adventure_date, group_size, first_name, last_name, email, phone,
+            dob, emergency_contact, emergency_phone, has_minors, minors, minor_age_flag, signature,
+            sign_date, text_version_id, legal_text_hash, submitted_at, ip_address, user_agent,
+            reuses_waiver_id, group_code
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+        &record.id,
+        &record.rental_type,
+        &record.adventure_date,
+        &record.group_size,
+        &record.first_name,
+        &record.last_name,
+        &record.email,
+        &record.phone,
+        &record.dob,
+        &record.emergency_contact,
+        &record.emergency_phone,
+        &(record.has_minors as i32),
+        &minors_json,
+        &(record.minor_age_flag as i32),
+        &record.signature,
+        &record.sign_date,
+        &record.text_version_id,
+        &record.legal_text_hash,
+        &record.submitted_at,
+        &record.ip_address,
+        &record.user_agent,
+        &record.reuses_waiver_id,
+        &record.group_code,
+    )?
+    .run()
+    .await?;
+    
+    Ok(())
+}
+
+// ============================================
+// RETURNING CUSTOMERS: SEASON WAIVER REUSE
+// ============================================
+
+/// POST /api/waiver/lookup - find a reusable waiver by email and date of birth
+pub async fn lookup_waiver(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    // Email + DOB is the only credential here, so keep guessing slow
+    if rate_limited(&ctx, &format!("rate:waiver-lookup:{}", client_ip), 10).await {
+        return error_response("Too many lookups. Please try again later.", 429);
+    }
+    
+    let body: WaiverLookupRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return error_response("Please enter your email and date of birth.", 400),
+    };
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Waiver storage not available", 500),
+    };
+    
+    let not_found = WaiverLookupResponse {
+        found: false,
+        message: "No waiver on file for this season. Please fill out the form below.".to_string(),
+        waiver_id: None,
+    };
+    
+    let original = match find_reusable_waiver(&db, &ctx, &body.email, &body.dob).await? {
+        Some(w) => w,
+        None => return json_response(&not_found, 200),
+    };
+    
+    json_response(&WaiverLookupResponse {
+        found: true,
+        message: "Welcome back! Your season waiver is still valid. Confirm it for this adventure below.".to_string(),
+        waiver_id: Some(original.id),
+    }, 200)
+}
+
+/// POST /api/waiver/confirm - link a new adventure to an existing season waiver
+pub async fn confirm_waiver(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+    let user_agent = req.headers()
+        .get("User-Agent")
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    
+    if rate_limited(&ctx, &format!("rate:waiver:{}", client_ip), 30).await {
+        return error_response("Too many submissions. Please try again later.", 429);
+    }
+    
+    let body: ConfirmWaiverRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return error_response("Invalid confirmation data", 400),
+    };
+    
+    if let Err(message) = body.validate(local_now(shop_timezone(&ctx.env).await).date()) {
+        return error_response(&message, 400);
+    }
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Waiver storage not available", 500),
+    };
+    
+    // Re-run the lookup rather than trusting the id: the credential is email + DOB
+    let original = match find_reusable_waiver(&db, &ctx, &body.email, &body.dob).await? {
+        Some(w) if w.id == body.waiver_id => w,
+        _ => return error_response(
+            "That waiver can no longer be reused. Please fill out a new waiver.",
+            409,
+        ),
+    };
+    
+    if let Some(code) = body.group_code.as_deref().filter(|c| !c.trim().is_empty()) {
+        if let Err(message) = check_group_code(&db, code, &body.rental_type, &body.adventure_date).await? {
+            return error_response(&message, 400);
+        }
+    }
+    
+    let record = WaiverRecord::confirmation_of(
+        auth::generate_id("wv")?,
+        &original,
+        &body,
+        client_ip,
+        user_agent,
+    );
+    
+    if record.minors.iter().any(|m| m.age.is_some_and(|a| a >= ADULT_AGE)) {
+        return error_response(
+            "A minor on your waiver is now 18 and must sign their own. Please fill out a new waiver.",
+            409,
+        );
+    }
+    
+    if let Err(e) = insert_waiver(&db, &record).await {
+        console_error!("Failed to store waiver confirmation: {}", e);
+        return error_response("Could not confirm your waiver. Please try again.", 500);
+    }
+    
+    let message = format!(
+        "Confirmed for {} on {}. Your waiver signed {} applies.",
+        record.rental_type, record.adventure_date, record.sign_date
+    );
+    signed_response(&ctx, &record, message).await
+}
+
+/// Newest original waiver for this signer that is on the current agreement and was
+/// submitted inside the admin-configured season window. The window is checked
+/// against the server-set `submitted_at`, never the signer's own `sign_date`.
+async fn find_reusable_waiver(
+    db: &D1Database,
+    ctx: &RouteContext<()>,
+    email: &str,
+    dob: &str,
+) -> Result<Option<WaiverRecord>> {
+    let settings = match ctx.kv("SETTINGS") {
+        Ok(kv) => cache::get_cached::<WaiverSettings>(&kv, "waiver_settings").await.unwrap_or_default(),
+        Err(_) => WaiverSettings::default(),
+    };
+    let dob = match parse_date(dob) {
+        Some(d) if settings.reuse_enabled => d.format("%Y-%m-%d").to_string(),
+        _ => return Ok(None),
+    };
+    
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    let text_version = active_text_version(db, today).await?;
+    let valid_since = settings
+        .reuse_valid_since(today)
+        .format("%Y-%m-%d")
+        .to_string();
+    
+    query!(
+        db,
+        "SELECT * FROM waivers
+         WHERE email = ?1 AND dob = ?2 AND reuses_waiver_id IS NULL
+           AND text_version_id = ?3 AND legal_text_hash = ?4 AND submitted_at >= ?5
+         ORDER BY submitted_at DESC LIMIT 1",
+        &email.trim().to_lowercase(),
+        &dob,
+        &text_version.id,
+        &text_version.content_hash,
+        &valid_since,
+    )?
+    .first::<WaiverRecord>(None)
+    .await
+}
+
+/// Per-IP hourly counter in CACHE KV; true once `limit` is reached
+pub(crate) async fn rate_limited(ctx: &RouteContext<()>, rate_key: &str, limit: i32) -> bool {
+    let kv = match ctx.kv("CACHE") {
+        Ok(kv) => kv,
+        Err(_) => return false,
+    };
+    
+    match cache::get_cached::<i32>(&kv, rate_key).await {
+        Some(c) if c >= limit => true,
+        Some(c) => {
+            let _ = cache::set_cached(&kv, rate_key, &(c + 1), 3600).await;
+            false
+        }
+        None => {
+            let _ = cache::set_cached(&kv, rate_key, &1, 3600).await;
+            false
+        }
+    }
+}
+
+// ============================================
+// ADMIN: SEARCH & CHECK-IN
+// ============================================
+
+#[derive(Deserialize)]
+struct CountRow {
+    total: i64,
+    headcount: Option<i64>,
+}
+
+/// GET /api/admin/waivers?q=&name=&email=&date=today&rental_type=&checked_in=&sort=&order=&page=&per_page=
+pub async fn list_waivers(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let url = req.url()?;
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let query = WaiverSearchQuery::from_pairs(
+        pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+        local_now(shop_timezone(&ctx.env).await).date(),
+    );
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+    
+    let (where_clause, params) = query.where_clause();
+    let bindings: Vec<JsValue> = params.iter().map(|p| JsValue::from(p.as_str())).collect();
+    
+    let count_sql = format!(
+        "SELECT COUNT(*) AS total, SUM(group_size) AS headcount FROM waivers {}",
+        where_clause
+    );
+    let counts = db.prepare(count_sql)
+        .bind(&bindings)?
+        .first::<CountRow>(None)
+        .await?
+        .unwrap_or(CountRow { total: 0, headcount: None });
+    
+    let list_sql = format!(
+        "SELECT {} FROM waivers {} ORDER BY {} LIMIT {} OFFSET {}",
+        WAIVER_SUMMARY_COLUMNS,
+        where_clause,
+        query.sort.order_by(query.descending),
+        query.per_page,
+        query.offset()
+    );
+    let waivers = db.prepare(list_sql)
+        .bind(&bindings)?
+        .all()
+        .await?
+        .results::<WaiverSummary>()?;
+    
+    let total_pages = (counts.total as u32).div_ceil(query.per_page);
+    
+    json_response(&WaiverSearchResponse {
+        waivers,
+        total: counts.total,
+        total_headcount: counts.headcount.unwrap_or(0),
+        page: query.page,
+        per_page: query.per_page,
+        total_pages,
+    }, 200)
+}
+
+/// GET /api/admin/waivers/:id - full record including the signature image
+pub async fn get_waiver(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+    
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing waiver id", 400),
+    };
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+    
+    match find_waiver(&db, &id).await? {
+        Some(waiver) => json_response(&waiver, 200),
+        None => Response::error("Waiver not found", 404),
+    }
+}
+
+/// POST /api/admin/waivers/:id/checkin - body `{"checked_in": false}` undoes a check-in
+pub async fn check_in_waiver(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+    
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing waiver id", 400),
+    };
+    
+    // Body is optional; an empty POST means "check in"
+    let body: CheckInRequest = req.json().await.unwrap_or_default();
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+    
+    let (checked_in_at, checked_in_by) = if body.checked_in {
+        (Some(chrono::Utc::now().to_rfc3339()), Some(session.username))
+    } else {
+        (None, None)
+    };
+    
+    let result = query!(
+        &db,
+        "UPDATE waivers SET checked_in_at = ?1, checked_in_by = ?2 WHERE id = ?3",
+        &checked_in_at,
+        &checked_in_by,
+        &id,
+    )?
+    .run()
+    .await?;
+    
+    let changes = result.meta()?.and_then(|m| m.changes).unwrap_or(0);
+    if changes == 0 {
+        return Response::error("Waiver not found", 404);
+    }
+    
+    json_response(&serde_json::json!({
+        "success": true,
+        "id": id,
+        "checked_in_at": checked_in_at,
+        "checked_in_by": checked_in_by,
+    }), 200)
+}
+
+// ============================================
+// QR CONFIRMATION & SCAN CHECK-IN
+// ============================================
+
+/// Success response for a stored waiver: check-in QR code plus confirmation email
+async fn signed_response(ctx: &RouteContext<()>, record: &WaiverRecord, message: String) -> Result<Response> {
+    let token = checkin_token(ctx, &record.id);
+    let qr_svg = token.as_deref().and_then(qr::svg);
+    
+    if let Some(config) = EmailConfig::from_env(&ctx.env) {
+        let site_url = ctx.var("SITE_URL").map(|v| v.to_string()).unwrap_or_default();
+        let (subject, html, text) = confirmation_email(record, token.as_deref(), &site_url);
+        if let Err(e) = config.send(&record.email, &subject, &html, &text).await {
+            console_error!("Failed to send waiver confirmation to {}: {}", record.id, e);
+        }
+    }
+    
+    json_response(&WaiverResponse {
+        success: true,
+        message,
+        waiver_id: Some(record.id.clone()),
+        error: None,
+        confirmation_token: token,
+        qr_svg,
+    }, 200)
+}
+
+/// `RACW1:<waiver id>.<signature>`; None when WAIVER_TOKEN_SECRET is not configured
+fn checkin_token(ctx: &RouteContext<()>, waiver_id: &str) -> Option<String> {
+    let secret = ctx.secret("WAIVER_TOKEN_SECRET").ok()?.to_string();
+    Some(auth::sign_token(&secret, &format!("{}{}", CHECKIN_TOKEN_PREFIX, waiver_id)))
+}
+
+/// Waiver id from a scanned token, if the signature is ours
+fn verify_checkin_token(ctx: &RouteContext<()>, token: &str) -> Option<String> {
+    let secret = ctx.secret("WAIVER_TOKEN_SECRET").ok()?.to_string();
+    auth::verify_token(&secret, token)?
+        .strip_prefix(CHECKIN_TOKEN_PREFIX)
+        .map(|id| id.to_string())
+}
+
+fn confirmation_email(record: &WaiverRecord, token: Option<&str>, site_url: &str) -> (String, String, String) {
+    let subject = format!("Your waiver for {} on {}", record.rental_type, record.adventure_date);
+    let qr_url = token.and_then(|t| {
+        Url::parse_with_params(&format!("{}/api/waiver/qr", site_url.trim_end_matches('/')), &[("token", t)]).ok()
+    });
+    
+    let mut html = format!(
+        "<p>Hi {},</p>\
+         <p>Thanks for signing your liability waiver with Riverview Adventure Company. You're all set for \
+         <strong>{}</strong> on <strong>{}</strong> (group of {}).</p>",
+        escape_html(&record.first_name),
+        escape_html(&record.rental_type),
+        escape_html(&record.adventure_date),
+        record.group_size,
+    );
+    let mut text = format!(
+        "Hi {},\n\nThanks for signing your liability waiver with Riverview Adventure Company. \
+         You're all set for {} on {} (group of {}).\n",
+        record.first_name, record.rental_type, record.adventure_date, record.group_size,
+    );
+    
+    if let (Some(token), Some(qr_url)) = (token, qr_url) {
+        html.push_str(&format!(
+            "<p>Show this code at the launch for a quick check-in:</p>\
+             <p><img src=\"{}\" width=\"200\" height=\"200\" alt=\"Check-in QR code\"></p>\
+             <p style=\"font-family:monospace;font-size:12px\">{}</p>",
+            escape_html(qr_url.as_str()),
+            escape_html(token),
+        ));
+        text.push_str(&format!("\nYour check-in code: {}\n", token));
+    }
+    
+    html.push_str(&format!(
+        "<p>Waiver ID: {}<br>Questions? Call (608) 515-3456.</p>",
+        escape_html(&record.id)
+    ));
+    text.push_str(&format!("\nWaiver ID: {}\nQuestions? Call (608) 515-3456.\n", record.id));
+    
+    (subject, html, text)
+}
+
+/// GET /api/waiver/qr?token= - QR PNG for the confirmation email (valid tokens only).
+/// PNG rather than SVG: Gmail and Outlook don't display SVG images.
+pub async fn waiver_qr(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let url = req.url()?;
+    let token = url.query_pairs()
+        .find(|(k, _)| k == "token")
+        .map(|(_, v)| v.to_string())
+        .unwrap_or_default();
+    
+    if verify_checkin_token(&ctx, &token).is_none() {
+        return Response::error("Invalid check-in token", 404);
+    }
+    
+    let png = match qr::png(&token) {
+        Some(png) => png,
+        None => return Response::error("Could not render QR code", 500),
+    };
+    
+    let headers = Headers::new();
+    headers.set("Content-Type", "image/png")?;
+    headers.set("Cache-Control", "public, max-age=86400")?;
+    Ok(Response::from_bytes(png)?.with_headers(headers))
+}
+
+/// POST /api/admin/waivers/scan - verify a scanned token and check the waiver in (once)
+pub async fn scan_check_in(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+    
+    let body: ScanCheckInRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Missing token", 400),
+    };
+    
+    if ctx.secret("WAIVER_TOKEN_SECRET").is_err() {
+        return Response::error("Check-in tokens are not configured", 500);
+    }
+    let id = match verify_checkin_token(&ctx, &body.token) {
+        Some(id) => id,
+        None => return json_response(&serde_json::json!({
+            "success": false,
+            "status": "invalid",
+            "error": "This QR code is not a valid Riverview waiver.",
+        }), 400),
+    };
+    
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+    
+    // Conditional update: a screenshot shared around the group only works once
+    let now = chrono::Utc::now().to_rfc3339();
+    let result = query!(
+        &db,
+        "UPDATE waivers SET checked_in_at = ?1, checked_in_by = ?2 WHERE id = ?3 AND checked_in_at IS NULL",
+        &now,
+        &session.username,
+        &id,
+    )?
+    .run()
+    .await?;
+    let newly_checked_in = result.meta()?.and_then(|m| m.changes).unwrap_or(0) > 0;
+    
+    let sql = format!("SELECT {} FROM waivers WHERE id = ?1", WAIVER_SUMMARY_COLUMNS);
+    let waiver = match db.prepare(sql).bind(&[id.as_str().into()])?.first::<WaiverSummary>(None).await? {
+        Some(w) => w,
+        None => return Response::error("Waiver not found", 404),
+    };
+    
+    let mut warnings: Vec<String> = vec![];
+    let today = local_now(shop_timezone(&ctx.env).await).date().format("%Y-%m-%d").to_string();
+    if waiver.adventure_date != today {
+        warnings.push(format!("Waiver is for {}, not today", waiver.adventure_date));
+    }
+    if waiver.minor_age_flag {
+        warnings.push("A minor on this waiver is below the activity minimum age".to_string());
+    }
+    
+    if newly_checked_in {
+        json_response(&serde_json::json!({
+            "success": true,
+            "status": "checked_in",
+            "waiver": waiver,
+            "warnings": warnings,
+        }), 200)
+    } else {
+        json_response(&serde_json::json!({
+            "success": false,
+            "status": "already_used",
+            "error": format!(
+                "Already checked in at {} by {}",
+                waiver.checked_in_at.as_deref().unwrap_or("-"),
+                waiver.checked_in_by.as_deref().unwrap_or("staff")
+            ),
+            "waiver": waiver,
+            "warnings": warnings,
+        }), 409)
+    }
+}
+
+pub(crate) async fn find_waiver(db: &D1Database, id: &str) -> Result<Option<WaiverRecord>> {
+    query!(db, "SELECT * FROM waivers WHERE id = ?1", &id)?
+        .first::<WaiverRecord>(None)
+        .await
+}
+
+fn error_response(message: &str, status: u16) -> Result<Response> {
+    json_response(&WaiverResponse {
+        success: false,
+        message: message.to_string(),
+        waiver_id: None,
+        error: Some(message.to_string()),
+        confirmation_token: None,
+        qr_svg: None,
+    }, status)
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
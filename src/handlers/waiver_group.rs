@@ -0,0 +1,314 @@
+//! Group waiver links and signed-vs-expected progress
+
+use worker::*;
+use serde::Deserialize;
+use crate::handlers::admin::verify_session;
+use crate::handlers::settings::shop_timezone;
+use crate::handlers::waiver::rate_limited;
+use crate::models::*;
+use crate::utils::auth;
+
+/// Waiver `w` is its signer's latest in the group. Someone who signs again (or signs and then
+/// reuses a waiver) has several rows; only the newest counts toward the headcount.
+const LATEST_FOR_SIGNER: &str = "NOT EXISTS (
+    SELECT 1 FROM waivers newer
+    WHERE newer.group_code = w.group_code AND newer.email = w.email AND newer.dob = w.dob
+      AND (newer.submitted_at > w.submitted_at OR (newer.submitted_at = w.submitted_at AND newer.id > w.id)))";
+
+#[derive(Deserialize)]
+struct GroupCounts {
+    waivers_signed: u32,
+    signed_members: u32,
+}
+
+/// POST /api/waiver/group - a group leader creates a shareable link
+pub async fn create_group(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if rate_limited(&ctx, &format!("rate:waiver-group:{}", client_ip), 10).await {
+        return error_response("Too many groups created. Please try again later.", 429);
+    }
+
+    let body: CreateWaiverGroupRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return error_response("Invalid group details", 400),
+    };
+
+    if let Err(message) = body.validate(local_now(shop_timezone(&ctx.env).await).date()) {
+        return error_response(&message, 400);
+    }
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Waiver storage not available", 500),
+    };
+
+    // Codes are short, so retry the rare collision instead of failing
+    let mut code = None;
+    for _ in 0..5 {
+        let candidate = auth::generate_code(GROUP_CODE_ALPHABET, GROUP_CODE_LENGTH)?;
+        if find_group(&db, &candidate).await?.is_none() {
+            code = Some(candidate);
+            break;
+        }
+    }
+    let code = match code {
+        Some(c) => c,
+        None => return error_response("Could not create a group link. Please try again.", 500),
+    };
+
+    let group = body.into_group(code);
+    let result = query!(
+        &db,
+        "INSERT INTO waiver_groups
+            (code, name, leader_name, leader_email, rental_type, adventure_date, expected_members, created_at, ip_address)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        &group.code,
+        &group.name,
+        &group.leader_name,
+        &group.leader_email,
+        &group.rental_type,
+        &group.adventure_date,
+        &group.expected_members,
+        &group.created_at,
+        &client_ip,
+    )?
+    .run()
+    .await;
+
+    if let Err(e) = result {
+        console_error!("Failed to store waiver group: {}", e);
+        return error_response("Could not create a group link. Please try again.", 500);
+    }
+
+    let origin = req.url()?.origin().ascii_serialization();
+    json_response(&serde_json::json!({
+        "success": true,
+        "code": group.code,
+        "url": format!("{}/g/{}", origin, group.code),
+        "group": WaiverGroupProgress::new(&group, 0, 0),
+    }), 200)
+}
+
+/// GET /api/waiver/group/:code - public progress for the waiver page banner
+pub async fn get_group_progress(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let code = match ctx.param("code").and_then(|c| normalize_group_code(c)) {
+        Some(c) => c,
+        None => return error_response("Group link not found", 404),
+    };
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return error_response("Waiver storage not available", 500),
+    };
+
+    match find_group(&db, &code).await? {
+        Some(group) => {
+            let counts = group_counts(&db, &code).await?;
+            json_response(&WaiverGroupProgress::new(&group, counts.waivers_signed, counts.signed_members), 200)
+        }
+        None => error_response("Group link not found", 404),
+    }
+}
+
+/// GET /g/:code - short link shared by the group leader
+pub async fn group_link(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let mut url = req.url()?;
+    url.set_path("/waiver");
+    let code = ctx.param("code").and_then(|c| normalize_group_code(c));
+    url.set_query(code.map(|c| format!("group={}", c)).as_deref());
+    Response::redirect(url)
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+#[derive(Deserialize)]
+struct GroupListRow {
+    code: String,
+    name: String,
+    leader_name: String,
+    leader_email: String,
+    rental_type: String,
+    adventure_date: String,
+    expected_members: u32,
+    created_at: String,
+    waivers_signed: u32,
+    signed_members: u32,
+}
+
+/// GET /api/admin/waiver-groups?date_from=&date_to= - defaults to today onward
+pub async fn list_groups(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    let mut date_from = today;
+    let mut date_to = today + chrono::Duration::days(365);
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "date" if value == "today" => { date_from = today; date_to = today; }
+            "date" => if let Some(d) = parse_date(&value) { date_from = d; date_to = d; },
+            "date_from" => if let Some(d) = parse_date(&value) { date_from = d; },
+            "date_to" => if let Some(d) = parse_date(&value) { date_to = d; },
+            _ => {}
+        }
+    }
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+
+    let sql = format!(
+        "SELECT g.code, g.name, g.leader_name, g.leader_email, g.rental_type, g.adventure_date,
+                g.expected_members, g.created_at,
+                COUNT(w.id) AS waivers_signed,
+                COALESCE(SUM(1 + json_array_length(w.minors)), 0) AS signed_members
+         FROM waiver_groups g LEFT JOIN waivers w ON w.group_code = g.code AND {}
+         WHERE g.adventure_date >= ?1 AND g.adventure_date <= ?2
+         GROUP BY g.code
+         ORDER BY g.adventure_date, g.name
+         LIMIT 500",
+        LATEST_FOR_SIGNER
+    );
+    let rows = query!(
+        &db,
+        &sql,
+        &date_from.format("%Y-%m-%d").to_string(),
+        &date_to.format("%Y-%m-%d").to_string(),
+    )?
+    .all()
+    .await?
+    .results::<GroupListRow>()?;
+
+    let groups: Vec<WaiverGroupListItem> = rows
+        .into_iter()
+        .map(|row| {
+            let group = WaiverGroup {
+                code: row.code,
+                name: row.name,
+                leader_name: row.leader_name,
+                leader_email: row.leader_email,
+                rental_type: row.rental_type,
+                adventure_date: row.adventure_date,
+                expected_members: row.expected_members,
+                created_at: row.created_at,
+            };
+            WaiverGroupListItem {
+                progress: WaiverGroupProgress::new(&group, row.waivers_signed, row.signed_members),
+                leader_name: group.leader_name,
+                leader_email: group.leader_email,
+                created_at: group.created_at,
+            }
+        })
+        .collect();
+
+    json_response(&serde_json::json!({ "groups": groups }), 200)
+}
+
+/// GET /api/admin/waiver-groups/:code - progress plus every member's waiver
+pub async fn get_group(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let code = match ctx.param("code").and_then(|c| normalize_group_code(c)) {
+        Some(c) => c,
+        None => return Response::error("Group not found", 404),
+    };
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+
+    let group = match find_group(&db, &code).await? {
+        Some(g) => g,
+        None => return Response::error("Group not found", 404),
+    };
+
+    let sql = format!(
+        "SELECT {} FROM waivers WHERE group_code = ?1 ORDER BY last_name, first_name",
+        WAIVER_SUMMARY_COLUMNS
+    );
+    let waivers = db.prepare(sql)
+        .bind(&[code.as_str().into()])?
+        .all()
+        .await?
+        .results::<WaiverSummary>()?;
+
+    let counts = group_counts(&db, &code).await?;
+    json_response(&WaiverGroupDetail {
+        progress: WaiverGroupProgress::new(&group, counts.waivers_signed, counts.signed_members),
+        leader_name: group.leader_name,
+        leader_email: group.leader_email,
+        created_at: group.created_at,
+        waivers,
+    }, 200)
+}
+
+/// Check that a waiver may attach to the group in its submission.
+/// The outer error is storage; the inner one is a message for the signer.
+pub(crate) async fn check_group_code(
+    db: &D1Database,
+    raw_code: &str,
+    rental_type: &str,
+    adventure_date: &str,
+) -> Result<std::result::Result<(), String>> {
+    let not_found = Err("That group link was not found. Check the code with your group leader.".to_string());
+    let code = match normalize_group_code(raw_code) {
+        Some(c) => c,
+        None => return Ok(not_found),
+    };
+    Ok(match find_group(db, &code).await? {
+        Some(group) => group.accepts(rental_type, adventure_date),
+        None => not_found,
+    })
+}
+
+async fn find_group(db: &D1Database, code: &str) -> Result<Option<WaiverGroup>> {
+    query!(db, "SELECT * FROM waiver_groups WHERE code = ?1", &code)?
+        .first::<WaiverGroup>(None)
+        .await
+}
+
+async fn group_counts(db: &D1Database, code: &str) -> Result<GroupCounts> {
+    let sql = format!(
+        "SELECT COUNT(*) AS waivers_signed,
+                COALESCE(SUM(1 + json_array_length(w.minors)), 0) AS signed_members
+         FROM waivers w WHERE w.group_code = ?1 AND {}",
+        LATEST_FOR_SIGNER
+    );
+    Ok(query!(
+        db,
+        &sql,
+        &code,
+    )?
+    .first::<GroupCounts>(None)
+    .await?
+    .unwrap_or(GroupCounts { waivers_signed: 0, signed_members: 0 }))
+}
+
+fn error_response(message: &str, status: u16) -> Result<Response> {
+    json_response(&serde_json::json!({
+        "success": false,
+        "error": message,
+    }), status)
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
@@ -0,0 +1,220 @@
+//! Signed waiver PDF documents (single download and bulk ZIP export)
+
+use worker::*;
+use crate::handlers::admin::verify_session;
+use crate::handlers::waiver::find_waiver;
+use crate::handlers::waiver_text::find_text_version;
+use crate::models::*;
+use crate::utils::pdf::{Font, PdfBuilder};
+use crate::utils::{png, zip};
+
+/// Most waivers returned by a single bulk export
+const MAX_EXPORT_WAIVERS: u32 = 500;
+
+/// GET /api/admin/waivers/:id/pdf
+pub async fn download_waiver_pdf(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing waiver id", 400),
+    };
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+
+    let waiver = match find_waiver(&db, &id).await? {
+        Some(w) => w,
+        None => return Response::error("Waiver not found", 404),
+    };
+
+    let text_version = signed_text_version(&db, &waiver).await?;
+    let pdf = render_waiver_pdf(&waiver, &text_version);
+    file_response(pdf, "application/pdf", &waiver_file_name(&waiver))
+}
+
+/// GET /api/admin/waivers/export?date_from=YYYY-MM-DD&date_to=YYYY-MM-DD
+pub async fn export_waivers_zip(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let mut date_from: Option<String> = None;
+    let mut date_to: Option<String> = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "date_from" => date_from = parse_date(&value).map(|d| d.format("%Y-%m-%d").to_string()),
+            "date_to" => date_to = parse_date(&value).map(|d| d.format("%Y-%m-%d").to_string()),
+            _ => {}
+        }
+    }
+
+    let (date_from, date_to) = match (date_from, date_to) {
+        (Some(from), Some(to)) if from <= to => (from, to),
+        _ => return Response::error("Provide date_from and date_to as YYYY-MM-DD", 400),
+    };
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+
+    let waivers = query!(
+        &db,
+        "SELECT * FROM waivers WHERE adventure_date >= ?1 AND adventure_date <= ?2
+         ORDER BY adventure_date, last_name, first_name LIMIT ?3",
+        &date_from,
+        &date_to,
+        &(MAX_EXPORT_WAIVERS + 1),
+    )?
+    .all()
+    .await?
+    .results::<WaiverRecord>()?;
+
+    if waivers.is_empty() {
+        return Response::error("No waivers found for that date range", 404);
+    }
+    if waivers.len() > MAX_EXPORT_WAIVERS as usize {
+        return Response::error(
+            format!("More than {} waivers in range; narrow the dates", MAX_EXPORT_WAIVERS),
+            400,
+        );
+    }
+
+    let mut versions: std::collections::HashMap<Option<i64>, WaiverTextVersion> = Default::default();
+    let mut archive = zip::ZipWriter::new();
+    for waiver in &waivers {
+        if let std::collections::hash_map::Entry::Vacant(entry) = versions.entry(waiver.text_version_id) {
+            entry.insert(signed_text_version(&db, waiver).await?);
+        }
+        let pdf = render_waiver_pdf(waiver, &versions[&waiver.text_version_id]);
+        archive.add_file(&waiver_file_name(waiver), &pdf);
+    }
+
+    file_response(
+        archive.finish(),
+        "application/zip",
+        &format!("waivers-{}-to-{}.zip", date_from, date_to),
+    )
+}
+
+/// The agreement revision a waiver was signed under (rows from before versioning are version 1)
+async fn signed_text_version(db: &D1Database, waiver: &WaiverRecord) -> Result<WaiverTextVersion> {
+    let id = waiver.text_version_id.unwrap_or(1);
+    Ok(find_text_version(db, id).await?.unwrap_or_else(WaiverTextVersion::builtin))
+}
+
+/// Build the signed waiver document from a stored record and the text it was signed under
+pub fn render_waiver_pdf(waiver: &WaiverRecord, text_version: &WaiverTextVersion) -> Vec<u8> {
+    let full_name = format!("{} {}", waiver.first_name, waiver.last_name);
+    let mut pdf = PdfBuilder::new(
+        &format!("Liability Release - {}", full_name),
+        &format!("Riverview Adventure Company  |  Waiver {}", waiver.id),
+    );
+
+    pdf.text("RIVERVIEW ADVENTURE COMPANY", Font::Bold, 16.0);
+    pdf.text("740 Water St., Sauk City, WI 53583", Font::Regular, 9.0);
+    pdf.gap(6.0);
+    pdf.text("Release of Liability, Assumption of Risk and Indemnification Agreement", Font::Bold, 12.0);
+    pdf.rule();
+
+    pdf.heading("Adventure", 11.0);
+    pdf.field("Activity", &waiver.rental_type);
+    pdf.field("Adventure date", &waiver.adventure_date);
+    pdf.field("Group size", &waiver.group_size.to_string());
+
+    pdf.heading("Participant", 11.0);
+    pdf.field("Name", &full_name);
+    pdf.field("Date of birth", &waiver.dob);
+    pdf.field("Email", &waiver.email);
+    pdf.field("Phone", waiver.phone.as_deref().unwrap_or("-"));
+    pdf.field("Emergency contact", &format!("{} ({})", waiver.emergency_contact, waiver.emergency_phone));
+
+    pdf.heading("Minors", 11.0);
+    if !waiver.minors.is_empty() {
+        for minor in &waiver.minors {
+            let age = minor.age.map(|a| format!(", age {} on adventure date", a)).unwrap_or_default();
+            let flag = if minor.below_minimum_age { "  [BELOW ACTIVITY MINIMUM AGE]" } else { "" };
+            pdf.field(&minor.name, &format!("born {}{}{}", minor.dob, age, flag));
+        }
+    } else if let Some(info) = waiver.minors_info.as_deref().filter(|_| waiver.has_minors) {
+        pdf.text(info, Font::Regular, 10.0);
+    } else {
+        pdf.text("No minors listed.", Font::Regular, 10.0);
+    }
+
+    pdf.heading("Release Agreement", 11.0);
+    for paragraph in &text_version.paragraphs {
+        pdf.text(paragraph, Font::Regular, 9.0);
+        pdf.gap(4.0);
+    }
+    pdf.text(&text_version.acknowledgement, Font::Bold, 9.0);
+
+    pdf.heading("Signature", 11.0);
+    match png::decode_data_url(&waiver.signature) {
+        Ok(image) => pdf.image(image, 300.0, 100.0),
+        Err(e) => pdf.text(&format!("[Signature image could not be rendered: {}]", e), Font::Regular, 9.0),
+    }
+    pdf.field("Signed by", &full_name);
+    pdf.field("Date signed", &waiver.sign_date);
+
+    pdf.heading("Audit Trail", 11.0);
+    pdf.field("Waiver ID", &waiver.id);
+    pdf.field("Submitted at (UTC)", &waiver.submitted_at);
+    pdf.field("IP address", &waiver.ip_address);
+    pdf.field("User agent", if waiver.user_agent.is_empty() { "-" } else { &waiver.user_agent });
+    pdf.field("Agreement version", &format!(
+        "#{} {} (effective {})",
+        text_version.id, text_version.label, text_version.effective_date
+    ));
+    if let Some(original_id) = &waiver.reuses_waiver_id {
+        pdf.field("Season confirmation of", original_id);
+    }
+    pdf.field("Agreement SHA-256", &waiver.legal_text_hash);
+    if text_version.content_hash != waiver.legal_text_hash || !text_version.verify_hash() {
+        pdf.text(
+            "WARNING: the agreement text above does not match the hash recorded at signing.",
+            Font::Bold,
+            9.0,
+        );
+    }
+    if let Some(checked_in_at) = &waiver.checked_in_at {
+        pdf.field("Checked in", &format!(
+            "{} by {}",
+            checked_in_at,
+            waiver.checked_in_by.as_deref().unwrap_or("staff")
+        ));
+    }
+
+    pdf.finish()
+}
+
+fn waiver_file_name(waiver: &WaiverRecord) -> String {
+    let slug = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect::<String>()
+            .trim_matches('-')
+            .to_string()
+    };
+    format!(
+        "waiver-{}-{}-{}-{}.pdf",
+        waiver.adventure_date,
+        slug(&waiver.last_name),
+        slug(&waiver.first_name),
+        waiver.id
+    )
+}
+
+fn file_response(body: Vec<u8>, content_type: &str, file_name: &str) -> Result<Response> {
+    let headers = Headers::new();
+    headers.set("Content-Type", content_type)?;
+    headers.set("Content-Disposition", &format!("attachment; filename=\"{}\"", file_name))?;
+    headers.set("Cache-Control", "no-store")?;
+    Ok(Response::from_bytes(body)?.with_headers(headers))
+}
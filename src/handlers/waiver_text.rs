@@ -0,0 +1,124 @@
+//! Versioned waiver legal text
+
+use worker::*;
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+
+/// GET /api/waiver/text - the agreement currently in force, rendered by /waiver
+pub async fn get_active_text(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let version = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => active_text_version(&db, local_now(shop_timezone(&ctx.env).await).date()).await?,
+        Err(_) => WaiverTextVersion::builtin(),
+    };
+
+    let json = serde_json::to_string(&version).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "public, max-age=60")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers))
+}
+
+/// GET /api/admin/waiver-text - every published version, newest first
+pub async fn list_text_versions(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+
+    let versions = db
+        .prepare("SELECT * FROM waiver_text_versions ORDER BY effective_date DESC, id DESC")
+        .all()
+        .await?
+        .results::<WaiverTextVersion>()?;
+    let active_id = active_text_version(&db, local_now(shop_timezone(&ctx.env).await).date()).await?.id;
+
+    json_response(&serde_json::json!({
+        "active_version_id": active_id,
+        "versions": versions,
+    }))
+}
+
+/// POST /api/admin/waiver-text - publish a new version (takes effect on its effective date)
+pub async fn publish_text_version(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let body: PublishWaiverTextRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid waiver text data", 400),
+    };
+
+    if let Err(message) = body.validate(local_now(shop_timezone(&ctx.env).await).date()) {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_WAIVERS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Waiver storage not available", 500),
+    };
+
+    let paragraphs = body.normalized_paragraphs();
+    let acknowledgement = body.acknowledgement.trim().to_string();
+    let content_hash = legal_text_hash(&paragraphs, &acknowledgement);
+    let paragraphs_json = serde_json::to_string(&paragraphs).map_err(|e| Error::from(e.to_string()))?;
+
+    let version = query!(
+        &db,
+        "INSERT INTO waiver_text_versions
+            (label, paragraphs, acknowledgement, content_hash, effective_date, created_at, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         RETURNING *",
+        &body.label.trim(),
+        &paragraphs_json,
+        &acknowledgement,
+        &content_hash,
+        &body.effective_date.trim(),
+        &chrono::Utc::now().to_rfc3339(),
+        &session.username,
+    )?
+    .first::<WaiverTextVersion>(None)
+    .await?;
+
+    match version {
+        Some(v) => json_response(&serde_json::json!({"success": true, "version": v})),
+        None => Response::error("Failed to publish waiver text", 500),
+    }
+}
+
+/// The newest version whose effective date has arrived by the shop-local `today`
+/// (built-in text if none are stored)
+pub(crate) async fn active_text_version(db: &D1Database, today: chrono::NaiveDate) -> Result<WaiverTextVersion> {
+    let today = today.format("%Y-%m-%d").to_string();
+    let version = query!(
+        db,
+        "SELECT * FROM waiver_text_versions WHERE effective_date <= ?1
+         ORDER BY effective_date DESC, id DESC LIMIT 1",
+        &today,
+    )?
+    .first::<WaiverTextVersion>(None)
+    .await?;
+
+    Ok(version.unwrap_or_else(WaiverTextVersion::builtin))
+}
+
+pub(crate) async fn find_text_version(db: &D1Database, id: i64) -> Result<Option<WaiverTextVersion>> {
+    query!(db, "SELECT * FROM waiver_text_versions WHERE id = ?1", &id)?
+        .first::<WaiverTextVersion>(None)
+        .await
+}
+
+fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    Ok(Response::ok(json)?.with_headers(headers))
+}
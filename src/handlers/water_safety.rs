@@ -0,0 +1,103 @@
+//! Stops water bookings and flags same-day trips when flow or NWS alerts turn unsafe
+
+use chrono::NaiveDate;
+use worker::*;
+use crate::handlers::admin::verify_session;
+use crate::handlers::flow::current_flow;
+use crate::handlers::settings::shop_timezone;
+use crate::handlers::weather::active_alerts;
+use crate::models::*;
+
+/// Hazards right now. Feed failures are logged and treated as no hazard, so an
+/// outage at USGS or NWS doesn't shut down bookings.
+pub(crate) async fn current_hazards(env: &Env) -> Vec<WaterHazard> {
+    let flow = match current_flow(env).await {
+        Ok(f) => Some(f),
+        Err(e) => {
+            console_error!("Flow check for bookings failed: {}", e);
+            None
+        }
+    };
+    let alerts = match active_alerts(env).await {
+        Ok(a) => a,
+        Err(e) => {
+            console_error!("Alert check for bookings failed: {}", e);
+            vec![]
+        }
+    };
+    water_hazards(flow.as_ref(), &alerts, local_now(shop_timezone(env).await).date())
+}
+
+/// Move confirmed bookings on `today` (shop-local) for the hazard's services to at-risk.
+/// Bookings staff cleared for this same hazard aren't flagged again; a new or worse
+/// hazard flags them. Returns how many changed.
+pub(crate) async fn flag_same_day_bookings(db: &D1Database, hazard: &WaterHazard, today: NaiveDate) -> Result<u32> {
+    if hazard.services.is_empty() {
+        return Ok(0);
+    }
+    let water: Vec<String> = hazard.services.iter().map(|s| format!("'{}'", s.slug())).collect();
+    let sql = format!(
+        "UPDATE bookings SET status = 'at_risk', at_risk_reason = ?1, at_risk_at = ?2, at_risk_key = ?4, updated_at = ?2
+         WHERE status = 'confirmed' AND booking_date = ?3 AND service IN ({})
+           AND (risk_cleared_key IS NULL OR risk_cleared_key != ?4)",
+        water.join(", ")
+    );
+    let result = query!(
+        db,
+        &sql,
+        &hazard.reason,
+        &chrono::Utc::now().to_rfc3339(),
+        &today.format("%Y-%m-%d").to_string(),
+        &hazard.key,
+    )?
+    .run()
+    .await?;
+    Ok(result.meta()?.and_then(|m| m.changes).unwrap_or(0) as u32)
+}
+
+/// Cron trigger: check conditions and flag today's trips
+pub async fn run_scheduled(env: &Env) -> Result<()> {
+    let hazards = current_hazards(env).await;
+    let today = local_now(shop_timezone(env).await).date();
+    for hazard in hazards.iter().filter(|h| today <= h.until) {
+        let db = env.d1("DB_BOOKINGS")?;
+        let flagged = flag_same_day_bookings(&db, hazard, today).await?;
+        if flagged > 0 {
+            console_log!("Flagged {} water bookings at risk: {}", flagged, hazard.reason);
+        }
+    }
+    Ok(())
+}
+
+/// POST /api/admin/bookings/conditions-check - run the check now
+pub async fn check_conditions(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Booking storage not available", 500),
+    };
+
+    let hazards = current_hazards(&ctx.env).await;
+    let today = local_now(shop_timezone(&ctx.env).await).date();
+    let mut flagged = 0;
+    for hazard in hazards.iter().filter(|h| today <= h.until) {
+        flagged += flag_same_day_bookings(&db, hazard, today).await?;
+    }
+
+    json_response(&serde_json::json!({
+        "safe": hazards.is_empty(),
+        "hazards": hazards,
+        "flagged": flagged,
+    }))
+}
+
+fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    Ok(Response::ok(json)?.with_headers(headers))
+}
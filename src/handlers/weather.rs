@@ -65,50 +65,54 @@ pub async fn get_weather_data(_req: Request, ctx: RouteContext<()>) -> Result<Re
 }
 
 pub async fn get_weather_alerts(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let zone = ctx.var("NWS_ZONE")
+    match active_alerts(&ctx.env).await {
+        Ok(alerts) => json_response(&alerts),
+        Err(e) => Response::error(format!("Failed to fetch alerts: {}", e), 500),
+    }
+}
+
+/// Active NWS alerts for the configured zone, cached for 5 minutes
+pub(crate) async fn active_alerts(env: &Env) -> Result<Vec<WeatherAlert>> {
+    let zone = env.var("NWS_ZONE")
         .map(|v| v.to_string())
         .unwrap_or_else(|_| "WIZ061".to_string());
     
     // Try cache first
     let cache_key = format!("alerts:{}", zone);
     
-    if let Ok(kv) = ctx.kv("CACHE") {
+    if let Ok(kv) = env.kv("CACHE") {
         if let Some(cached) = cache::get_cached::<Vec<WeatherAlert>>(&kv, &cache_key).await {
-            return json_response(&cached);
+            return Ok(cached);
         }
     }
     
     // Fetch from NWS
-    match weather_api::fetch_nws_alerts(&zone).await {
-        Ok(response) => {
-            let alerts: Vec<WeatherAlert> = response.features.iter().map(|f| {
-                WeatherAlert {
-                    id: f.id.clone(),
-                    event: f.properties.event.clone(),
-                    headline: f.properties.headline.clone().unwrap_or_default(),
-                    description: f.properties.description.clone().unwrap_or_default(),
-                    severity: AlertSeverity::from(f.properties.severity.as_deref().unwrap_or("unknown")),
-                    urgency: f.properties.urgency.clone().unwrap_or_default(),
-                    effective: f.properties.effective.clone().unwrap_or_default(),
-                    expires: f.properties.expires.clone().unwrap_or_default(),
-                    sender: f.properties.sender_name.clone().unwrap_or_default(),
-                }
-            }).collect();
-            
-            // Cache for 5 minutes
-            if let Ok(kv) = ctx.kv("CACHE") {
-                let _ = cache::set_cached(&kv, &cache_key, &alerts, 300).await;
-            }
-            
-            json_response(&alerts)
+    let response = weather_api::fetch_nws_alerts(&zone).await?;
+    let alerts: Vec<WeatherAlert> = response.features.iter().map(|f| {
+        WeatherAlert {
+            id: f.id.clone(),
+            event: f.properties.event.clone(),
+            headline: f.properties.headline.clone().unwrap_or_default(),
+            description: f.properties.description.clone().unwrap_or_default(),
+            severity: AlertSeverity::from(f.properties.severity.as_deref().unwrap_or("unknown")),
+            urgency: f.properties.urgency.clone().unwrap_or_default(),
+            effective: f.properties.effective.clone().unwrap_or_default(),
+            expires: f.properties.expires.clone().unwrap_or_default(),
+            sender: f.properties.sender_name.clone().unwrap_or_default(),
         }
-        Err(e) => Response::error(format!("Failed to fetch alerts: {}", e), 500),
+    }).collect();
+    
+    // Cache for 5 minutes
+    if let Ok(kv) = env.kv("CACHE") {
+        let _ = cache::set_cached(&kv, &cache_key, &alerts, 300).await;
     }
+    
+    Ok(alerts)
 }
 
 fn json_response<T: serde::Serialize>(data: &T) -> Result<Response> {
     let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
-    let mut headers = Headers::new();
+    let headers = Headers::new();
     headers.set("Content-Type", "application/json")?;
     headers.set("Cache-Control", "public, max-age=300")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
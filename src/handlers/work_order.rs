@@ -0,0 +1,446 @@
+//! Bike repair work orders: customers open them from the repair catalog and follow them by
+//! ticket; mechanics work the queue by promised date
+
+use worker::*;
+use crate::api::email::{escape_html, EmailConfig};
+use crate::handlers::admin::{admin_session, verify_session};
+use crate::handlers::store::load_repairs;
+use crate::handlers::waiver::rate_limited;
+use crate::handlers::settings::shop_timezone;
+use crate::models::*;
+use crate::utils::auth;
+
+/// POST /api/repairs/orders - open a work order and issue its ticket
+pub async fn create_order(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if rate_limited(&ctx, &format!("rate:repair-order:{}", client_ip), 10).await {
+        return Response::error("Too many requests. Please call the shop.", 429);
+    }
+
+    let body: WorkOrderRequest = match req.json().await {
+        Ok(b) => b,
+        Err(_) => return Response::error("Invalid repair request", 400),
+    };
+
+    let catalog = load_repairs(&ctx.env).await;
+    if let Err(message) = body.validate(&catalog) {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Repair storage not available", 500),
+    };
+
+    let services = catalog_services(&body.repair_service_ids, &catalog);
+    let estimate = RepairEstimate::for_services(&services);
+    let promised_on = promised_date(&services, local_now(shop_timezone(&ctx.env).await).date()).format("%Y-%m-%d").to_string();
+    let service_ids = serde_json::to_string(&body.repair_service_ids).map_err(|e| Error::from(e.to_string()))?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    // Retry on the (unlikely) ticket collision
+    for _ in 0..3 {
+        let id = auth::generate_id("wo")?;
+        let ticket = format!("{}{}", TICKET_PREFIX, auth::generate_code(GROUP_CODE_ALPHABET, TICKET_CODE_LENGTH)?);
+        let statements = vec![
+            query!(
+                &db,
+                "INSERT INTO work_orders (id, ticket, status, customer_name, customer_email, customer_phone, bike,
+                    problem, repair_service_ids, estimate_min, estimate_max, promised_on, ip_address,
+                    created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?14)",
+                &id,
+                &ticket,
+                &WorkOrderStatus::Received,
+                &body.name.trim(),
+                &body.email.trim().to_lowercase(),
+                &body.phone.trim(),
+                &body.bike.trim(),
+                &clean(&body.problem),
+                &service_ids,
+                &estimate.min,
+                &estimate.max,
+                &promised_on,
+                &client_ip,
+                &now,
+            )?,
+            insert_event(&db, &id, None, WorkOrderStatus::Received, None, None, &now)?,
+        ];
+        match db.batch(statements).await {
+            Ok(_) => {}
+            Err(e) if e.to_string().contains("UNIQUE") => continue,
+            Err(e) => return Err(e),
+        }
+
+        let order = match find_order(&db, &id).await? {
+            Some(o) => o,
+            None => return Response::error("Could not open your repair request", 500),
+        };
+        send_order_email(&ctx.env, &order, &services, OrderEmail::Received).await;
+        return json_response(&serde_json::json!({
+            "success": true,
+            "message": format!(
+                "Your ticket is {}. Bring your bike to the shop; we expect to have it done by {}.",
+                order.ticket, order.promised_on
+            ),
+            "order": WorkOrderLookup::new(&order, &[], &catalog),
+        }), 200);
+    }
+
+    Response::error("Could not open your repair request. Please try again.", 500)
+}
+
+/// GET /api/repairs/orders/:ticket - public status of a work order
+pub async fn lookup_order(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let client_ip = req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Tickets are hard to guess; keep it that way
+    if rate_limited(&ctx, &format!("rate:repair-lookup:{}", client_ip), 30).await {
+        return Response::error("Too many lookups. Please try again later.", 429);
+    }
+
+    let not_found = || Response::error("We couldn't find that ticket. Check the number and try again.", 404);
+    let ticket = match ctx.param("ticket").and_then(|t| normalize_ticket(t)) {
+        Some(t) => t,
+        None => return not_found(),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Repair storage not available", 500),
+    };
+
+    let order = match query!(&db, "SELECT * FROM work_orders WHERE ticket = ?1", &ticket)?
+        .first::<WorkOrder>(None)
+        .await?
+    {
+        Some(o) => o,
+        None => return not_found(),
+    };
+
+    let events = order_events(&db, &order.id).await?;
+    let catalog = load_repairs(&ctx.env).await;
+    json_response(&WorkOrderLookup::new(&order, &events, &catalog), 200)
+}
+
+// ============================================
+// ADMIN
+// ============================================
+
+/// GET /api/admin/repairs?status=&all=1 - the mechanics' queue, soonest promised first.
+/// Picked-up orders are left out unless asked for by status or `all`.
+pub async fn list_orders(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let url = req.url()?;
+    let mut status = None;
+    let mut all = false;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "status" if !value.is_empty() => {
+                match serde_json::from_value::<WorkOrderStatus>(serde_json::Value::String(value.to_string())) {
+                    Ok(s) => status = Some(s),
+                    Err(_) => return Response::error("Unknown work order status", 400),
+                }
+            }
+            "all" => all = value == "1" || value == "true",
+            _ => {}
+        }
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Repair storage not available", 500),
+    };
+
+    let orders = query!(
+        &db,
+        "SELECT * FROM work_orders
+         WHERE (?1 IS NULL OR status = ?1) AND (?1 IS NOT NULL OR ?2 = 1 OR status != 'picked-up')
+         ORDER BY promised_on, created_at
+         LIMIT 500",
+        &status,
+        &(all as i32),
+    )?
+    .all()
+    .await?
+    .results::<WorkOrder>()?;
+
+    let catalog = load_repairs(&ctx.env).await;
+    json_response(&WorkOrderQueue::build(orders, &catalog, local_now(shop_timezone(&ctx.env).await).date()), 200)
+}
+
+/// GET /api/admin/repairs/:id - an order with its services and history
+pub async fn get_order(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    if !verify_session(&req, &ctx).await {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing work order id", 400),
+    };
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Repair storage not available", 500),
+    };
+
+    match find_order(&db, &id).await? {
+        Some(order) => {
+            let detail = order_detail(&db, &ctx.env, order).await?;
+            json_response(&detail, 200)
+        }
+        None => Response::error("Work order not found", 404),
+    }
+}
+
+/// POST /api/admin/repairs/:id - move an order along and edit what the shop tracks on it.
+/// The customer is emailed when it becomes ready.
+pub async fn update_order(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let session = match admin_session(&req, &ctx).await {
+        Some(s) => s,
+        None => return Response::error("Unauthorized", 401),
+    };
+
+    let id = match ctx.param("id") {
+        Some(id) => id.to_string(),
+        None => return Response::error("Missing work order id", 400),
+    };
+
+    let update: WorkOrderUpdate = match req.json().await {
+        Ok(u) => u,
+        Err(_) => return Response::error("Invalid work order update", 400),
+    };
+
+    let catalog = load_repairs(&ctx.env).await;
+    if let Err(message) = update.validate(&catalog) {
+        return Response::error(message, 400);
+    }
+
+    let db = match ctx.d1("DB_BOOKINGS") {
+        Ok(db) => db,
+        Err(_) => return Response::error("Repair storage not available", 500),
+    };
+
+    let order = match find_order(&db, &id).await? {
+        Some(o) => o,
+        None => return Response::error("Work order not found", 404),
+    };
+
+    let status = match update.status {
+        Some(next) if next != order.status && !order.status.can_move_to(next) => {
+            return Response::error(
+                format!("A work order can't go from {} to {}", order.status.label(), next.label()),
+                409,
+            );
+        }
+        Some(next) => next,
+        None => order.status,
+    };
+    let moved = status != order.status;
+
+    let (service_ids, estimate) = match &update.repair_service_ids {
+        Some(ids) => (
+            serde_json::to_string(ids).map_err(|e| Error::from(e.to_string()))?,
+            RepairEstimate::for_services(&catalog_services(ids, &catalog)),
+        ),
+        None => (order.repair_service_ids.clone(), order.estimate()),
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let pick = |new: &Option<String>, old: &Option<String>| match new {
+        Some(_) => clean(new),
+        None => old.clone(),
+    };
+    let stamp = |reached: bool, old: &Option<String>| match old {
+        Some(_) => old.clone(),
+        None => reached.then(|| now.clone()),
+    };
+
+    // The status guard keeps two staff moving the same order from both winning
+    let mut statements = vec![query!(
+        &db,
+        "UPDATE work_orders SET status = ?1, repair_service_ids = ?2, estimate_min = ?3, estimate_max = ?4,
+            promised_on = ?5, final_price = ?6, mechanic = ?7, customer_message = ?8, staff_notes = ?9,
+            ready_at = ?10, picked_up_at = ?11, updated_at = ?12
+         WHERE id = ?13 AND status = ?14",
+        &status,
+        &service_ids,
+        &estimate.min,
+        &estimate.max,
+        &update.promised_on.as_deref().and_then(parse_date).map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| order.promised_on.clone()),
+        &update.final_price.map(round_cents).or(order.final_price),
+        &pick(&update.mechanic, &order.mechanic),
+        &pick(&update.customer_message, &order.customer_message),
+        &pick(&update.staff_notes, &order.staff_notes),
+        &stamp(status == WorkOrderStatus::Ready, &order.ready_at),
+        &stamp(status == WorkOrderStatus::PickedUp, &order.picked_up_at),
+        &now,
+        &id,
+        &order.status,
+    )?];
+    let note = clean(&update.note);
+    if moved || note.is_some() {
+        statements.push(insert_event(
+            &db,
+            &id,
+            Some(order.status),
+            status,
+            note.as_deref(),
+            Some(&session.username),
+            &now,
+        )?);
+    }
+    let results = db.batch(statements).await?;
+    let changed = match results.first() {
+        Some(r) => r.meta()?.and_then(|m| m.changes).unwrap_or(0),
+        None => 0,
+    };
+    if changed == 0 {
+        return Response::error("This work order was just changed by someone else. Reload and try again.", 409);
+    }
+
+    let order = match find_order(&db, &id).await? {
+        Some(o) => o,
+        None => return Response::error("Work order not found", 404),
+    };
+    if moved && status == WorkOrderStatus::Ready {
+        let services = catalog_services(&order.repair_service_ids(), &catalog);
+        send_order_email(&ctx.env, &order, &services, OrderEmail::Ready).await;
+    }
+
+    let detail = order_detail(&db, &ctx.env, order).await?;
+    json_response(&serde_json::json!({"success": true, "order": detail}), 200)
+}
+
+async fn find_order(db: &D1Database, id: &str) -> Result<Option<WorkOrder>> {
+    query!(db, "SELECT * FROM work_orders WHERE id = ?1", &id)?
+        .first::<WorkOrder>(None)
+        .await
+}
+
+async fn order_events(db: &D1Database, id: &str) -> Result<Vec<WorkOrderEvent>> {
+    query!(
+        db,
+        "SELECT * FROM work_order_events WHERE work_order_id = ?1 ORDER BY created_at, id",
+        &id,
+    )?
+    .all()
+    .await?
+    .results::<WorkOrderEvent>()
+}
+
+async fn order_detail(db: &D1Database, env: &Env, order: WorkOrder) -> Result<WorkOrderDetail> {
+    let events = order_events(db, &order.id).await?;
+    Ok(WorkOrderDetail {
+        repair_services: catalog_services(&order.repair_service_ids(), &load_repairs(env).await),
+        order,
+        events,
+    })
+}
+
+fn insert_event(
+    db: &D1Database,
+    order_id: &str,
+    from: Option<WorkOrderStatus>,
+    status: WorkOrderStatus,
+    note: Option<&str>,
+    created_by: Option<&str>,
+    now: &str,
+) -> Result<D1PreparedStatement> {
+    query!(
+        db,
+        "INSERT INTO work_order_events (id, work_order_id, from_status, status, note, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        &auth::generate_id("woe")?,
+        &order_id,
+        &from,
+        &status,
+        &note,
+        &created_by,
+        &now,
+    )
+}
+
+enum OrderEmail {
+    Received,
+    Ready,
+}
+
+/// Email the customer about their order. Failures are logged, not returned.
+async fn send_order_email(env: &Env, order: &WorkOrder, services: &[RepairService], kind: OrderEmail) {
+    let Some(config) = EmailConfig::from_env(env) else {
+        return;
+    };
+    let names = services.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ");
+
+    let (subject, lead) = match kind {
+        OrderEmail::Received => (
+            format!("Repair request received ({})", order.ticket),
+            format!(
+                "We have your repair request for your {}. Bring the bike to the shop; we expect to have it done by {}.",
+                order.bike, order.promised_on
+            ),
+        ),
+        OrderEmail::Ready => (
+            format!("Your bike is ready for pickup ({})", order.ticket),
+            format!("Your {} is ready. Stop by the shop during open hours to pick it up.", order.bike),
+        ),
+    };
+    let status_url = format!(
+        "{}/repairs?ticket={}",
+        env.var("SITE_URL").map(|v| v.to_string()).unwrap_or_default().trim_end_matches('/'),
+        order.ticket
+    );
+    let price = match order.final_price {
+        Some(p) => format!("Total: ${:.2}", p),
+        None => format!("Estimate: {}", order.estimate().display()),
+    };
+
+    let html = format!(
+        "<p>Hi {},</p><p>{}</p>\
+         <p>Ticket: <strong>{}</strong><br>Services: {}<br>{}</p>\
+         <p>Keep your ticket number to <a href=\"{}\">check on the repair</a>. Questions? Call (608) 515-3456.</p>",
+        escape_html(&order.customer_name),
+        escape_html(&lead),
+        escape_html(&order.ticket),
+        escape_html(&names),
+        escape_html(&price),
+        escape_html(&status_url),
+    );
+    let text = format!(
+        "Hi {},\n\n{}\n\nTicket: {}\nServices: {}\n{}\n\n\
+         Keep your ticket number to check on the repair: {}\nQuestions? Call (608) 515-3456.\n",
+        order.customer_name, lead, order.ticket, names, price, status_url,
+    );
+
+    if let Err(e) = config.send(&order.customer_email, &subject, &html, &text).await {
+        console_error!("Failed to send work order email for {}: {}", order.id, e);
+    }
+}
+
+fn clean(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn json_response<T: serde::Serialize>(data: &T, status: u16) -> Result<Response> {
+    let json = serde_json::to_string(data).map_err(|e| Error::from(e.to_string()))?;
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Cache-Control", "no-store")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+    Ok(Response::ok(json)?.with_headers(headers).with_status(status))
+}
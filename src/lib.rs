@@ -26,6 +26,9 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .get_async("/ebikes", |req, ctx| async move { handlers::pages::ebikes(req, ctx).await })
         .get_async("/contact", |req, ctx| async move { handlers::pages::contact(req, ctx).await })
         .get_async("/waiver", |req, ctx| async move { handlers::pages::waiver(req, ctx).await })
+        .get_async("/book", |req, ctx| async move { handlers::pages::book(req, ctx).await })
+        .get_async("/repairs", |req, ctx| async move { handlers::pages::repairs(req, ctx).await })
+        .get_async("/g/:code", |req, ctx| async move { handlers::waiver_group::group_link(req, ctx).await })
         .get_async("/howitworks", |req, ctx| async move { handlers::pages::how_it_works(req, ctx).await })
         .get_async("/gallery", |req, ctx| async move { handlers::pages::gallery(req, ctx).await })
         .get_async("/conditions", |req, ctx| async move { handlers::pages::conditions(req, ctx).await })
@@ -44,6 +47,8 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         // PUBLIC API ENDPOINTS
         // ============================================
         .get_async("/api/flow", |req, ctx| async move { handlers::flow::get_flow_data(req, ctx).await })
+        .get_async("/api/flow/network", |req, ctx| async move { handlers::flow::get_network(req, ctx).await })
+        .get_async("/api/flow/forecast", |req, ctx| async move { handlers::flow::get_forecast(req, ctx).await })
         .get_async("/api/weather", |req, ctx| async move { handlers::weather::get_weather_data(req, ctx).await })
         .get_async("/api/alerts", |req, ctx| async move { handlers::weather::get_weather_alerts(req, ctx).await })
         .get_async("/api/moon", |req, ctx| async move { handlers::moon::get_moon_phase(req, ctx).await })
@@ -52,6 +57,9 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .get_async("/api/services", |req, ctx| async move { handlers::services::get_services(req, ctx).await })
         .get_async("/api/bikes", |req, ctx| async move { handlers::store::get_bikes(req, ctx).await })
         .get_async("/api/repairs", |req, ctx| async move { handlers::store::get_repairs(req, ctx).await })
+        .post_async("/api/repairs/orders", |req, ctx| async move { handlers::work_order::create_order(req, ctx).await })
+        .get_async("/api/repairs/orders/:ticket", |req, ctx| async move { handlers::work_order::lookup_order(req, ctx).await })
+        .get_async("/api/pricing", |req, ctx| async move { handlers::pricing::get_pricing(req, ctx).await })
         .get_async("/api/settings/public", |req, ctx| async move { handlers::settings::get_public_settings(req, ctx).await })
         
         // ============================================
@@ -74,20 +82,107 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .get_async("/api/admin/settings", |req, ctx| async move { handlers::admin::get_settings(req, ctx).await })
         .post_async("/api/admin/settings", |req, ctx| async move { handlers::admin::update_settings(req, ctx).await })
         .post_async("/api/admin/services", |req, ctx| async move { handlers::admin::update_services(req, ctx).await })
+        .get_async("/api/admin/thresholds", |req, ctx| async move { handlers::admin::get_thresholds(req, ctx).await })
         .post_async("/api/admin/thresholds", |req, ctx| async move { handlers::admin::update_thresholds(req, ctx).await })
         .post_async("/api/admin/content", |req, ctx| async move { handlers::admin::update_content(req, ctx).await })
         .get_async("/api/admin/analytics", |req, ctx| async move { handlers::admin::get_analytics(req, ctx).await })
+        .get_async("/api/admin/waivers", |req, ctx| async move { handlers::waiver::list_waivers(req, ctx).await })
+        .get_async("/api/admin/waivers/export", |req, ctx| async move { handlers::waiver_pdf::export_waivers_zip(req, ctx).await })
+        .get_async("/api/admin/waivers/:id", |req, ctx| async move { handlers::waiver::get_waiver(req, ctx).await })
+        .get_async("/api/admin/waivers/:id/pdf", |req, ctx| async move { handlers::waiver_pdf::download_waiver_pdf(req, ctx).await })
+        .get_async("/api/admin/waiver-text", |req, ctx| async move { handlers::waiver_text::list_text_versions(req, ctx).await })
+        .post_async("/api/admin/waiver-text", |req, ctx| async move { handlers::waiver_text::publish_text_version(req, ctx).await })
+        .post_async("/api/admin/waivers/:id/checkin", |req, ctx| async move { handlers::waiver::check_in_waiver(req, ctx).await })
+        .post_async("/api/admin/waivers/scan", |req, ctx| async move { handlers::waiver::scan_check_in(req, ctx).await })
+        .get_async("/api/admin/waiver-settings", |req, ctx| async move { handlers::admin::get_waiver_settings(req, ctx).await })
+        .post_async("/api/admin/waiver-settings", |req, ctx| async move { handlers::admin::update_waiver_settings(req, ctx).await })
+        .get_async("/api/admin/waiver-groups", |req, ctx| async move { handlers::waiver_group::list_groups(req, ctx).await })
+        .get_async("/api/admin/waiver-groups/:code", |req, ctx| async move { handlers::waiver_group::get_group(req, ctx).await })
+        .get_async("/api/admin/bookings", |req, ctx| async move { handlers::booking::list_bookings(req, ctx).await })
+        .get_async("/api/admin/bookings/:id", |req, ctx| async move { handlers::booking::get_booking(req, ctx).await })
+        .post_async("/api/admin/bookings/:id", |req, ctx| async move { handlers::booking::update_booking(req, ctx).await })
+        .post_async("/api/admin/bookings/:id/cancel", |req, ctx| async move { handlers::booking::cancel_booking(req, ctx).await })
+        .post_async("/api/admin/bookings/:id/clear-risk", |req, ctx| async move { handlers::booking::clear_risk(req, ctx).await })
+        .post_async("/api/admin/bookings/:id/shuttle", |req, ctx| async move { handlers::shuttle::assign_shuttle(req, ctx).await })
+        .get_async("/api/admin/bookings/:id/payments", |req, ctx| async move { handlers::payment::list_payments(req, ctx).await })
+        .post_async("/api/admin/bookings/conditions-check", |req, ctx| async move { handlers::water_safety::check_conditions(req, ctx).await })
+        .get_async("/api/admin/shuttle/manifest", |req, ctx| async move { handlers::shuttle::get_manifest(req, ctx).await })
+        .get_async("/api/admin/shuttle-schedule", |req, ctx| async move { handlers::admin::get_shuttle_schedule(req, ctx).await })
+        .post_async("/api/admin/shuttle-schedule", |req, ctx| async move { handlers::admin::update_shuttle_schedule(req, ctx).await })
+        .get_async("/api/admin/booking-schedule", |req, ctx| async move { handlers::admin::get_booking_schedule(req, ctx).await })
+        .post_async("/api/admin/booking-schedule", |req, ctx| async move { handlers::admin::update_booking_schedule(req, ctx).await })
+        .get_async("/api/admin/pricing", |req, ctx| async move { handlers::admin::get_price_list(req, ctx).await })
+        .post_async("/api/admin/pricing", |req, ctx| async move { handlers::admin::update_price_list(req, ctx).await })
+        .get_async("/api/admin/promos", |req, ctx| async move { handlers::promo::list_promos(req, ctx).await })
+        .post_async("/api/admin/promos", |req, ctx| async move { handlers::promo::create_promo(req, ctx).await })
+        .get_async("/api/admin/promos/report", |req, ctx| async move { handlers::promo::promo_report(req, ctx).await })
+        .get_async("/api/admin/promos/:code", |req, ctx| async move { handlers::promo::get_promo(req, ctx).await })
+        .post_async("/api/admin/promos/:code", |req, ctx| async move { handlers::promo::update_promo(req, ctx).await })
+        .post_async("/api/admin/promos/:code/delete", |req, ctx| async move { handlers::promo::delete_promo(req, ctx).await })
+        .get_async("/api/admin/gift-cards", |req, ctx| async move { handlers::gift_card::list_gift_cards(req, ctx).await })
+        .post_async("/api/admin/gift-cards", |req, ctx| async move { handlers::gift_card::issue_gift_card(req, ctx).await })
+        .get_async("/api/admin/gift-cards/ledger", |req, ctx| async move { handlers::gift_card::ledger_report(req, ctx).await })
+        .post_async("/api/admin/gift-cards/redeem", |req, ctx| async move { handlers::gift_card::redeem_walk_up(req, ctx).await })
+        .get_async("/api/admin/gift-cards/:id", |req, ctx| async move { handlers::gift_card::get_gift_card(req, ctx).await })
+        .post_async("/api/admin/gift-cards/:id/adjust", |req, ctx| async move { handlers::gift_card::adjust_gift_card(req, ctx).await })
+        .post_async("/api/admin/gift-cards/:id/void", |req, ctx| async move { handlers::gift_card::void_gift_card(req, ctx).await })
+        .get_async("/api/admin/fleet", |req, ctx| async move { handlers::fleet::get_fleet(req, ctx).await })
+        .post_async("/api/admin/fleet/categories/:category", |req, ctx| async move { handlers::fleet::update_category(req, ctx).await })
+        .post_async("/api/admin/fleet/assets", |req, ctx| async move { handlers::fleet::create_asset(req, ctx).await })
+        .post_async("/api/admin/fleet/assets/:id", |req, ctx| async move { handlers::fleet::update_asset(req, ctx).await })
+        .get_async("/api/admin/fleet/assets/:id/maintenance", |req, ctx| async move { handlers::maintenance::asset_log(req, ctx).await })
+        .post_async("/api/admin/fleet/assets/:id/maintenance", |req, ctx| async move { handlers::maintenance::create_record(req, ctx).await })
+        .get_async("/api/admin/maintenance", |req, ctx| async move { handlers::maintenance::list_records(req, ctx).await })
+        .get_async("/api/admin/maintenance/overdue", |req, ctx| async move { handlers::maintenance::overdue_report(req, ctx).await })
+        .post_async("/api/admin/maintenance/:id", |req, ctx| async move { handlers::maintenance::update_record(req, ctx).await })
+        .get_async("/api/admin/service-intervals", |req, ctx| async move { handlers::admin::get_service_intervals(req, ctx).await })
+        .post_async("/api/admin/service-intervals", |req, ctx| async move { handlers::admin::update_service_intervals(req, ctx).await })
+        .get_async("/api/admin/river-network", |req, ctx| async move { handlers::admin::get_river_network(req, ctx).await })
+        .post_async("/api/admin/river-network", |req, ctx| async move { handlers::admin::update_river_network(req, ctx).await })
+        .post_async("/api/admin/rentals", |req, ctx| async move { handlers::rental::check_out(req, ctx).await })
+        .get_async("/api/admin/rentals/out", |req, ctx| async move { handlers::rental::currently_out(req, ctx).await })
+        .get_async("/api/admin/rentals/reconciliation", |req, ctx| async move { handlers::rental::reconciliation(req, ctx).await })
+        .get_async("/api/admin/rentals/:id", |req, ctx| async move { handlers::rental::get_rental(req, ctx).await })
+        .post_async("/api/admin/rentals/:id/checkin", |req, ctx| async move { handlers::rental::check_in(req, ctx).await })
+        .get_async("/api/admin/repairs", |req, ctx| async move { handlers::work_order::list_orders(req, ctx).await })
+        .get_async("/api/admin/repairs/:id", |req, ctx| async move { handlers::work_order::get_order(req, ctx).await })
+        .post_async("/api/admin/repairs/:id", |req, ctx| async move { handlers::work_order::update_order(req, ctx).await })
         
         // ============================================
         // CONTACT FORM
         // ============================================
         .post_async("/api/contact", |req, ctx| async move { handlers::contact::submit_form(req, ctx).await })
         
+        // ============================================
+        // LIABILITY WAIVERS
+        // ============================================
+        .post_async("/api/waiver", |req, ctx| async move { handlers::waiver::submit_waiver(req, ctx).await })
+        .get_async("/api/waiver/text", |req, ctx| async move { handlers::waiver_text::get_active_text(req, ctx).await })
+        .get_async("/api/waiver/qr", |req, ctx| async move { handlers::waiver::waiver_qr(req, ctx).await })
+        .post_async("/api/waiver/lookup", |req, ctx| async move { handlers::waiver::lookup_waiver(req, ctx).await })
+        .post_async("/api/waiver/confirm", |req, ctx| async move { handlers::waiver::confirm_waiver(req, ctx).await })
+        .post_async("/api/waiver/group", |req, ctx| async move { handlers::waiver_group::create_group(req, ctx).await })
+        .get_async("/api/waiver/group/:code", |req, ctx| async move { handlers::waiver_group::get_group_progress(req, ctx).await })
+        
+        // ============================================
+        // RESERVATIONS
+        // ============================================
+        .get_async("/api/book/availability", |req, ctx| async move { handlers::booking::get_availability(req, ctx).await })
+        .post_async("/api/book/hold", |req, ctx| async move { handlers::booking::create_hold(req, ctx).await })
+        .post_async("/api/book", |req, ctx| async move { handlers::booking::confirm_booking(req, ctx).await })
+        .get_async("/api/book/reschedule", |req, ctx| async move { handlers::booking::get_reschedule(req, ctx).await })
+        .post_async("/api/book/reschedule", |req, ctx| async move { handlers::booking::reschedule_booking(req, ctx).await })
+        .post_async("/api/promo/validate", |req, ctx| async move { handlers::promo::validate_promo(req, ctx).await })
+        .post_async("/api/gift-cards/balance", |req, ctx| async move { handlers::gift_card::check_balance(req, ctx).await })
+        .post_async("/api/payments/intent", |req, ctx| async move { handlers::payment::create_payment(req, ctx).await })
+        .post_async("/api/payments/webhook", |req, ctx| async move { handlers::payment::handle_webhook(req, ctx).await })
+        .get_async("/api/fleet/availability", |req, ctx| async move { handlers::fleet::get_availability(req, ctx).await })
+        
         // ============================================
         // CORS PREFLIGHT
         // ============================================
         .options("/api/*path", |_, _| {
-            let mut headers = Headers::new();
+            let headers = Headers::new();
             headers.set("Access-Control-Allow-Origin", "*")?;
             headers.set("Access-Control-Allow-Methods", "GET, POST, OPTIONS")?;
             headers.set("Access-Control-Allow-Headers", "Content-Type, Authorization, X-TOTP-Code")?;
@@ -98,3 +193,18 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .run(req, env)
         .await
 }
+
+#[event(scheduled)]
+async fn scheduled(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+    console_error_panic_hook::set_once();
+
+    if let Err(e) = handlers::water_safety::run_scheduled(&env).await {
+        console_error!("Booking conditions check failed: {}", e);
+    }
+
+    match handlers::booking_email::send_reminders(&env).await {
+        Ok(sent) if sent > 0 => console_log!("Sent {} booking reminders", sent),
+        Ok(_) => {}
+        Err(e) => console_error!("Booking reminders failed: {}", e),
+    }
+}
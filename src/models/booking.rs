@@ -0,0 +1,745 @@
+//! Reservations: bookable services, slot schedule, holds and confirmed bookings
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use super::flow::{FlowData, FlowStatus, RiverActivity};
+use super::payment::BookingPaymentStatus;
+use super::pricing::{round_cents, PriceList, PriceQuote};
+use super::services::{ServiceState, ServicesConfig, ServiceStatus};
+use super::shuttle::ShuttleRunAvailability;
+use super::waiver::{de_json, parse_date};
+use super::weather::{AlertSeverity, WeatherAlert};
+
+pub const MAX_BOOKING_PARTY: u32 = 50;
+/// Booking and rental times are shop wall-clock times in `site_settings.general.timezone`;
+/// this zone is used when that setting is missing or not a tz database name
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::America::Chicago;
+/// How far ahead the public booking page accepts reservations
+pub const MAX_BOOKING_DAYS_AHEAD: i64 = 180;
+
+/// Services linked from the "Book Now" buttons (`/book?service=...`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum BookingService {
+    KayakSingle,
+    KayakTandem,
+    Canoe,
+    Tubing,
+    Bike,
+    EbikeRental,
+}
+
+impl BookingService {
+    pub const ALL: [BookingService; 6] = [
+        BookingService::KayakSingle,
+        BookingService::KayakTandem,
+        BookingService::Canoe,
+        BookingService::Tubing,
+        BookingService::Bike,
+        BookingService::EbikeRental,
+    ];
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|s| s.slug() == slug.trim())
+    }
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            BookingService::KayakSingle => "kayak-single",
+            BookingService::KayakTandem => "kayak-tandem",
+            BookingService::Canoe => "canoe",
+            BookingService::Tubing => "tubing",
+            BookingService::Bike => "bike",
+            BookingService::EbikeRental => "ebike-rental",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BookingService::KayakSingle => "Single Kayak",
+            BookingService::KayakTandem => "Tandem Kayak",
+            BookingService::Canoe => "Canoe",
+            BookingService::Tubing => "River Tubing",
+            BookingService::Bike => "Standard Bike",
+            BookingService::EbikeRental => "E-Bike Rental",
+        }
+    }
+
+    /// People one unit (boat, tube, bike) carries
+    pub fn seats_per_unit(&self) -> u32 {
+        match self {
+            BookingService::KayakTandem => 2,
+            BookingService::Canoe => 3,
+            _ => 1,
+        }
+    }
+
+    /// Units needed to carry a party
+    pub fn units_for(&self, party_size: u32) -> u32 {
+        party_size.div_ceil(self.seats_per_unit())
+    }
+
+    /// Services that put customers on the river
+    pub fn on_water(&self) -> bool {
+        !matches!(self, BookingService::Bike | BookingService::EbikeRental)
+    }
+
+    /// The activity whose flow limits apply, for water trips
+    pub fn river_activity(&self) -> Option<RiverActivity> {
+        match self {
+            BookingService::KayakSingle | BookingService::KayakTandem => Some(RiverActivity::Kayak),
+            BookingService::Canoe => Some(RiverActivity::Canoe),
+            BookingService::Tubing => Some(RiverActivity::Tubing),
+            BookingService::Bike | BookingService::EbikeRental => None,
+        }
+    }
+
+    /// Tubers ride the shuttle to the put-in, one seat each
+    pub fn uses_shuttle(&self) -> bool {
+        matches!(self, BookingService::Tubing)
+    }
+
+    /// The service status card on /services that governs this booking
+    pub fn status<'a>(&self, services: &'a ServicesConfig) -> &'a ServiceStatus {
+        match self {
+            BookingService::KayakSingle | BookingService::KayakTandem | BookingService::Canoe => &services.kayak,
+            BookingService::Tubing => &services.tubing,
+            BookingService::Bike => &services.bike_rental,
+            BookingService::EbikeRental => &services.ebike,
+        }
+    }
+}
+
+/// Departure times for one service. Capacity comes from the in-service fleet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceSchedule {
+    pub service: BookingService,
+    pub enabled: bool,
+    /// Slot start times, HH:MM local
+    pub slot_times: Vec<String>,
+    pub duration_minutes: u32,
+    /// Most units sold online per window, keeping the rest of the fleet for walk-ups
+    #[serde(default, alias = "capacity")]
+    pub online_limit: Option<u32>,
+}
+
+/// Booking schedule for every service (SETTINGS KV `booking_schedule`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookingSchedule {
+    pub services: Vec<ServiceSchedule>,
+    /// Minutes a checkout hold reserves units before it lapses
+    pub hold_minutes: u32,
+}
+
+impl Default for BookingSchedule {
+    fn default() -> Self {
+        let schedule = |service, times: &[&str], duration_minutes| ServiceSchedule {
+            service,
+            enabled: true,
+            slot_times: times.iter().map(|t| t.to_string()).collect(),
+            duration_minutes,
+            online_limit: None,
+        };
+        Self {
+            services: vec![
+                schedule(BookingService::KayakSingle, &["09:00", "11:00", "13:00"], 240),
+                schedule(BookingService::KayakTandem, &["09:00", "11:00", "13:00"], 240),
+                schedule(BookingService::Canoe, &["09:00", "11:00", "13:00"], 240),
+                schedule(BookingService::Tubing, &["10:00", "12:00", "14:00"], 180),
+                schedule(BookingService::Bike, &["09:00", "13:00"], 240),
+                schedule(BookingService::EbikeRental, &["09:00", "13:00"], 240),
+            ],
+            hold_minutes: 15,
+        }
+    }
+}
+
+impl BookingSchedule {
+    pub fn for_service(&self, service: BookingService) -> Option<&ServiceSchedule> {
+        self.services.iter().find(|s| s.service == service)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hold_minutes == 0 || self.hold_minutes > 120 {
+            return Err("Hold time must be between 1 and 120 minutes".to_string());
+        }
+        for schedule in &self.services {
+            if schedule.duration_minutes == 0 || schedule.duration_minutes > 12 * 60 {
+                return Err(format!("{}: duration must be 1-720 minutes", schedule.service.label()));
+            }
+            for time in &schedule.slot_times {
+                let start = parse_time(time)
+                    .ok_or_else(|| format!("{}: invalid slot time {}", schedule.service.label(), time))?;
+                if start.overflowing_add_signed(schedule.duration()).1 != 0 {
+                    return Err(format!("{}: slot {} runs past midnight", schedule.service.label(), time));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ServiceSchedule {
+    pub fn duration(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.duration_minutes as i64)
+    }
+
+    /// Units bookable online per window given the in-service fleet
+    pub fn online_capacity(&self, in_service: u32) -> u32 {
+        self.online_limit.map_or(in_service, |limit| limit.min(in_service))
+    }
+
+    /// Start and end of a published slot on `date`
+    pub fn window(&self, date: NaiveDate, start_time: &str) -> Option<TimeWindow> {
+        let time = parse_time(start_time)?;
+        if !self.slot_times.iter().any(|t| parse_time(t) == Some(time)) {
+            return None;
+        }
+        let start = date.and_time(time);
+        Some(TimeWindow { start, end: start + self.duration() })
+    }
+}
+
+/// A booked stretch of time, compared as local `YYYY-MM-DDTHH:MM` strings in D1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl TimeWindow {
+    pub fn start_at(&self) -> String {
+        self.start.format("%Y-%m-%dT%H:%M").to_string()
+    }
+
+    pub fn end_at(&self) -> String {
+        self.end.format("%Y-%m-%dT%H:%M").to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BookingStatus {
+    /// Units reserved during checkout until `hold_expires_at`
+    Held,
+    Confirmed,
+    /// Confirmed, but river or weather conditions turned unsafe; staff should call
+    #[serde(rename = "at_risk")]
+    AtRisk,
+    Cancelled,
+}
+
+impl BookingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookingStatus::Held => "held",
+            BookingStatus::Confirmed => "confirmed",
+            BookingStatus::AtRisk => "at_risk",
+            BookingStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<Self> {
+        match value {
+            "held" => Some(BookingStatus::Held),
+            "confirmed" => Some(BookingStatus::Confirmed),
+            "at_risk" => Some(BookingStatus::AtRisk),
+            "cancelled" => Some(BookingStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// SQL predicate for rows that currently hold units (`?now` is an RFC 3339 timestamp)
+pub fn committed_booking_predicate(now_param: &str) -> String {
+    format!(
+        "(status IN ('confirmed', 'at_risk') OR (status = 'held' AND hold_expires_at > {}))",
+        now_param
+    )
+}
+
+/// Units a committed booking holds over its window
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommittedUnits {
+    pub start_at: String,
+    pub end_at: String,
+    pub quantity: u32,
+}
+
+/// Units committed at any point in `window`. Overlapping bookings are summed, which
+/// can over-count back-to-back rentals but never oversells.
+pub fn committed_in(rows: &[CommittedUnits], window: &TimeWindow) -> u32 {
+    let (start, end) = (window.start_at(), window.end_at());
+    rows.iter()
+        .filter(|r| r.start_at < end && r.end_at > start)
+        .map(|r| r.quantity)
+        .sum()
+}
+
+/// A row in the `bookings` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Booking {
+    pub id: String,
+    pub confirmation_number: Option<String>,
+    pub service: BookingService,
+    pub booking_date: String,
+    pub start_time: String,
+    pub start_at: String,
+    pub end_at: String,
+    pub quantity: u32,
+    pub party_size: u32,
+    pub status: BookingStatus,
+    #[serde(default)]
+    pub hold_expires_at: Option<String>,
+    #[serde(default)]
+    pub customer_name: Option<String>,
+    #[serde(default)]
+    pub customer_email: Option<String>,
+    #[serde(default)]
+    pub customer_phone: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub cancelled_at: Option<String>,
+    #[serde(default)]
+    pub cancelled_by: Option<String>,
+    #[serde(default)]
+    pub cancel_reason: Option<String>,
+    #[serde(default)]
+    pub at_risk_reason: Option<String>,
+    #[serde(default)]
+    pub at_risk_at: Option<String>,
+    /// `WaterHazard::key` of the hazard that flagged it
+    #[serde(default)]
+    pub at_risk_key: Option<String>,
+    #[serde(default)]
+    pub risk_cleared_at: Option<String>,
+    /// Staff cleared the booking for this hazard; other hazards still flag it
+    #[serde(default)]
+    pub risk_cleared_key: Option<String>,
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    #[serde(default)]
+    pub promo_discount: f64,
+    #[serde(default)]
+    pub gift_card_id: Option<String>,
+    #[serde(default)]
+    pub gift_card_amount: f64,
+    #[serde(default)]
+    pub amount_paid: f64,
+    #[serde(default)]
+    pub payment_status: BookingPaymentStatus,
+    #[serde(default)]
+    pub reminder_sent_at: Option<String>,
+    #[serde(default)]
+    pub shuttle_run: Option<String>,
+    #[serde(default)]
+    pub shuttle_departs: Option<String>,
+    #[serde(default)]
+    pub shuttle_put_in: Option<String>,
+    #[serde(default)]
+    pub shuttle_seats: u32,
+    /// What the customer was quoted at confirm; see [`Booking::quote`]
+    #[serde(default, deserialize_with = "de_json")]
+    pub confirmed_quote: Option<PriceQuote>,
+    /// Signed into reschedule links; replaced each time the booking moves
+    #[serde(default, skip_serializing)]
+    pub reschedule_nonce: Option<String>,
+}
+
+impl Booking {
+    /// Rental length in hours, from the stored window
+    pub fn hours(&self) -> f64 {
+        let parse = |v: &str| NaiveDateTime::parse_from_str(v, "%Y-%m-%dT%H:%M").ok();
+        match (parse(&self.start_at), parse(&self.end_at)) {
+            (Some(start), Some(end)) => (end - start).num_minutes() as f64 / 60.0,
+            _ => 0.0,
+        }
+    }
+
+    /// The quote saved at confirm. Holds, and bookings confirmed before quotes were
+    /// saved, are priced from `prices` instead.
+    pub fn quote(&self, prices: &PriceList) -> Option<PriceQuote> {
+        match &self.confirmed_quote {
+            Some(quote) => Some(quote.clone()),
+            None => self.live_quote(prices),
+        }
+    }
+
+    /// What the booking costs under `prices`, less any promo discount it was booked with,
+    /// and what's left to pay after its gift card
+    pub fn live_quote(&self, prices: &PriceList) -> Option<PriceQuote> {
+        let mut quote = prices.quote(
+            self.service,
+            parse_date(&self.booking_date)?,
+            self.party_size,
+            self.quantity,
+            self.hours(),
+        )?;
+        if self.promo_code.is_some() {
+            quote.promo_discount = self.promo_discount.min(quote.total);
+            quote.promo_code = self.promo_code.clone();
+            quote.total = round_cents(quote.total - quote.promo_discount);
+        }
+        quote.gift_card = self.gift_card_amount.min(quote.total);
+        quote.amount_due = round_cents(quote.total - quote.gift_card);
+        Some(quote)
+    }
+}
+
+/// Remaining units in one slot
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotAvailability {
+    pub start_time: String,
+    pub end_time: String,
+    pub capacity: u32,
+    pub committed: u32,
+    pub available: u32,
+    /// Shuttle departures for tubing; `available` is capped by the roomiest one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shuttle: Vec<ShuttleRunAvailability>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailabilityResponse {
+    pub service: BookingService,
+    pub label: String,
+    pub date: String,
+    pub open: bool,
+    pub message: Option<String>,
+    pub seats_per_unit: u32,
+    pub slots: Vec<SlotAvailability>,
+}
+
+/// POST /api/book/hold - reserve units while the customer enters details
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldRequest {
+    pub service: String,
+    pub date: String,
+    pub start_time: String,
+    pub party_size: u32,
+    /// Defaults to the fewest units that seat the party
+    #[serde(default)]
+    pub quantity: Option<u32>,
+    /// Tubing shuttle run; defaults to the earliest in the window with room
+    #[serde(default)]
+    pub shuttle_run: Option<String>,
+}
+
+/// Validated hold: service, window and unit count
+pub struct HoldPlan {
+    pub service: BookingService,
+    pub date: NaiveDate,
+    pub start_time: String,
+    pub window: TimeWindow,
+    pub quantity: u32,
+    pub party_size: u32,
+}
+
+impl HoldRequest {
+    /// `now` is shop-local wall-clock time
+    pub fn plan(&self, schedule: &BookingSchedule, now: NaiveDateTime) -> Result<HoldPlan, String> {
+        let today = now.date();
+        let service = BookingService::from_slug(&self.service)
+            .ok_or_else(|| "Please choose a service".to_string())?;
+        let service_schedule = schedule
+            .for_service(service)
+            .filter(|s| s.enabled)
+            .ok_or_else(|| format!("{} is not taking online bookings", service.label()))?;
+        let date = parse_date(&self.date).ok_or_else(|| "Please choose a valid date".to_string())?;
+        if date < today {
+            return Err("That date has already passed".to_string());
+        }
+        if date > today + chrono::Duration::days(MAX_BOOKING_DAYS_AHEAD) {
+            return Err(format!("Bookings open {} days ahead", MAX_BOOKING_DAYS_AHEAD));
+        }
+        let window = service_schedule
+            .window(date, &self.start_time)
+            .ok_or_else(|| "Please choose one of the listed times".to_string())?;
+        if window.start <= now {
+            return Err("That time has already started. Please choose a later time".to_string());
+        }
+        if self.party_size == 0 || self.party_size > MAX_BOOKING_PARTY {
+            return Err(format!("Party size must be between 1 and {}", MAX_BOOKING_PARTY));
+        }
+        let needed = service.units_for(self.party_size);
+        let quantity = self.quantity.unwrap_or(needed);
+        if quantity < needed {
+            return Err(format!(
+                "{} people need at least {} {}",
+                self.party_size,
+                needed,
+                service.label().to_lowercase()
+            ));
+        }
+        if quantity > self.party_size {
+            return Err("Cannot book more units than people".to_string());
+        }
+        Ok(HoldPlan {
+            service,
+            date,
+            start_time: window.start.format("%H:%M").to_string(),
+            window,
+            quantity,
+            party_size: self.party_size,
+        })
+    }
+}
+
+/// POST /api/book - confirm a hold with contact details
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookingRequest {
+    pub hold_id: String,
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    #[serde(default)]
+    pub gift_card_code: Option<String>,
+}
+
+impl BookingRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.phone.trim().is_empty() {
+            return Err("Please enter your name and phone number".to_string());
+        }
+        if !self.email.contains('@') || !self.email.contains('.') {
+            return Err("Please enter a valid email address".to_string());
+        }
+        if self.notes.as_deref().map(|n| n.len() > 1000).unwrap_or(false) {
+            return Err("Notes are limited to 1000 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// POST /api/book/reschedule - move a booking with the link from the reminder email
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RescheduleRequest {
+    pub token: String,
+    pub date: String,
+    pub start_time: String,
+    #[serde(default)]
+    pub shuttle_run: Option<String>,
+}
+
+/// POST /api/admin/bookings/:id - fields staff may change (omitted fields are kept)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BookingUpdate {
+    pub date: Option<String>,
+    pub start_time: Option<String>,
+    pub quantity: Option<u32>,
+    pub party_size: Option<u32>,
+    pub customer_name: Option<String>,
+    pub customer_email: Option<String>,
+    pub customer_phone: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CancelBookingRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Staff refunded a voided gift card's share some other way
+    #[serde(default)]
+    pub refund_elsewhere: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BookingResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub booking: Option<Booking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<PriceQuote>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Admin booking list filters
+#[derive(Debug, Clone, Default)]
+pub struct BookingSearchQuery {
+    pub q: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub service: Option<BookingService>,
+    pub status: Option<BookingStatus>,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl BookingSearchQuery {
+    pub fn from_pairs<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>, today: NaiveDate) -> Self {
+        let mut query = Self { page: 1, per_page: 50, ..Default::default() };
+        let day = |value: &str| -> Option<String> {
+            if value == "today" {
+                Some(today.format("%Y-%m-%d").to_string())
+            } else {
+                parse_date(value).map(|d| d.format("%Y-%m-%d").to_string())
+            }
+        };
+        for (key, value) in pairs {
+            match key {
+                "q" if !value.trim().is_empty() => query.q = Some(value.trim().to_string()),
+                "date" => {
+                    query.date_from = day(value);
+                    query.date_to = query.date_from.clone();
+                }
+                "date_from" => query.date_from = day(value),
+                "date_to" => query.date_to = day(value),
+                "service" => query.service = BookingService::from_slug(value),
+                "status" => query.status = BookingStatus::from_param(value),
+                "page" => query.page = value.parse().unwrap_or(1).max(1),
+                "per_page" => query.per_page = value.parse().unwrap_or(50).clamp(1, 200),
+                _ => {}
+            }
+        }
+        query
+    }
+
+    /// WHERE clause with `?N` placeholders and the matching bind values
+    pub fn where_clause(&self) -> (String, Vec<String>) {
+        let mut conditions: Vec<String> = vec![];
+        let mut params: Vec<String> = vec![];
+        let push = |sql: &str, value: String, params: &mut Vec<String>| {
+            params.push(value);
+            sql.replace('?', &format!("?{}", params.len()))
+        };
+
+        if let Some(q) = &self.q {
+            let like = format!("%{}%", q.to_lowercase());
+            let n = params.len() + 1;
+            params.push(like);
+            conditions.push(format!(
+                "(LOWER(customer_name) LIKE ?{n} OR LOWER(customer_email) LIKE ?{n} \
+                 OR customer_phone LIKE ?{n} OR LOWER(confirmation_number) LIKE ?{n})"
+            ));
+        }
+        if let Some(from) = &self.date_from {
+            conditions.push(push("booking_date >= ?", from.clone(), &mut params));
+        }
+        if let Some(to) = &self.date_to {
+            conditions.push(push("booking_date <= ?", to.clone(), &mut params));
+        }
+        if let Some(service) = self.service {
+            conditions.push(push("service = ?", service.slug().to_string(), &mut params));
+        }
+        match self.status {
+            Some(status) => conditions.push(push("status = ?", status.as_str().to_string(), &mut params)),
+            // Lapsed checkout holds are noise unless asked for
+            None => conditions.push("status != 'held'".to_string()),
+        }
+
+        if conditions.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), params)
+        }
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.page.saturating_sub(1).saturating_mul(self.per_page)
+    }
+}
+
+/// River or weather condition that stops bookings for `services` through `until`
+#[derive(Debug, Clone, Serialize)]
+pub struct WaterHazard {
+    /// Stable across sweeps while the same condition lasts, e.g. `flow:tubing`
+    pub key: String,
+    pub reason: String,
+    pub until: NaiveDate,
+    pub services: Vec<BookingService>,
+}
+
+/// Days a Danger flow reading blocks, counting today
+pub const FLOW_HAZARD_DAYS: i64 = 2;
+
+/// Hazards from the latest flow reading and active NWS alerts. A Danger reading
+/// blocks today and tomorrow, for every water trip or just the activities rated
+/// Danger against their own limits; an Extreme/Severe alert blocks all water trips
+/// through its expiry.
+pub fn water_hazards(flow: Option<&FlowData>, alerts: &[WeatherAlert], today: NaiveDate) -> Vec<WaterHazard> {
+    let water: Vec<BookingService> = BookingService::ALL.iter().copied().filter(|s| s.on_water()).collect();
+    let flow_until = today + chrono::Duration::days(FLOW_HAZARD_DAYS - 1);
+    let mut hazards = vec![];
+    if let Some(flow) = flow {
+        if flow.status == FlowStatus::Danger {
+            hazards.push(WaterHazard {
+                key: "flow".to_string(),
+                reason: format!("River flow is {:.0} cfs, above the safe limit for water trips", flow.flow_cfs),
+                until: flow_until,
+                services: water.clone(),
+            });
+        } else {
+            for activity in flow.activities.iter().filter(|a| a.status == FlowStatus::Danger) {
+                hazards.push(WaterHazard {
+                    key: format!("flow:{}", activity.activity.label().to_lowercase()),
+                    reason: format!(
+                        "River flow is {:.0} cfs, above the safe limit for {}",
+                        flow.flow_cfs,
+                        activity.activity.label().to_lowercase()
+                    ),
+                    until: flow_until,
+                    services: water.iter().copied().filter(|s| s.river_activity() == Some(activity.activity)).collect(),
+                });
+            }
+        }
+    }
+    for alert in alerts {
+        if !matches!(alert.severity, AlertSeverity::Extreme | AlertSeverity::Severe) {
+            continue;
+        }
+        let until = chrono::DateTime::parse_from_rfc3339(&alert.expires)
+            .map(|d| d.date_naive())
+            .unwrap_or(today)
+            .max(today);
+        hazards.push(WaterHazard {
+            key: format!("alert:{}", alert.event.to_lowercase()),
+            reason: format!("{} in effect for our area", alert.event),
+            until,
+            services: water.clone(),
+        });
+    }
+    hazards
+}
+
+/// First hazard covering `service` on `date`, if any
+pub fn hazard_on(hazards: &[WaterHazard], service: BookingService, date: NaiveDate) -> Option<&WaterHazard> {
+    hazards.iter().find(|h| date <= h.until && h.services.contains(&service))
+}
+
+/// Whether the /services status card allows booking
+pub fn service_open(status: &ServiceStatus) -> bool {
+    status.status != ServiceState::Closed
+}
+
+/// Wall-clock time at the shop
+pub fn local_now(tz: Tz) -> NaiveDateTime {
+    local_time(Utc::now(), tz)
+}
+
+/// `at` on the shop's wall clock, daylight saving included
+pub fn local_time(at: DateTime<Utc>, tz: Tz) -> NaiveDateTime {
+    at.with_timezone(&tz).naive_local()
+}
+
+/// Hours the shop's clock is ahead of UTC on `date` (-5.0 during CDT, -6.0 during CST)
+pub fn utc_offset_hours(date: NaiveDate, tz: Tz) -> f64 {
+    use chrono::{Offset, TimeZone};
+    tz.offset_from_utc_date(&date).fix().local_minus_utc() as f64 / 3600.0
+}
+
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
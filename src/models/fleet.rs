@@ -0,0 +1,162 @@
+//! Rental fleet: unit counts per category and individually tracked assets
+
+use serde::{Deserialize, Serialize};
+use super::booking::BookingService;
+use super::waiver::de_flag;
+
+/// Kinds of rental equipment we own
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FleetCategory {
+    Tube,
+    KayakSingle,
+    KayakTandem,
+    Canoe,
+    Bike,
+    Ebike,
+}
+
+impl FleetCategory {
+    pub const ALL: [FleetCategory; 6] = [
+        FleetCategory::Tube,
+        FleetCategory::KayakSingle,
+        FleetCategory::KayakTandem,
+        FleetCategory::Canoe,
+        FleetCategory::Bike,
+        FleetCategory::Ebike,
+    ];
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.slug() == slug.trim())
+    }
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            FleetCategory::Tube => "tube",
+            FleetCategory::KayakSingle => "kayak_single",
+            FleetCategory::KayakTandem => "kayak_tandem",
+            FleetCategory::Canoe => "canoe",
+            FleetCategory::Bike => "bike",
+            FleetCategory::Ebike => "ebike",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FleetCategory::Tube => "River Tubes",
+            FleetCategory::KayakSingle => "Single Kayaks",
+            FleetCategory::KayakTandem => "Tandem Kayaks",
+            FleetCategory::Canoe => "Canoes",
+            FleetCategory::Bike => "Standard Bikes",
+            FleetCategory::Ebike => "Rental E-Bikes",
+        }
+    }
+}
+
+impl BookingService {
+    /// Equipment a booking of this service takes out of the fleet
+    pub fn fleet_category(&self) -> FleetCategory {
+        match self {
+            BookingService::KayakSingle => FleetCategory::KayakSingle,
+            BookingService::KayakTandem => FleetCategory::KayakTandem,
+            BookingService::Canoe => FleetCategory::Canoe,
+            BookingService::Tubing => FleetCategory::Tube,
+            BookingService::Bike => FleetCategory::Bike,
+            BookingService::EbikeRental => FleetCategory::Ebike,
+        }
+    }
+}
+
+/// An individually identified unit (hull number, frame serial, tube tag)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetAsset {
+    pub id: String,
+    pub category: FleetCategory,
+    pub name: String,
+    #[serde(default)]
+    pub serial: Option<String>,
+    #[serde(deserialize_with = "de_flag")]
+    pub out_of_service: bool,
+    #[serde(default)]
+    pub out_of_service_reason: Option<String>,
+    #[serde(default)]
+    pub out_of_service_since: Option<String>,
+    /// Taken out by an open maintenance record
+    #[serde(default, deserialize_with = "de_flag")]
+    pub out_for_maintenance: bool,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Fleet size for one category. `total_units` is what we own; identified assets beyond
+/// that count grow the fleet, and each out-of-service asset takes one unit away.
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetSummary {
+    pub category: FleetCategory,
+    pub label: String,
+    pub total_units: u32,
+    pub identified_assets: u32,
+    pub out_of_service: u32,
+    pub in_service: u32,
+}
+
+impl FleetSummary {
+    pub fn new(category: FleetCategory, total_units: u32, identified_assets: u32, out_of_service: u32) -> Self {
+        Self {
+            category,
+            label: category.label().to_string(),
+            total_units,
+            identified_assets,
+            out_of_service,
+            in_service: total_units.max(identified_assets).saturating_sub(out_of_service),
+        }
+    }
+}
+
+/// Fleet minus committed bookings for a time window
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetAvailability {
+    pub category: FleetCategory,
+    pub label: String,
+    pub in_service: u32,
+    pub committed: u32,
+    pub available: u32,
+}
+
+/// POST /api/admin/fleet/categories/:category
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateFleetCategoryRequest {
+    pub total_units: u32,
+}
+
+/// POST /api/admin/fleet/assets
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateFleetAssetRequest {
+    pub category: FleetCategory,
+    pub name: String,
+    #[serde(default)]
+    pub serial: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl CreateFleetAssetRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.trim().len() > 80 {
+            return Err("Asset name is required (80 characters max)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// POST /api/admin/fleet/assets/:id - omitted fields are kept
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateFleetAssetRequest {
+    pub name: Option<String>,
+    pub serial: Option<String>,
+    pub notes: Option<String>,
+    pub out_of_service: Option<bool>,
+    pub out_of_service_reason: Option<String>,
+}
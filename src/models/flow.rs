@@ -1,7 +1,26 @@
 //! River flow data models
 
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
+/// Instantaneous readings fetched with the latest value, for trends
+pub const FLOW_TREND_PERIOD: &str = "PT12H";
+
+/// USGS parameter codes
+pub const DISCHARGE: &str = "00060";
+pub const WATER_TEMPERATURE: &str = "00010";
+pub const GAGE_HEIGHT: &str = "00065";
+pub const TURBIDITY: &str = "63680";
+/// Requested when USGS_PARAMETERS is not set; sites skip codes they don't report
+pub const DEFAULT_USGS_PARAMETERS: [&str; 4] = [DISCHARGE, WATER_TEMPERATURE, GAGE_HEIGHT, TURBIDITY];
+/// A reading this close to "4 hours ago" stands in for it
+const TREND_TOLERANCE_MINUTES: i64 = 60;
+/// Rates are percent of the current flow per hour
+const RISING_FAST_PCT_PER_HOUR: f64 = 2.0;
+const STEADY_PCT_PER_HOUR: f64 = 0.5;
+/// Projections further out than this are too uncertain to show
+const MAX_PROJECTION_HOURS: f64 = 48.0;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowData {
     pub flow_cfs: f64,
@@ -10,8 +29,173 @@ pub struct FlowData {
     pub station_id: String,
     pub station_name: String,
     pub status: FlowStatus,
+    /// `status` before cold water is counted; the next reading holds against this
+    #[serde(default)]
+    pub flow_status: Option<FlowStatus>,
     pub change_4h: Option<f64>,
     pub change_12h: Option<f64>,
+    /// From the 4-hour change
+    #[serde(default)]
+    pub trend: Option<FlowTrend>,
+    #[serde(default)]
+    pub rate_cfs_per_hour: Option<f64>,
+    /// When a rising river reaches caution at the current rate, if within two days
+    #[serde(default)]
+    pub caution_at: Option<String>,
+    #[serde(default)]
+    pub hours_to_caution: Option<f64>,
+    #[serde(default)]
+    pub gage_height: Option<ParameterReading>,
+    /// Every other parameter the site reported (temperature, turbidity, ...)
+    #[serde(default)]
+    pub water_quality: Vec<ParameterReading>,
+    /// Tubing, kayak and canoe against their own limits
+    #[serde(default)]
+    pub activities: Vec<ActivityStatus>,
+}
+
+impl FlowData {
+    /// The latest discharge and temperature from a USGS response, with trends from the
+    /// rest of the series. `None` when the station reported no discharge. Without
+    /// thresholds the status is unknown and nothing is projected. `previous` is the
+    /// station's last reading, whose flow-only statuses hold until flow clears the
+    /// hysteresis.
+    pub fn from_usgs(
+        response: &UsgsResponse,
+        station_id: &str,
+        thresholds: Option<&FlowThresholds>,
+        previous: Option<&FlowData>,
+    ) -> Option<Self> {
+        let discharge = response.series(DISCHARGE);
+        let latest = discharge.last()?;
+        let readings = response.latest_readings();
+        let change = |hours: i64| change_over(&discharge, hours);
+        let change_4h = change(4);
+        let rate = change_4h.map(|c| c / 4.0);
+        let trend = rate.map(|r| FlowTrend::classify(r, latest.value));
+        let hours_to_caution = match (rate, trend, thresholds) {
+            (Some(r), Some(FlowTrend::Rising | FlowTrend::RisingFast), Some(t)) if latest.value < t.safe_max => {
+                Some((t.safe_max - latest.value) / r).filter(|h| *h <= MAX_PROJECTION_HOURS)
+            }
+            _ => None,
+        };
+
+        let water_temp_f = response.series(WATER_TEMPERATURE).last().map(|t| t.value * 9.0 / 5.0 + 32.0);
+        let previous_flow = previous.and_then(|p| p.flow_status.as_ref());
+
+        Some(Self {
+            flow_cfs: latest.value,
+            water_temp_f,
+            timestamp: latest.date_time.clone(),
+            station_id: station_id.to_string(),
+            station_name: response.value.time_series.first().map(|ts| ts.source_info.site_name.clone()).unwrap_or_default(),
+            status: thresholds.map_or(FlowStatus::Unknown, |t| t.status(latest.value, water_temp_f, previous_flow)),
+            flow_status: thresholds.map(|t| t.flow_status(latest.value, previous_flow)),
+            change_4h,
+            change_12h: change(12),
+            trend,
+            rate_cfs_per_hour: rate.map(|r| r.round()),
+            caution_at: hours_to_caution
+                .map(|h| (latest.at + chrono::Duration::minutes((h * 60.0).round() as i64)).to_rfc3339()),
+            hours_to_caution: hours_to_caution.map(|h| (h * 10.0).round() / 10.0),
+            gage_height: readings.iter().find(|r| r.parameter == GAGE_HEIGHT).cloned(),
+            water_quality: readings
+                .into_iter()
+                .filter(|r| r.parameter != DISCHARGE && r.parameter != GAGE_HEIGHT)
+                .collect(),
+            activities: thresholds.map_or_else(Vec::new, |t| {
+                t.activity_statuses(latest.value, water_temp_f, previous.map_or(&[][..], |p| &p.activities))
+            }),
+        })
+    }
+}
+
+/// Latest value minus the one `hours` earlier, when the series reaches back that far
+fn change_over(series: &[UsgsReading], hours: i64) -> Option<f64> {
+    let latest = series.last()?;
+    let target = latest.at - chrono::Duration::hours(hours);
+    series
+        .iter()
+        .map(|r| (r, (r.at - target).num_minutes().abs()))
+        .filter(|(_, off)| *off <= TREND_TOLERANCE_MINUTES)
+        .min_by_key(|(_, off)| *off)
+        .map(|(r, _)| latest.value - r.value)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowTrend {
+    RisingFast,
+    Rising,
+    Steady,
+    Falling,
+}
+
+impl FlowTrend {
+    /// Classify a rate of change relative to the current flow
+    pub fn classify(cfs_per_hour: f64, cfs: f64) -> Self {
+        let pct = if cfs > 0.0 { cfs_per_hour / cfs * 100.0 } else { 0.0 };
+        if pct >= RISING_FAST_PCT_PER_HOUR {
+            FlowTrend::RisingFast
+        } else if pct >= STEADY_PCT_PER_HOUR {
+            FlowTrend::Rising
+        } else if pct > -STEADY_PCT_PER_HOUR {
+            FlowTrend::Steady
+        } else {
+            FlowTrend::Falling
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FlowTrend::RisingFast => "Rising fast",
+            FlowTrend::Rising => "Rising",
+            FlowTrend::Steady => "Steady",
+            FlowTrend::Falling => "Falling",
+        }
+    }
+}
+
+/// Parameter codes from a comma-separated USGS_PARAMETERS value; discharge is always
+/// requested since everything else hangs off it
+pub fn usgs_parameters(configured: Option<&str>) -> Vec<String> {
+    let mut codes: Vec<String> = vec![DISCHARGE.to_string()];
+    let listed: Vec<&str> = match configured {
+        Some(list) if !list.trim().is_empty() => list.split(',').map(str::trim).collect(),
+        _ => DEFAULT_USGS_PARAMETERS.to_vec(),
+    };
+    for code in listed {
+        if code.len() == 5 && code.bytes().all(|b| b.is_ascii_digit()) && !codes.iter().any(|c| c == code) {
+            codes.push(code.to_string());
+        }
+    }
+    codes
+}
+
+/// Display name for the parameters we know; others use the name USGS reports
+pub fn parameter_name(code: &str) -> Option<&'static str> {
+    match code {
+        DISCHARGE => Some("Discharge"),
+        WATER_TEMPERATURE => Some("Water temperature"),
+        GAGE_HEIGHT => Some("Gage height"),
+        TURBIDITY => Some("Turbidity"),
+        "00095" => Some("Specific conductance"),
+        "00300" => Some("Dissolved oxygen"),
+        "00400" => Some("pH"),
+        "32316" => Some("Chlorophyll"),
+        _ => None,
+    }
+}
+
+/// The latest value of one USGS parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterReading {
+    pub parameter: String,
+    pub name: String,
+    pub value: f64,
+    /// As USGS reports it, e.g. `ft`, `deg C`, `_FNU`
+    pub unit: String,
+    pub timestamp: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -24,15 +208,29 @@ pub enum FlowStatus {
 }
 
 impl FlowStatus {
+    /// Rate flow alone against the overall limits, without hysteresis or temperature
     pub fn from_cfs(cfs: f64, thresholds: &FlowThresholds) -> Self {
-        if cfs < thresholds.safe_max {
+        Self::rate(cfs, thresholds.safe_max, thresholds.caution_max)
+    }
+
+    fn rate(cfs: f64, safe_max: f64, caution_max: f64) -> Self {
+        if cfs < safe_max {
             FlowStatus::Safe
-        } else if cfs < thresholds.caution_max {
+        } else if cfs < caution_max {
             FlowStatus::Caution
         } else {
             FlowStatus::Danger
         }
     }
+
+    /// Unknown ranks with safe: it never holds a status up
+    fn severity(&self) -> u8 {
+        match self {
+            FlowStatus::Safe | FlowStatus::Unknown => 0,
+            FlowStatus::Caution => 1,
+            FlowStatus::Danger => 2,
+        }
+    }
     
     pub fn message(&self) -> &'static str {
         match self {
@@ -53,10 +251,81 @@ impl FlowStatus {
     }
 }
 
+/// Water activities rated against their own flow limits
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RiverActivity {
+    Tubing,
+    Kayak,
+    Canoe,
+}
+
+impl RiverActivity {
+    pub const ALL: [RiverActivity; 3] = [RiverActivity::Tubing, RiverActivity::Kayak, RiverActivity::Canoe];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RiverActivity::Tubing => "Tubing",
+            RiverActivity::Kayak => "Kayaking",
+            RiverActivity::Canoe => "Canoeing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityLimit {
+    pub activity: RiverActivity,
+    pub safe_max: f64,
+    pub caution_max: f64,
+    /// Colder water than this rates the activity at least caution
+    #[serde(default)]
+    pub min_water_temp_f: Option<f64>,
+}
+
+/// How one activity rates right now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityStatus {
+    pub activity: RiverActivity,
+    pub status: FlowStatus,
+    pub message: String,
+    /// `status` before cold water is counted; the next reading holds against this
+    #[serde(default)]
+    pub flow_status: Option<FlowStatus>,
+}
+
+impl ActivityStatus {
+    fn new(activity: RiverActivity, flow_status: FlowStatus, status: FlowStatus) -> Self {
+        let cold = status != flow_status;
+        let label = activity.label();
+        let message = match (&status, cold) {
+            (FlowStatus::Danger, _) => format!("{} is suspended due to high flow", label),
+            (FlowStatus::Caution, true) => format!("{}: cold water - dress for a swim", label),
+            (FlowStatus::Caution, false) => format!("{}: exercise caution - elevated water levels", label),
+            (FlowStatus::Safe, _) => format!("Good conditions for {}", label.to_lowercase()),
+            (FlowStatus::Unknown, _) => format!("{}: unable to determine current conditions", label),
+        };
+        Self { activity, status, message, flow_status: Some(flow_status) }
+    }
+}
+
+/// Flow limits (SETTINGS KV `flow_thresholds`, or a network station's own)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowThresholds {
     pub safe_max: f64,
     pub caution_max: f64,
+    /// Activities not listed here use the limits above
+    #[serde(default)]
+    pub activities: Vec<ActivityLimit>,
+    /// Colder water than this rates the river at least caution
+    #[serde(default)]
+    pub min_water_temp_f: Option<f64>,
+    /// A status only steps down once flow is this far back under its limit
+    #[serde(default = "default_hysteresis_cfs")]
+    pub hysteresis_cfs: f64,
+}
+
+fn default_hysteresis_cfs() -> f64 {
+    250.0
 }
 
 impl Default for FlowThresholds {
@@ -64,10 +333,102 @@ impl Default for FlowThresholds {
         Self {
             safe_max: 8000.0,
             caution_max: 15000.0,
+            // Every activity uses the overall limits until the shop sets its own
+            activities: Vec::new(),
+            min_water_temp_f: None,
+            hysteresis_cfs: default_hysteresis_cfs(),
         }
     }
 }
 
+impl FlowThresholds {
+    /// Overall status from flow alone. A `previous` flow status higher than the flow
+    /// calls for is held until flow drops `hysteresis_cfs` below its limit, so readings
+    /// hovering at a limit don't flap.
+    pub fn flow_status(&self, cfs: f64, previous: Option<&FlowStatus>) -> FlowStatus {
+        self.held(cfs, self.safe_max, self.caution_max, previous)
+    }
+
+    /// Overall status: the held flow status, then raised for cold water. `previous`
+    /// is the last reading's flow-only status, so a cold spell isn't held once the
+    /// water warms.
+    pub fn status(&self, cfs: f64, water_temp_f: Option<f64>, previous: Option<&FlowStatus>) -> FlowStatus {
+        chilled(self.flow_status(cfs, previous), water_temp_f, self.min_water_temp_f)
+    }
+
+    /// One activity's status, from its own limits or the overall ones; `previous` is
+    /// its last flow-only status
+    pub fn activity_status(
+        &self,
+        activity: RiverActivity,
+        cfs: f64,
+        water_temp_f: Option<f64>,
+        previous: Option<&FlowStatus>,
+    ) -> ActivityStatus {
+        let limit = self.activities.iter().find(|l| l.activity == activity);
+        let (safe_max, caution_max) = limit.map_or((self.safe_max, self.caution_max), |l| (l.safe_max, l.caution_max));
+        let min_temp = limit.and_then(|l| l.min_water_temp_f).or(self.min_water_temp_f);
+        let flow_status = self.held(cfs, safe_max, caution_max, previous);
+        let status = chilled(flow_status.clone(), water_temp_f, min_temp);
+        ActivityStatus::new(activity, flow_status, status)
+    }
+
+    /// Every activity's status; `previous` is the last reading's
+    pub fn activity_statuses(&self, cfs: f64, water_temp_f: Option<f64>, previous: &[ActivityStatus]) -> Vec<ActivityStatus> {
+        RiverActivity::ALL
+            .iter()
+            .map(|&activity| {
+                let before = previous.iter().find(|p| p.activity == activity).and_then(|p| p.flow_status.as_ref());
+                self.activity_status(activity, cfs, water_temp_f, before)
+            })
+            .collect()
+    }
+
+    fn held(&self, cfs: f64, safe_max: f64, caution_max: f64, previous: Option<&FlowStatus>) -> FlowStatus {
+        let status = FlowStatus::rate(cfs, safe_max, caution_max);
+        match previous {
+            Some(before) if before.severity() > status.severity() => {
+                let held = FlowStatus::rate(cfs + self.hysteresis_cfs, safe_max, caution_max);
+                if held.severity() < before.severity() { held } else { before.clone() }
+            }
+            _ => status,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.safe_max <= 0.0 || self.caution_max <= self.safe_max {
+            return Err("Caution flow must be above safe flow".to_string());
+        }
+        if !(0.0..self.safe_max).contains(&self.hysteresis_cfs) {
+            return Err("Hysteresis must be between 0 and the safe flow".to_string());
+        }
+        if self.min_water_temp_f.is_some_and(|t| !(32.0..=90.0).contains(&t)) {
+            return Err("Minimum water temperature must be 32-90°F".to_string());
+        }
+        for (i, limit) in self.activities.iter().enumerate() {
+            let label = limit.activity.label();
+            if self.activities[..i].iter().any(|other| other.activity == limit.activity) {
+                return Err(format!("{} is listed twice", label));
+            }
+            if limit.safe_max <= 0.0 || limit.caution_max <= limit.safe_max {
+                return Err(format!("{}: caution flow must be above safe flow", label));
+            }
+            if limit.min_water_temp_f.is_some_and(|t| !(32.0..=90.0).contains(&t)) {
+                return Err(format!("{}: minimum water temperature must be 32-90°F", label));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Raise a safe status to caution when the water is colder than `min_temp_f`
+fn chilled(status: FlowStatus, water_temp_f: Option<f64>, min_temp_f: Option<f64>) -> FlowStatus {
+    match (water_temp_f, min_temp_f) {
+        (Some(temp), Some(min)) if temp < min && status == FlowStatus::Safe => FlowStatus::Caution,
+        _ => status,
+    }
+}
+
 // USGS API Response structures
 #[derive(Debug, Deserialize)]
 pub struct UsgsResponse {
@@ -98,6 +459,18 @@ pub struct UsgsSourceInfo {
 #[serde(rename_all = "camelCase")]
 pub struct UsgsVariable {
     pub variable_code: Vec<UsgsVariableCode>,
+    #[serde(default)]
+    pub variable_name: String,
+    #[serde(default)]
+    pub unit: Option<UsgsUnit>,
+    #[serde(default)]
+    pub no_data_value: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsgsUnit {
+    pub unit_code: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -116,3 +489,211 @@ pub struct UsgsDataValue {
     pub value: String,
     pub date_time: String,
 }
+
+/// One parsed instantaneous value
+#[derive(Debug, Clone)]
+pub struct UsgsReading {
+    pub date_time: String,
+    pub at: DateTime<FixedOffset>,
+    pub value: f64,
+}
+
+impl UsgsTimeSeries {
+    pub fn parameter(&self) -> Option<&str> {
+        self.variable.variable_code.first().map(|c| c.value.as_str())
+    }
+}
+
+impl UsgsResponse {
+    /// Readings for a parameter code, oldest first. Missing values (the series'
+    /// no-data value, -999999) are skipped.
+    pub fn series(&self, parameter: &str) -> Vec<UsgsReading> {
+        let mut readings: Vec<UsgsReading> = self
+            .value
+            .time_series
+            .iter()
+            .filter(|ts| ts.parameter() == Some(parameter))
+            .flat_map(|ts| ts.values.iter().flat_map(|v| v.value.iter()).map(move |v| (ts, v)))
+            .filter_map(|(ts, v)| {
+                let value: f64 = v.value.parse().ok()?;
+                if value <= -999_000.0 || ts.variable.no_data_value == Some(value) {
+                    return None;
+                }
+                Some(UsgsReading {
+                    date_time: v.date_time.clone(),
+                    at: DateTime::parse_from_rfc3339(&v.date_time).ok()?,
+                    value,
+                })
+            })
+            .collect();
+        readings.sort_by_key(|r| r.at);
+        readings
+    }
+
+    /// The newest value of every parameter in the response, in the order USGS lists them
+    pub fn latest_readings(&self) -> Vec<ParameterReading> {
+        let mut readings: Vec<ParameterReading> = vec![];
+        for ts in &self.value.time_series {
+            let Some(code) = ts.parameter() else { continue };
+            if readings.iter().any(|r| r.parameter == code) {
+                continue;
+            }
+            if let Some(latest) = self.series(code).pop() {
+                readings.push(ParameterReading {
+                    parameter: code.to_string(),
+                    name: parameter_name(code)
+                        .map(str::to_string)
+                        .unwrap_or_else(|| ts.variable.variable_name.split(',').next().unwrap_or(code).trim().to_string()),
+                    value: latest.value,
+                    unit: ts.variable.unit.as_ref().map(|u| u.unit_code.clone()).unwrap_or_default(),
+                    timestamp: latest.date_time,
+                });
+            }
+        }
+        readings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thresholds() -> FlowThresholds {
+        FlowThresholds {
+            safe_max: 8000.0,
+            caution_max: 15000.0,
+            activities: Vec::new(),
+            min_water_temp_f: None,
+            hysteresis_cfs: 250.0,
+        }
+    }
+
+    fn tubing_limits() -> FlowThresholds {
+        FlowThresholds {
+            activities: vec![ActivityLimit {
+                activity: RiverActivity::Tubing,
+                safe_max: 6000.0,
+                caution_max: 8000.0,
+                min_water_temp_f: Some(60.0),
+            }],
+            ..thresholds()
+        }
+    }
+
+    fn activity(statuses: &[ActivityStatus], activity: RiverActivity) -> &ActivityStatus {
+        statuses.iter().find(|s| s.activity == activity).unwrap()
+    }
+
+    #[test]
+    fn holds_a_higher_status_through_the_hysteresis_band() {
+        let t = thresholds();
+        assert_eq!(t.status(8100.0, None, None), FlowStatus::Caution);
+        // Just under the limit: still caution
+        assert_eq!(t.status(7900.0, None, Some(&FlowStatus::Caution)), FlowStatus::Caution);
+        assert_eq!(t.status(7750.0, None, Some(&FlowStatus::Caution)), FlowStatus::Caution);
+        // Clear of the band: steps down
+        assert_eq!(t.status(7749.0, None, Some(&FlowStatus::Caution)), FlowStatus::Safe);
+        // Without a previous reading there is nothing to hold
+        assert_eq!(t.status(7900.0, None, None), FlowStatus::Safe);
+        // Rising is never held back
+        assert_eq!(t.status(15000.0, None, Some(&FlowStatus::Caution)), FlowStatus::Danger);
+    }
+
+    #[test]
+    fn danger_steps_down_one_level_at_a_time() {
+        let t = thresholds();
+        assert_eq!(t.status(14900.0, None, Some(&FlowStatus::Danger)), FlowStatus::Danger);
+        assert_eq!(t.status(14000.0, None, Some(&FlowStatus::Danger)), FlowStatus::Caution);
+        assert_eq!(t.status(7000.0, None, Some(&FlowStatus::Danger)), FlowStatus::Safe);
+    }
+
+    #[test]
+    fn cold_water_is_not_held_once_it_warms() {
+        let t = FlowThresholds { min_water_temp_f: Some(60.0), ..thresholds() };
+        // Cold raises safe flow to caution, but the flow-only status stays safe
+        assert_eq!(t.status(5000.0, Some(55.0), None), FlowStatus::Caution);
+        let flow_only = t.flow_status(5000.0, None);
+        assert_eq!(flow_only, FlowStatus::Safe);
+        // Warmer water the next reading: safe, even inside the band
+        assert_eq!(t.status(7900.0, Some(65.0), Some(&flow_only)), FlowStatus::Safe);
+    }
+
+    #[test]
+    fn cold_activity_reading_keeps_its_flow_only_status() {
+        let t = tubing_limits();
+        let cold = t.activity_statuses(5000.0, Some(55.0), &[]);
+        let tubing = activity(&cold, RiverActivity::Tubing);
+        assert_eq!(tubing.status, FlowStatus::Caution);
+        assert_eq!(tubing.flow_status, Some(FlowStatus::Safe));
+        assert!(tubing.message.contains("cold water"));
+
+        let warm = t.activity_statuses(5900.0, Some(65.0), &cold);
+        assert_eq!(activity(&warm, RiverActivity::Tubing).status, FlowStatus::Safe);
+    }
+
+    #[test]
+    fn activities_hold_against_their_own_limits() {
+        let t = tubing_limits();
+        let high = t.activity_statuses(6100.0, None, &[]);
+        assert_eq!(activity(&high, RiverActivity::Tubing).status, FlowStatus::Caution);
+        let lower = t.activity_statuses(5900.0, None, &high);
+        assert_eq!(activity(&lower, RiverActivity::Tubing).status, FlowStatus::Caution);
+        let clear = t.activity_statuses(5700.0, None, &lower);
+        assert_eq!(activity(&clear, RiverActivity::Tubing).status, FlowStatus::Safe);
+    }
+
+    #[test]
+    fn rates_each_activity_against_its_own_limits() {
+        let t = tubing_limits();
+        let statuses = t.activity_statuses(9000.0, None, &[]);
+        assert_eq!(statuses.len(), RiverActivity::ALL.len());
+        assert_eq!(activity(&statuses, RiverActivity::Tubing).status, FlowStatus::Danger);
+        // Not listed: the overall limits
+        assert_eq!(activity(&statuses, RiverActivity::Kayak).status, FlowStatus::Caution);
+        assert_eq!(activity(&statuses, RiverActivity::Canoe).status, FlowStatus::Caution);
+    }
+
+    #[test]
+    fn default_limits_apply_to_every_activity() {
+        let t = FlowThresholds::default();
+        assert!(t.activities.is_empty());
+        for cfs in [5000.0, 9000.0, 16000.0] {
+            let overall = t.status(cfs, None, None);
+            for status in t.activity_statuses(cfs, None, &[]) {
+                assert_eq!(status.status, overall, "{:?} at {}", status.activity, cfs);
+            }
+        }
+    }
+
+    #[test]
+    fn validates_limits() {
+        assert!(FlowThresholds::default().validate().is_ok());
+        assert!(tubing_limits().validate().is_ok());
+
+        let inverted = FlowThresholds { caution_max: 8000.0, ..thresholds() };
+        assert!(inverted.validate().is_err());
+        let zero = FlowThresholds { safe_max: 0.0, ..thresholds() };
+        assert!(zero.validate().is_err());
+        let wide_band = FlowThresholds { hysteresis_cfs: 8000.0, ..thresholds() };
+        assert!(wide_band.validate().is_err());
+        let negative_band = FlowThresholds { hysteresis_cfs: -1.0, ..thresholds() };
+        assert!(negative_band.validate().is_err());
+        let frozen = FlowThresholds { min_water_temp_f: Some(20.0), ..thresholds() };
+        assert!(frozen.validate().is_err());
+
+        let mut twice = tubing_limits();
+        twice.activities.push(twice.activities[0].clone());
+        assert_eq!(twice.validate(), Err("Tubing is listed twice".to_string()));
+
+        let mut inverted_activity = tubing_limits();
+        inverted_activity.activities[0].caution_max = 5000.0;
+        assert_eq!(
+            inverted_activity.validate(),
+            Err("Tubing: caution flow must be above safe flow".to_string())
+        );
+
+        let mut cold_activity = tubing_limits();
+        cold_activity.activities[0].min_water_temp_f = Some(95.0);
+        assert!(cold_activity.validate().is_err());
+    }
+}
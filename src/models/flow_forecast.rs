@@ -0,0 +1,247 @@
+//! River forecast hydrograph from the NWS National Water Prediction Service
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use super::booking::local_time;
+use super::flow::{ActivityStatus, FlowStatus, FlowThresholds};
+
+/// NWPS `/gauges/{id}/stageflow/forecast`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NwpsForecastResponse {
+    #[serde(default)]
+    pub issued_time: Option<String>,
+    #[serde(default)]
+    pub primary_units: Option<String>,
+    #[serde(default)]
+    pub secondary_units: Option<String>,
+    #[serde(default)]
+    pub data: Vec<NwpsForecastValue>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NwpsForecastValue {
+    pub valid_time: String,
+    #[serde(default)]
+    pub primary: Option<f64>,
+    #[serde(default)]
+    pub secondary: Option<f64>,
+}
+
+/// NWPS reports missing values as -999
+fn present(value: Option<f64>) -> Option<f64> {
+    value.filter(|v| v.is_finite() && *v > -999.0)
+}
+
+/// Multiplier from an NWPS flow unit to cfs
+fn cfs_per_unit(unit: &str) -> Option<f64> {
+    match unit.trim().to_lowercase().as_str() {
+        "kcfs" => Some(1000.0),
+        "cfs" | "ft3/s" => Some(1.0),
+        _ => None,
+    }
+}
+
+/// One forecast time, rated against the flow thresholds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastPoint {
+    pub valid_time: String,
+    pub stage: Option<f64>,
+    pub flow_cfs: Option<f64>,
+    pub status: FlowStatus,
+    /// Each activity at this flow, against its own limits
+    #[serde(default)]
+    pub activities: Vec<ActivityStatus>,
+}
+
+/// The forecast for one local day, rated by its highest flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastDay {
+    pub date: String,
+    pub peak_flow_cfs: Option<f64>,
+    pub peak_stage: Option<f64>,
+    pub peak_at: Option<String>,
+    pub status: FlowStatus,
+    pub message: String,
+    /// Each activity at the day's peak flow
+    #[serde(default)]
+    pub activities: Vec<ActivityStatus>,
+}
+
+/// GET /api/flow/forecast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowForecast {
+    pub gauge_id: String,
+    pub issued_at: Option<String>,
+    pub stage_units: Option<String>,
+    pub days: Vec<ForecastDay>,
+    pub points: Vec<ForecastPoint>,
+}
+
+impl FlowForecast {
+    /// Parse an NWPS stage/flow forecast body (see `fixtures/nwps_stageflow_forecast.json`);
+    /// days are local to `tz`
+    pub fn parse(gauge_id: &str, body: &str, thresholds: &FlowThresholds, tz: Tz) -> Result<Self, String> {
+        let response: NwpsForecastResponse =
+            serde_json::from_str(body).map_err(|e| format!("Invalid NWPS forecast: {}", e))?;
+        Ok(Self::from_nwps(gauge_id, &response, thresholds, tz))
+    }
+
+    pub fn from_nwps(gauge_id: &str, response: &NwpsForecastResponse, thresholds: &FlowThresholds, tz: Tz) -> Self {
+        // Flow without a unit we know can't be compared to the thresholds
+        let scale = response.secondary_units.as_deref().and_then(cfs_per_unit);
+        let mut points: Vec<ForecastPoint> = response
+            .data
+            .iter()
+            .filter(|v| DateTime::parse_from_rfc3339(&v.valid_time).is_ok())
+            // A time with neither stage nor flow (both -999) says nothing
+            .filter(|v| present(v.primary).is_some() || present(v.secondary).is_some())
+            .map(|v| {
+                let flow_cfs = present(v.secondary).zip(scale).map(|(f, s)| (f * s).round());
+                ForecastPoint {
+                    valid_time: v.valid_time.clone(),
+                    stage: present(v.primary),
+                    flow_cfs,
+                    status: flow_cfs.map_or(FlowStatus::Unknown, |cfs| FlowStatus::from_cfs(cfs, thresholds)),
+                    // Forecasts carry no water temperature, so activities are rated on flow alone
+                    activities: flow_cfs.map_or_else(Vec::new, |cfs| thresholds.activity_statuses(cfs, None, &[])),
+                }
+            })
+            .collect();
+        points.sort_by_key(|p| DateTime::parse_from_rfc3339(&p.valid_time).ok());
+
+        Self {
+            gauge_id: gauge_id.to_string(),
+            issued_at: response.issued_time.clone(),
+            stage_units: response.primary_units.clone(),
+            days: forecast_days(&points, tz),
+            points,
+        }
+    }
+}
+
+fn local_date(valid_time: &str, tz: Tz) -> Option<NaiveDate> {
+    let at = DateTime::parse_from_rfc3339(valid_time).ok()?;
+    Some(local_time(at.with_timezone(&Utc), tz).date())
+}
+
+fn forecast_days(points: &[ForecastPoint], tz: Tz) -> Vec<ForecastDay> {
+    let mut dates: Vec<NaiveDate> = points.iter().filter_map(|p| local_date(&p.valid_time, tz)).collect();
+    dates.dedup();
+    dates
+        .into_iter()
+        .map(|date| {
+            let day: Vec<&ForecastPoint> =
+                points.iter().filter(|p| local_date(&p.valid_time, tz) == Some(date)).collect();
+            let peak = day
+                .iter()
+                .filter(|p| p.flow_cfs.is_some())
+                .max_by(|a, b| a.flow_cfs.partial_cmp(&b.flow_cfs).unwrap_or(std::cmp::Ordering::Equal));
+            let status = peak.map_or(FlowStatus::Unknown, |p| p.status.clone());
+            ForecastDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                peak_flow_cfs: peak.and_then(|p| p.flow_cfs),
+                peak_stage: day.iter().filter_map(|p| p.stage).reduce(f64::max),
+                peak_at: peak.map(|p| p.valid_time.clone()),
+                message: status.message().to_string(),
+                status,
+                activities: peak.map_or_else(Vec::new, |p| p.activities.clone()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::flow::{ActivityLimit, RiverActivity};
+
+    const FIXTURE: &str = include_str!("../../fixtures/nwps_stageflow_forecast.json");
+
+    fn thresholds() -> FlowThresholds {
+        FlowThresholds {
+            safe_max: 8000.0,
+            caution_max: 9000.0,
+            activities: Vec::new(),
+            ..FlowThresholds::default()
+        }
+    }
+
+    #[test]
+    fn parses_the_nwps_fixture() {
+        let forecast = FlowForecast::parse("05406500", FIXTURE, &thresholds(), chrono_tz::America::Chicago).unwrap();
+        assert_eq!(forecast.gauge_id, "05406500");
+        assert_eq!(forecast.issued_at.as_deref(), Some("2026-10-18T14:12:00Z"));
+        assert_eq!(forecast.stage_units.as_deref(), Some("ft"));
+
+        // kcfs scaled to cfs, and the -999 time left out
+        assert_eq!(forecast.points.len(), 10);
+        assert!(!forecast.points.iter().any(|p| p.valid_time == "2026-10-20T06:00:00Z"));
+        assert_eq!(forecast.points[0].flow_cfs, Some(6200.0));
+        assert_eq!(forecast.points[0].stage, Some(4.1));
+        assert!(forecast.points.iter().all(|p| p.flow_cfs.is_some() && p.stage.is_some()));
+    }
+
+    #[test]
+    fn rates_each_local_day_by_its_peak() {
+        let forecast = FlowForecast::parse("05406500", FIXTURE, &thresholds(), chrono_tz::America::Chicago).unwrap();
+        let days: Vec<(&str, Option<f64>, Option<&str>, &FlowStatus)> = forecast
+            .days
+            .iter()
+            .map(|d| (d.date.as_str(), d.peak_flow_cfs, d.peak_at.as_deref(), &d.status))
+            .collect();
+        // Central Daylight Time: 00Z belongs to the evening before
+        assert_eq!(days, vec![
+            ("2026-10-18", Some(6900.0), Some("2026-10-19T00:00:00Z"), &FlowStatus::Safe),
+            ("2026-10-19", Some(9300.0), Some("2026-10-19T18:00:00Z"), &FlowStatus::Danger),
+            ("2026-10-20", Some(8100.0), Some("2026-10-20T12:00:00Z"), &FlowStatus::Caution),
+            ("2026-10-21", Some(6000.0), Some("2026-10-21T12:00:00Z"), &FlowStatus::Safe),
+        ]);
+        assert_eq!(forecast.days[1].peak_stage, Some(5.6));
+        assert_eq!(forecast.days[1].message, FlowStatus::Danger.message());
+    }
+
+    #[test]
+    fn rates_each_activity_against_its_own_limits() {
+        let thresholds = FlowThresholds {
+            activities: vec![ActivityLimit {
+                activity: RiverActivity::Tubing,
+                safe_max: 6500.0,
+                caution_max: 8000.0,
+                min_water_temp_f: None,
+            }],
+            ..thresholds()
+        };
+        let forecast = FlowForecast::parse("05406500", FIXTURE, &thresholds, chrono_tz::America::Chicago).unwrap();
+        let status = |activities: &[ActivityStatus], activity: RiverActivity| {
+            activities.iter().find(|a| a.activity == activity).map(|a| a.status.clone())
+        };
+
+        // 6200 cfs: inside every limit
+        let first = &forecast.points[0];
+        assert_eq!(status(&first.activities, RiverActivity::Tubing), Some(FlowStatus::Safe));
+        assert_eq!(status(&first.activities, RiverActivity::Kayak), Some(FlowStatus::Safe));
+
+        // Peaks of 6900 and 8100 cfs: tubing rates a step higher than the overall limits
+        assert_eq!(status(&forecast.days[0].activities, RiverActivity::Tubing), Some(FlowStatus::Caution));
+        assert_eq!(status(&forecast.days[0].activities, RiverActivity::Kayak), Some(FlowStatus::Safe));
+        assert_eq!(status(&forecast.days[2].activities, RiverActivity::Tubing), Some(FlowStatus::Danger));
+        assert_eq!(status(&forecast.days[2].activities, RiverActivity::Canoe), Some(FlowStatus::Caution));
+    }
+
+    #[test]
+    fn flow_in_an_unknown_unit_is_not_rated() {
+        let body = FIXTURE.replace("\"kcfs\"", "\"m3/s\"");
+        let forecast = FlowForecast::parse("05406500", &body, &thresholds(), chrono_tz::America::Chicago).unwrap();
+        assert!(forecast.points.iter().all(|p| p.flow_cfs.is_none() && p.status == FlowStatus::Unknown));
+        assert!(forecast.points.iter().all(|p| p.activities.is_empty()));
+        assert!(forecast.days.iter().all(|d| d.status == FlowStatus::Unknown));
+    }
+
+    #[test]
+    fn rejects_a_malformed_body() {
+        assert!(FlowForecast::parse("05406500", "<html>", &thresholds(), chrono_tz::America::Chicago).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Gift cards and their balance ledger
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use super::waiver::parse_date;
+
+/// Codes look like `K7RM-2QXC-9HNT-4BWP` (about 79 bits)
+pub const GIFT_CARD_CODE_LENGTH: usize = 16;
+pub const MAX_GIFT_CARD_CENTS: i64 = 100_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GiftCardStatus {
+    Active,
+    Void,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerEntryType {
+    Issue,
+    Redeem,
+    Refund,
+    Adjust,
+    Void,
+}
+
+/// A row in `gift_cards`; the code hash is left out so it never reaches a response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftCard {
+    pub id: String,
+    pub last4: String,
+    pub initial_cents: i64,
+    pub balance_cents: i64,
+    pub status: GiftCardStatus,
+    #[serde(default)]
+    pub recipient_name: Option<String>,
+    #[serde(default)]
+    pub recipient_email: Option<String>,
+    #[serde(default)]
+    pub purchaser_name: Option<String>,
+    #[serde(default)]
+    pub purchaser_email: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub expires_on: Option<String>,
+    pub issued_by: String,
+    pub issued_at: String,
+    #[serde(default)]
+    pub voided_at: Option<String>,
+    #[serde(default)]
+    pub voided_by: Option<String>,
+    #[serde(default)]
+    pub void_reason: Option<String>,
+}
+
+impl GiftCard {
+    /// Why the card can't be spent today, if it can't
+    pub fn unusable(&self, today: NaiveDate) -> Option<&'static str> {
+        if self.status == GiftCardStatus::Void {
+            return Some("This gift card has been voided");
+        }
+        if self.expires_on.as_deref().and_then(parse_date).is_some_and(|d| today > d) {
+            return Some("This gift card has expired");
+        }
+        if self.balance_cents <= 0 {
+            return Some("This gift card has no balance left");
+        }
+        None
+    }
+}
+
+/// A row in `gift_card_ledger`; `amount_cents` is negative for spending
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftCardLedgerEntry {
+    pub id: String,
+    pub card_id: String,
+    pub entry_type: LedgerEntryType,
+    pub amount_cents: i64,
+    pub balance_after_cents: i64,
+    #[serde(default)]
+    pub booking_id: Option<String>,
+    #[serde(default)]
+    pub reference: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+/// Codes are entered with or without dashes, in any case
+pub fn normalize_gift_card_code(raw: &str) -> Option<String> {
+    let code: String = raw
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    (code.len() == GIFT_CARD_CODE_LENGTH && code.chars().all(|c| c.is_ascii_alphanumeric())).then_some(code)
+}
+
+/// `K7RM2QXC9HNT4BWP` -> `K7RM-2QXC-9HNT-4BWP`
+pub fn format_gift_card_code(code: &str) -> String {
+    code.as_bytes()
+        .chunks(4)
+        .map(|c| String::from_utf8_lossy(c).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+pub fn to_cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+pub fn from_cents(cents: i64) -> f64 {
+    cents as f64 / 100.0
+}
+
+/// POST /api/gift-cards/balance
+#[derive(Debug, Clone, Deserialize)]
+pub struct GiftCardBalanceRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GiftCardBalanceResponse {
+    pub found: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last4: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_on: Option<String>,
+}
+
+/// POST /api/admin/gift-cards
+#[derive(Debug, Clone, Deserialize)]
+pub struct IssueGiftCardRequest {
+    pub amount: f64,
+    #[serde(default)]
+    pub recipient_name: Option<String>,
+    #[serde(default)]
+    pub recipient_email: Option<String>,
+    #[serde(default)]
+    pub purchaser_name: Option<String>,
+    #[serde(default)]
+    pub purchaser_email: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub expires_on: Option<String>,
+    /// Sale receipt or order number
+    #[serde(default)]
+    pub reference: Option<String>,
+}
+
+impl IssueGiftCardRequest {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), String> {
+        let cents = to_cents(self.amount);
+        if cents <= 0 || cents > MAX_GIFT_CARD_CENTS {
+            return Err(format!("Amount must be between $0.01 and ${}", MAX_GIFT_CARD_CENTS / 100));
+        }
+        if let Some(date) = self.expires_on.as_deref().filter(|d| !d.trim().is_empty()) {
+            match parse_date(date) {
+                Some(d) if d > today => {}
+                Some(_) => return Err("Expiry date must be in the future".to_string()),
+                None => return Err("Expiry date must be YYYY-MM-DD".to_string()),
+            }
+        }
+        if self.message.as_deref().is_some_and(|m| m.len() > 500) {
+            return Err("Message is limited to 500 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// POST /api/admin/gift-cards/:id/adjust - positive adds, negative removes
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdjustGiftCardRequest {
+    pub amount: f64,
+    pub note: String,
+}
+
+/// POST /api/admin/gift-cards/:id/void
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VoidGiftCardRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// POST /api/admin/gift-cards/redeem - walk-up sale at the counter
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedeemGiftCardRequest {
+    pub code: String,
+    pub amount: f64,
+    #[serde(default)]
+    pub reference: Option<String>,
+}
@@ -0,0 +1,311 @@
+//! Maintenance log for rental assets and scheduled service intervals
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use super::fleet::FleetCategory;
+use super::store::RepairService;
+use super::waiver::{de_flag, parse_date};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceStatus {
+    /// Work in progress; the asset is out of service
+    Open,
+    Completed,
+}
+
+/// A row in `asset_maintenance`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRecord {
+    pub id: String,
+    pub asset_id: String,
+    pub status: MaintenanceStatus,
+    pub opened_on: String,
+    #[serde(default)]
+    pub completed_on: Option<String>,
+    pub work_done: String,
+    /// JSON array of `RepairService` ids from the repair catalog
+    #[serde(default)]
+    pub repair_service_ids: Option<String>,
+    #[serde(default)]
+    pub parts: Option<String>,
+    pub cost: f64,
+    #[serde(default)]
+    pub mechanic: Option<String>,
+    /// Routine service that restarts the asset's service interval
+    #[serde(deserialize_with = "de_flag")]
+    pub scheduled_service: bool,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl MaintenanceRecord {
+    pub fn repair_service_ids(&self) -> Vec<String> {
+        self.repair_service_ids
+            .as_deref()
+            .and_then(|ids| serde_json::from_str(ids).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// A record with its catalog services spelled out
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceEntry {
+    #[serde(flatten)]
+    pub record: MaintenanceRecord,
+    pub repair_services: Vec<RepairService>,
+}
+
+impl MaintenanceEntry {
+    pub fn new(record: MaintenanceRecord, catalog: &[RepairService]) -> Self {
+        let ids = record.repair_service_ids();
+        Self {
+            repair_services: catalog.iter().filter(|s| ids.contains(&s.id)).cloned().collect(),
+            record,
+        }
+    }
+}
+
+/// POST /api/admin/fleet/assets/:id/maintenance - open a record, or log finished work
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateMaintenanceRequest {
+    /// YYYY-MM-DD, defaults to today
+    #[serde(default)]
+    pub date: Option<String>,
+    pub work_done: String,
+    #[serde(default)]
+    pub repair_service_ids: Vec<String>,
+    #[serde(default)]
+    pub parts: Option<String>,
+    #[serde(default)]
+    pub cost: Option<f64>,
+    #[serde(default)]
+    pub mechanic: Option<String>,
+    #[serde(default)]
+    pub scheduled_service: bool,
+    /// Record work already done without taking the asset out of service
+    #[serde(default)]
+    pub completed: bool,
+}
+
+impl CreateMaintenanceRequest {
+    pub fn validate(&self, catalog: &[RepairService]) -> Result<(), String> {
+        if self.work_done.trim().is_empty() || self.work_done.len() > 2000 {
+            return Err("Describe the work (2000 characters max)".to_string());
+        }
+        validate_cost(self.cost)?;
+        validate_services(&self.repair_service_ids, catalog)?;
+        if let Some(date) = &self.date {
+            parse_date(date).ok_or_else(|| "Invalid date (YYYY-MM-DD)".to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// POST /api/admin/maintenance/:id - omitted fields are kept; `complete` closes the record
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateMaintenanceRequest {
+    pub work_done: Option<String>,
+    pub repair_service_ids: Option<Vec<String>>,
+    pub parts: Option<String>,
+    pub cost: Option<f64>,
+    pub mechanic: Option<String>,
+    pub scheduled_service: Option<bool>,
+    #[serde(default)]
+    pub complete: bool,
+    /// YYYY-MM-DD, defaults to today
+    pub completed_on: Option<String>,
+}
+
+impl UpdateMaintenanceRequest {
+    pub fn validate(&self, catalog: &[RepairService]) -> Result<(), String> {
+        if let Some(work) = &self.work_done {
+            if work.trim().is_empty() || work.len() > 2000 {
+                return Err("Describe the work (2000 characters max)".to_string());
+            }
+        }
+        validate_cost(self.cost)?;
+        if let Some(ids) = &self.repair_service_ids {
+            validate_services(ids, catalog)?;
+        }
+        if let Some(date) = &self.completed_on {
+            parse_date(date).ok_or_else(|| "Invalid completion date (YYYY-MM-DD)".to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_cost(cost: Option<f64>) -> Result<(), String> {
+    match cost {
+        Some(c) if !c.is_finite() || !(0.0..=100_000.0).contains(&c) => {
+            Err("Cost must be between $0 and $100,000".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_services(ids: &[String], catalog: &[RepairService]) -> Result<(), String> {
+    match ids.iter().find(|id| !catalog.iter().any(|s| &s.id == *id)) {
+        Some(id) => Err(format!("Unknown repair service {}", id)),
+        None => Ok(()),
+    }
+}
+
+/// How often one kind of equipment is due for routine service; either limit triggers it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceInterval {
+    pub category: FleetCategory,
+    #[serde(default)]
+    pub every_days: Option<u32>,
+    #[serde(default)]
+    pub every_rentals: Option<u32>,
+}
+
+/// Service intervals (SETTINGS KV `service_intervals`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceIntervals {
+    pub intervals: Vec<ServiceInterval>,
+}
+
+impl Default for ServiceIntervals {
+    fn default() -> Self {
+        let interval = |category, every_days, every_rentals| ServiceInterval {
+            category,
+            every_days: Some(every_days),
+            every_rentals: Some(every_rentals),
+        };
+        Self {
+            intervals: vec![
+                interval(FleetCategory::KayakSingle, 90, 60),
+                interval(FleetCategory::KayakTandem, 90, 60),
+                interval(FleetCategory::Canoe, 90, 60),
+                interval(FleetCategory::Bike, 60, 40),
+                interval(FleetCategory::Ebike, 30, 25),
+            ],
+        }
+    }
+}
+
+impl ServiceIntervals {
+    pub fn for_category(&self, category: FleetCategory) -> Option<&ServiceInterval> {
+        self.intervals.iter().find(|i| i.category == category)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, interval) in self.intervals.iter().enumerate() {
+            if self.intervals[..i].iter().any(|other| other.category == interval.category) {
+                return Err(format!("{} is listed twice", interval.category.label()));
+            }
+            if interval.every_days == Some(0) || interval.every_rentals == Some(0) {
+                return Err(format!("{}: intervals must be at least 1", interval.category.label()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An asset's service history, as read for the overdue report
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetServiceRow {
+    pub id: String,
+    pub name: String,
+    pub category: FleetCategory,
+    #[serde(deserialize_with = "de_flag")]
+    pub out_of_service: bool,
+    #[serde(default)]
+    pub last_service_on: Option<String>,
+    /// Last service, or the day the asset was registered
+    pub since: String,
+    pub rentals_since: u32,
+    pub open_records: u32,
+}
+
+/// Assets due within this many days are listed as coming up
+pub const SERVICE_DUE_SOON_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceDue {
+    pub asset_id: String,
+    pub name: String,
+    pub category: FleetCategory,
+    pub last_service_on: Option<String>,
+    pub due_on: Option<String>,
+    /// Negative when the date is still ahead
+    pub days_overdue: Option<i64>,
+    pub rentals_since: u32,
+    pub every_rentals: Option<u32>,
+    pub out_of_service: bool,
+    pub in_maintenance: bool,
+    pub reason: String,
+}
+
+impl ServiceDue {
+    /// Whether and why an asset needs routine service as of `today`
+    pub fn evaluate(row: &AssetServiceRow, interval: &ServiceInterval, today: NaiveDate) -> Option<Self> {
+        let since = parse_date(&row.since)?;
+        let due_on = interval.every_days.map(|d| since + chrono::Duration::days(d as i64));
+        let days_overdue = due_on.map(|d| (today - d).num_days());
+
+        let mut reasons = vec![];
+        if let (Some(due), Some(days)) = (due_on, days_overdue) {
+            if days >= 0 {
+                reasons.push(format!("service was due {}", due.format("%Y-%m-%d")));
+            } else if -days <= SERVICE_DUE_SOON_DAYS {
+                reasons.push(format!("service due {}", due.format("%Y-%m-%d")));
+            }
+        }
+        if let Some(limit) = interval.every_rentals {
+            if row.rentals_since >= limit {
+                reasons.push(format!("{} rentals since last service (every {})", row.rentals_since, limit));
+            }
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            asset_id: row.id.clone(),
+            name: row.name.clone(),
+            category: row.category,
+            last_service_on: row.last_service_on.clone(),
+            due_on: due_on.map(|d| d.format("%Y-%m-%d").to_string()),
+            days_overdue,
+            rentals_since: row.rentals_since,
+            every_rentals: interval.every_rentals,
+            out_of_service: row.out_of_service,
+            in_maintenance: row.open_records > 0,
+            reason: reasons.join("; "),
+        })
+    }
+
+    pub fn is_overdue(&self) -> bool {
+        self.days_overdue.is_some_and(|d| d >= 0) || self.every_rentals.is_some_and(|limit| self.rentals_since >= limit)
+    }
+}
+
+/// GET /api/admin/maintenance/overdue
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceReport {
+    pub as_of: String,
+    /// Most overdue first
+    pub overdue: Vec<ServiceDue>,
+    pub due_soon: Vec<ServiceDue>,
+}
+
+impl ServiceReport {
+    pub fn build(rows: &[AssetServiceRow], intervals: &ServiceIntervals, today: NaiveDate) -> Self {
+        let (mut overdue, mut due_soon): (Vec<ServiceDue>, Vec<ServiceDue>) = rows
+            .iter()
+            .filter_map(|row| ServiceDue::evaluate(row, intervals.for_category(row.category)?, today))
+            .partition(ServiceDue::is_overdue);
+        overdue.sort_by(|a, b| b.days_overdue.unwrap_or(i64::MIN).cmp(&a.days_overdue.unwrap_or(i64::MIN)));
+        due_soon.sort_by(|a, b| a.due_on.cmp(&b.due_on));
+        Self {
+            as_of: today.format("%Y-%m-%d").to_string(),
+            overdue,
+            due_soon,
+        }
+    }
+}
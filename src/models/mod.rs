@@ -1,17 +1,45 @@
 //! Data models for the application
 
 mod flow;
+mod river_network;
+mod flow_forecast;
 mod weather;
 mod moon;
 mod services;
 mod store;
 mod settings;
 mod admin;
+mod waiver;
+mod waiver_group;
+mod booking;
+mod fleet;
+mod pricing;
+mod promo;
+mod gift_card;
+mod payment;
+mod shuttle;
+mod rental;
+mod maintenance;
+mod work_order;
 
 pub use flow::*;
+pub use river_network::*;
+pub use flow_forecast::*;
 pub use weather::*;
 pub use moon::*;
 pub use services::*;
 pub use store::*;
 pub use settings::*;
 pub use admin::*;
+pub use waiver::*;
+pub use waiver_group::*;
+pub use booking::*;
+pub use fleet::*;
+pub use pricing::*;
+pub use promo::*;
+pub use gift_card::*;
+pub use payment::*;
+pub use shuttle::*;
+pub use rental::*;
+pub use maintenance::*;
+pub use work_order::*;
@@ -67,7 +67,8 @@ pub struct SunData {
 }
 
 impl SunData {
-    pub fn calculate(year: i32, month: u32, day: u32, lat: f64, lon: f64) -> Self {
+    /// Times are local to `utc_offset_hours` (e.g. -5.0 for CDT)
+    pub fn calculate(year: i32, month: u32, day: u32, lat: f64, lon: f64, utc_offset_hours: f64) -> Self {
         // Day of year
         let n1: f64 = (275 * month as i32 / 9) as f64;
         let n2: f64 = ((month as i32 + 9) / 12) as f64;
@@ -130,9 +131,9 @@ impl SunData {
         let t_utc_set: f64 = h_set + ra_set - (0.06571 * t_set) - 6.622;
         let ut_set: f64 = ((t_utc_set - lng_hour) % 24.0 + 24.0) % 24.0;
         
-        // Convert to local time (CST = UTC-6)
-        let local_rise: f64 = (ut_rise - 6.0 + 24.0) % 24.0;
-        let local_set: f64 = (ut_set - 6.0 + 24.0) % 24.0;
+        // Convert to local time
+        let local_rise: f64 = (ut_rise + utc_offset_hours + 24.0) % 24.0;
+        let local_set: f64 = (ut_set + utc_offset_hours + 24.0) % 24.0;
         let local_noon: f64 = (local_rise + local_set) / 2.0;
         
         let day_length_hours_raw: f64 = local_set - local_rise;
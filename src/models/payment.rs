@@ -0,0 +1,111 @@
+//! Booking payments: deposits and full prepayment by card
+
+use serde::{Deserialize, Serialize};
+use super::gift_card::to_cents;
+use super::pricing::PriceQuote;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentKind {
+    Deposit,
+    Full,
+}
+
+impl PaymentKind {
+    pub fn slug(&self) -> &'static str {
+        match self {
+            PaymentKind::Deposit => "deposit",
+            PaymentKind::Full => "full",
+        }
+    }
+}
+
+/// A row in `payments`, following the processor's intent
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentStatus {
+    Pending,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+/// `bookings.payment_status`, derived from its succeeded payments
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BookingPaymentStatus {
+    #[default]
+    Unpaid,
+    Partial,
+    Paid,
+}
+
+impl BookingPaymentStatus {
+    pub fn for_amounts(paid_cents: i64, due_cents: i64) -> Self {
+        if paid_cents <= 0 {
+            BookingPaymentStatus::Unpaid
+        } else if paid_cents < due_cents {
+            BookingPaymentStatus::Partial
+        } else {
+            BookingPaymentStatus::Paid
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payment {
+    pub id: String,
+    pub booking_id: String,
+    pub provider: String,
+    pub intent_id: String,
+    pub kind: PaymentKind,
+    pub amount_cents: i64,
+    pub currency: String,
+    pub status: PaymentStatus,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// What's owed on a booking and what the next payment of `kind` should be
+pub fn payment_amount(quote: &PriceQuote, deposit_percent: f64, paid_cents: i64, kind: PaymentKind) -> Result<i64, String> {
+    let due = to_cents(quote.amount_due);
+    let remaining = due - paid_cents;
+    if remaining <= 0 {
+        return Err("This booking is already paid in full".to_string());
+    }
+    match kind {
+        PaymentKind::Full => Ok(remaining),
+        PaymentKind::Deposit if paid_cents > 0 => Err("The deposit for this booking is already paid".to_string()),
+        PaymentKind::Deposit => {
+            let deposit = to_cents(quote.amount_due * deposit_percent / 100.0);
+            if deposit <= 0 {
+                return Err("No deposit is required for this booking".to_string());
+            }
+            Ok(deposit.min(remaining))
+        }
+    }
+}
+
+/// POST /api/payments/intent - the customer pays against their confirmation number
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePaymentRequest {
+    pub confirmation_number: String,
+    pub email: String,
+    pub kind: PaymentKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentIntentResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<String>,
+    /// Handed to the processor's browser SDK to collect the card
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publishable_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+}
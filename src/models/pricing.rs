@@ -0,0 +1,447 @@
+//! Rental price list: unit rates, weekend and seasonal pricing, group discounts, late fees
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use super::booking::BookingService;
+use super::fleet::FleetCategory;
+
+/// What a rate is charged per
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceUnit {
+    Person,
+    Day,
+    Hour,
+}
+
+/// Rates in effect between two MM-DD dates (inclusive; may wrap past New Year)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonalRate {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+    pub weekday: f64,
+    #[serde(default)]
+    pub weekend: Option<f64>,
+}
+
+impl SeasonalRate {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        let day = date.format("%m-%d").to_string();
+        if self.start <= self.end {
+            day >= self.start && day <= self.end
+        } else {
+            day >= self.start || day <= self.end
+        }
+    }
+}
+
+/// Price for one bookable service. Weekend rates apply Saturday and Sunday and
+/// fall back to the weekday rate when unset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServicePrice {
+    pub service: BookingService,
+    pub unit: PriceUnit,
+    pub weekday: f64,
+    #[serde(default)]
+    pub weekend: Option<f64>,
+    #[serde(default)]
+    pub seasonal: Vec<SeasonalRate>,
+}
+
+impl ServicePrice {
+    /// Rate per unit on `date`, and the season name when a seasonal rate applies
+    pub fn rate_on(&self, date: NaiveDate) -> (f64, Option<&str>) {
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        match self.seasonal.iter().find(|s| s.covers(date)) {
+            Some(season) => {
+                let rate = if weekend { season.weekend.unwrap_or(season.weekday) } else { season.weekday };
+                (rate, Some(season.name.as_str()))
+            }
+            None => {
+                let rate = if weekend { self.weekend.unwrap_or(self.weekday) } else { self.weekday };
+                (rate, None)
+            }
+        }
+    }
+
+    /// Lowest rate on the list, for "From $X" copy
+    pub fn starting_at(&self) -> f64 {
+        self.seasonal
+            .iter()
+            .flat_map(|s| [Some(s.weekday), s.weekend])
+            .chain([Some(self.weekday), self.weekend])
+            .flatten()
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Percent off for parties of at least `min_party` people
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupDiscount {
+    pub min_party: u32,
+    pub percent: f64,
+}
+
+/// Late return charge per unit: `amount` for each started `every_minutes` past the
+/// expected return, once a unit is more than `grace_minutes` late
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LateFeeRule {
+    /// Equipment this rule covers; a rule without one covers everything else
+    #[serde(default)]
+    pub category: Option<FleetCategory>,
+    pub grace_minutes: u32,
+    pub every_minutes: u32,
+    pub amount: f64,
+    #[serde(default)]
+    pub max_per_unit: Option<f64>,
+}
+
+impl LateFeeRule {
+    pub fn fee(&self, late_minutes: i64) -> f64 {
+        if late_minutes <= self.grace_minutes as i64 || self.every_minutes == 0 {
+            return 0.0;
+        }
+        let periods = (late_minutes as f64 / self.every_minutes as f64).ceil();
+        let fee = round_cents(periods * self.amount);
+        self.max_per_unit.map_or(fee, |max| fee.min(max))
+    }
+}
+
+/// Price list (SETTINGS KV `price_list`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceList {
+    pub services: Vec<ServicePrice>,
+    #[serde(default)]
+    pub group_discounts: Vec<GroupDiscount>,
+    /// Share of the total taken up front when a customer pays a deposit
+    #[serde(default = "default_deposit_percent")]
+    pub deposit_percent: f64,
+    #[serde(default = "default_late_fees")]
+    pub late_fees: Vec<LateFeeRule>,
+}
+
+fn default_deposit_percent() -> f64 {
+    25.0
+}
+
+fn default_late_fees() -> Vec<LateFeeRule> {
+    let rule = |category, amount, max| LateFeeRule {
+        category,
+        grace_minutes: 15,
+        every_minutes: 30,
+        amount,
+        max_per_unit: Some(max),
+    };
+    vec![rule(None, 5.0, 40.0), rule(Some(FleetCategory::Ebike), 10.0, 80.0)]
+}
+
+impl Default for PriceList {
+    fn default() -> Self {
+        let price = |service, unit, weekday| ServicePrice {
+            service,
+            unit,
+            weekday,
+            weekend: None,
+            seasonal: vec![],
+        };
+        Self {
+            services: vec![
+                price(BookingService::KayakSingle, PriceUnit::Day, 45.0),
+                price(BookingService::KayakTandem, PriceUnit::Day, 65.0),
+                price(BookingService::Canoe, PriceUnit::Day, 55.0),
+                price(BookingService::Tubing, PriceUnit::Person, 25.0),
+                price(BookingService::Bike, PriceUnit::Day, 35.0),
+                price(BookingService::EbikeRental, PriceUnit::Day, 75.0),
+            ],
+            group_discounts: vec![GroupDiscount { min_party: 10, percent: 10.0 }],
+            deposit_percent: default_deposit_percent(),
+            late_fees: default_late_fees(),
+        }
+    }
+}
+
+impl PriceList {
+    pub fn for_service(&self, service: BookingService) -> Option<&ServicePrice> {
+        self.services.iter().find(|p| p.service == service)
+    }
+
+    /// Largest discount the party size qualifies for
+    pub fn group_discount(&self, party_size: u32) -> f64 {
+        self.group_discounts
+            .iter()
+            .filter(|d| party_size >= d.min_party)
+            .map(|d| d.percent)
+            .fold(0.0, f64::max)
+    }
+
+    /// Late charge for one unit of `category` returned `late_minutes` past due
+    pub fn late_fee(&self, category: FleetCategory, late_minutes: i64) -> f64 {
+        self.late_fees
+            .iter()
+            .find(|r| r.category == Some(category))
+            .or_else(|| self.late_fees.iter().find(|r| r.category.is_none()))
+            .map_or(0.0, |rule| rule.fee(late_minutes))
+    }
+
+    /// Price a booking. `hours` is the rental length, used by hourly rates;
+    /// day rates charge whole days.
+    pub fn quote(
+        &self,
+        service: BookingService,
+        date: NaiveDate,
+        party_size: u32,
+        quantity: u32,
+        hours: f64,
+    ) -> Option<PriceQuote> {
+        let price = self.for_service(service)?;
+        let (unit_price, season) = price.rate_on(date);
+        let units = match price.unit {
+            PriceUnit::Person => party_size as f64,
+            PriceUnit::Day => quantity as f64 * (hours / 24.0).ceil().max(1.0),
+            PriceUnit::Hour => quantity as f64 * hours.ceil().max(1.0),
+        };
+        let subtotal = round_cents(unit_price * units);
+        let discount_percent = self.group_discount(party_size);
+        let discount = round_cents(subtotal * discount_percent / 100.0);
+        Some(PriceQuote {
+            service,
+            unit: price.unit,
+            unit_price,
+            units,
+            season: season.map(str::to_string),
+            subtotal,
+            discount_percent,
+            discount,
+            promo_code: None,
+            promo_discount: 0.0,
+            total: round_cents(subtotal - discount),
+            gift_card: 0.0,
+            amount_due: round_cents(subtotal - discount),
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_rate = |rate: f64| rate.is_finite() && (0.0..=10_000.0).contains(&rate);
+        let valid_day = |day: &str| NaiveDate::parse_from_str(&format!("2024-{}", day), "%Y-%m-%d").is_ok();
+        for (i, price) in self.services.iter().enumerate() {
+            let label = price.service.label();
+            if self.services[..i].iter().any(|p| p.service == price.service) {
+                return Err(format!("{} is listed twice", label));
+            }
+            if !valid_rate(price.weekday) || !price.weekend.map(valid_rate).unwrap_or(true) {
+                return Err(format!("{}: rates must be between $0 and $10,000", label));
+            }
+            for season in &price.seasonal {
+                if season.name.trim().is_empty() {
+                    return Err(format!("{}: seasonal rates need a name", label));
+                }
+                if !valid_day(&season.start) || !valid_day(&season.end) {
+                    return Err(format!("{}: season dates must be MM-DD", label));
+                }
+                if !valid_rate(season.weekday) || !season.weekend.map(valid_rate).unwrap_or(true) {
+                    return Err(format!("{}: {} rates must be between $0 and $10,000", label, season.name));
+                }
+            }
+        }
+        for discount in &self.group_discounts {
+            if discount.min_party < 2 || !(0.0..=100.0).contains(&discount.percent) {
+                return Err("Group discounts need a party of 2+ and 0-100 percent".to_string());
+            }
+        }
+        if !(0.0..=100.0).contains(&self.deposit_percent) {
+            return Err("Deposit must be 0-100 percent".to_string());
+        }
+        for (i, rule) in self.late_fees.iter().enumerate() {
+            if self.late_fees[..i].iter().any(|r| r.category == rule.category) {
+                return Err("Only one late fee rule per equipment type".to_string());
+            }
+            if rule.every_minutes == 0 || !valid_rate(rule.amount) || !rule.max_per_unit.map(valid_rate).unwrap_or(true) {
+                return Err("Late fees need a period of 1+ minutes and amounts between $0 and $10,000".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Price breakdown for one booking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceQuote {
+    pub service: BookingService,
+    pub unit: PriceUnit,
+    pub unit_price: f64,
+    pub units: f64,
+    pub season: Option<String>,
+    pub subtotal: f64,
+    pub discount_percent: f64,
+    pub discount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promo_code: Option<String>,
+    pub promo_discount: f64,
+    pub total: f64,
+    /// Paid with a gift card at checkout
+    pub gift_card: f64,
+    pub amount_due: f64,
+}
+
+/// GET /api/pricing - the price list plus a "from" price per service
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingResponse {
+    pub services: Vec<PricingSummary>,
+    pub group_discounts: Vec<GroupDiscount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingSummary {
+    #[serde(flatten)]
+    pub price: ServicePrice,
+    pub label: String,
+    pub starting_at: f64,
+    pub today: f64,
+}
+
+impl PricingResponse {
+    pub fn new(list: &PriceList, today: NaiveDate) -> Self {
+        Self {
+            services: list
+                .services
+                .iter()
+                .map(|p| PricingSummary {
+                    label: p.service.label().to_string(),
+                    starting_at: p.starting_at(),
+                    today: p.rate_on(today).0,
+                    price: p.clone(),
+                })
+                .collect(),
+            group_discounts: list.group_discounts.clone(),
+        }
+    }
+}
+
+pub fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn kayak_with_holiday_season() -> ServicePrice {
+        ServicePrice {
+            service: BookingService::KayakSingle,
+            unit: PriceUnit::Day,
+            weekday: 45.0,
+            weekend: Some(55.0),
+            seasonal: vec![SeasonalRate {
+                name: "Holiday".to_string(),
+                start: "12-15".to_string(),
+                end: "01-05".to_string(),
+                weekday: 30.0,
+                weekend: Some(40.0),
+            }],
+        }
+    }
+
+    fn late_rule() -> LateFeeRule {
+        LateFeeRule {
+            category: None,
+            grace_minutes: 15,
+            every_minutes: 30,
+            amount: 5.0,
+            max_per_unit: Some(40.0),
+        }
+    }
+
+    #[test]
+    fn seasons_wrap_past_new_year() {
+        let season = &kayak_with_holiday_season().seasonal[0];
+        assert!(season.covers(date("2025-12-15")));
+        assert!(season.covers(date("2025-12-31")));
+        assert!(season.covers(date("2026-01-01")));
+        assert!(season.covers(date("2026-01-05")));
+        assert!(!season.covers(date("2025-12-14")));
+        assert!(!season.covers(date("2026-01-06")));
+        assert!(!season.covers(date("2026-07-04")));
+    }
+
+    #[test]
+    fn weekend_rates_apply_inside_a_wrapping_season() {
+        let price = kayak_with_holiday_season();
+        // Saturdays either side of New Year
+        assert_eq!(price.rate_on(date("2025-12-20")), (40.0, Some("Holiday")));
+        assert_eq!(price.rate_on(date("2026-01-03")), (40.0, Some("Holiday")));
+        assert_eq!(price.rate_on(date("2026-01-05")), (30.0, Some("Holiday")));
+        // Outside the season the regular weekend and weekday rates apply
+        assert_eq!(price.rate_on(date("2026-07-04")), (55.0, None));
+        assert_eq!(price.rate_on(date("2026-07-06")), (45.0, None));
+        assert_eq!(price.starting_at(), 30.0);
+    }
+
+    #[test]
+    fn a_weekend_rate_left_unset_falls_back_to_the_weekday_rate() {
+        let mut price = kayak_with_holiday_season();
+        price.weekend = None;
+        price.seasonal[0].weekend = None;
+        assert_eq!(price.rate_on(date("2026-07-04")), (45.0, None));
+        assert_eq!(price.rate_on(date("2026-01-03")), (30.0, Some("Holiday")));
+    }
+
+    #[test]
+    fn quotes_whole_days_and_the_largest_group_discount() {
+        let mut list = PriceList::default();
+        list.group_discounts.push(GroupDiscount { min_party: 20, percent: 15.0 });
+
+        // Three kayaks for 30 hours is two days each
+        let quote = list.quote(BookingService::KayakSingle, date("2026-07-06"), 12, 3, 30.0).unwrap();
+        assert_eq!(quote.units, 6.0);
+        assert_eq!(quote.subtotal, 270.0);
+        assert_eq!(quote.discount_percent, 10.0);
+        assert_eq!(quote.discount, 27.0);
+        assert_eq!(quote.total, 243.0);
+        assert_eq!(quote.amount_due, 243.0);
+
+        let quote = list.quote(BookingService::Tubing, date("2026-07-06"), 24, 1, 3.0).unwrap();
+        assert_eq!(quote.subtotal, 600.0);
+        assert_eq!(quote.discount_percent, 15.0);
+        assert_eq!(quote.total, 510.0);
+    }
+
+    #[test]
+    fn no_late_fee_inside_the_grace_period() {
+        let rule = late_rule();
+        assert_eq!(rule.fee(-30), 0.0);
+        assert_eq!(rule.fee(0), 0.0);
+        assert_eq!(rule.fee(15), 0.0);
+    }
+
+    #[test]
+    fn late_fees_charge_each_started_period() {
+        let rule = late_rule();
+        // Periods count from the expected return, not from the end of the grace period
+        assert_eq!(rule.fee(16), 5.0);
+        assert_eq!(rule.fee(30), 5.0);
+        assert_eq!(rule.fee(31), 10.0);
+        assert_eq!(rule.fee(95), 20.0);
+    }
+
+    #[test]
+    fn late_fees_stop_at_the_maximum() {
+        let rule = late_rule();
+        assert_eq!(rule.fee(240), 40.0);
+        assert_eq!(rule.fee(600), 40.0);
+        let uncapped = LateFeeRule { max_per_unit: None, ..late_rule() };
+        assert_eq!(uncapped.fee(600), 100.0);
+    }
+
+    #[test]
+    fn late_fees_use_the_equipment_rule_before_the_general_one() {
+        let list = PriceList::default();
+        assert_eq!(list.late_fee(FleetCategory::Ebike, 45), 20.0);
+        assert_eq!(list.late_fee(FleetCategory::KayakSingle, 45), 10.0);
+        assert_eq!(list.late_fee(FleetCategory::Ebike, 1000), 80.0);
+    }
+}
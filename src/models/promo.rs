@@ -0,0 +1,297 @@
+//! Promo codes: percent or fixed discounts with eligibility, windows and caps
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use super::booking::BookingService;
+use super::pricing::{round_cents, PriceQuote};
+use super::waiver::{de_flag, de_json, parse_date};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscountType {
+    Percent,
+    Fixed,
+}
+
+/// A row in `promo_codes`, with its redemption count when listed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoCode {
+    pub code: String,
+    pub description: String,
+    pub discount_type: DiscountType,
+    pub amount: f64,
+    /// Eligible services; empty means all
+    #[serde(default, deserialize_with = "de_json")]
+    pub services: Vec<BookingService>,
+    #[serde(default)]
+    pub valid_from: Option<String>,
+    #[serde(default)]
+    pub valid_to: Option<String>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    #[serde(deserialize_with = "de_flag")]
+    pub once_per_email: bool,
+    #[serde(deserialize_with = "de_flag")]
+    pub active: bool,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub uses: u32,
+}
+
+impl PromoCode {
+    /// Whether the code can be used for `service` today. `email_used` is whether
+    /// this customer already redeemed it.
+    pub fn check(&self, service: BookingService, today: NaiveDate, email_used: bool) -> Result<(), String> {
+        let invalid = || Err("That promo code is not valid".to_string());
+        if !self.active {
+            return invalid();
+        }
+        if self.valid_from.as_deref().and_then(parse_date).is_some_and(|from| today < from) {
+            return Err("That promo code is not active yet".to_string());
+        }
+        if self.valid_to.as_deref().and_then(parse_date).is_some_and(|to| today > to) {
+            return Err("That promo code has expired".to_string());
+        }
+        if self.max_uses.is_some_and(|max| self.uses >= max) {
+            return Err("That promo code has been fully redeemed".to_string());
+        }
+        if !self.services.is_empty() && !self.services.contains(&service) {
+            return Err(format!("That promo code doesn't apply to {}", service.label()));
+        }
+        if self.once_per_email && email_used {
+            return Err("You've already used that promo code".to_string());
+        }
+        Ok(())
+    }
+
+    /// Discount off `total`, never more than the total itself
+    pub fn discount_on(&self, total: f64) -> f64 {
+        let discount = match self.discount_type {
+            DiscountType::Percent => total * self.amount / 100.0,
+            DiscountType::Fixed => self.amount,
+        };
+        round_cents(discount.clamp(0.0, total))
+    }
+}
+
+impl PriceQuote {
+    /// Apply a promo code after any group discount
+    pub fn with_promo(mut self, promo: &PromoCode) -> Self {
+        self.promo_discount = promo.discount_on(self.total);
+        self.promo_code = Some(promo.code.clone());
+        self.total = round_cents(self.total - self.promo_discount);
+        self.amount_due = self.total;
+        self
+    }
+}
+
+/// Promo codes are matched case-insensitively and stored upper-case
+pub fn normalize_promo_code(raw: &str) -> Option<String> {
+    let code = raw.trim().to_uppercase();
+    let valid = (3..=32).contains(&code.len())
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(code)
+}
+
+/// POST /api/admin/promos and /api/admin/promos/:code
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromoCodeRequest {
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub description: String,
+    pub discount_type: DiscountType,
+    pub amount: f64,
+    #[serde(default)]
+    pub services: Vec<BookingService>,
+    #[serde(default)]
+    pub valid_from: Option<String>,
+    #[serde(default)]
+    pub valid_to: Option<String>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    #[serde(default)]
+    pub once_per_email: bool,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl PromoCodeRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.discount_type {
+            DiscountType::Percent if !(self.amount > 0.0 && self.amount <= 100.0) => {
+                return Err("Percent off must be between 0 and 100".to_string());
+            }
+            DiscountType::Fixed if !(self.amount > 0.0 && self.amount <= 10_000.0) => {
+                return Err("Amount off must be between $0 and $10,000".to_string());
+            }
+            _ => {}
+        }
+        let day = |value: &Option<String>| -> Result<Option<NaiveDate>, String> {
+            match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                Some(v) => parse_date(v).map(Some).ok_or_else(|| "Dates must be YYYY-MM-DD".to_string()),
+                None => Ok(None),
+            }
+        };
+        if let (Some(from), Some(to)) = (day(&self.valid_from)?, day(&self.valid_to)?) {
+            if to < from {
+                return Err("The end date is before the start date".to_string());
+            }
+        }
+        if self.max_uses == Some(0) {
+            return Err("Usage cap must be at least 1 (leave empty for unlimited)".to_string());
+        }
+        if self.description.len() > 200 {
+            return Err("Description is limited to 200 characters".to_string());
+        }
+        Ok(())
+    }
+
+    /// Blank dates are stored as NULL
+    pub fn window(&self) -> (Option<String>, Option<String>) {
+        let clean = |v: &Option<String>| v.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        (clean(&self.valid_from), clean(&self.valid_to))
+    }
+}
+
+/// POST /api/promo/validate
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatePromoRequest {
+    pub code: String,
+    pub service: String,
+    pub date: String,
+    pub party_size: u32,
+    #[serde(default)]
+    pub quantity: Option<u32>,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidatePromoResponse {
+    pub valid: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<PriceQuote>,
+}
+
+/// A row in `promo_redemptions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoRedemption {
+    pub id: String,
+    pub code: String,
+    pub booking_id: String,
+    pub email: String,
+    pub subtotal: f64,
+    pub discount: f64,
+    pub redeemed_at: String,
+}
+
+/// One line of the redemption report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoReportRow {
+    pub code: String,
+    pub redemptions: u32,
+    pub customers: u32,
+    pub total_subtotal: f64,
+    pub total_discount: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::pricing::PriceList;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn promo(discount_type: DiscountType, amount: f64) -> PromoCode {
+        PromoCode {
+            code: "SUMMER".to_string(),
+            description: String::new(),
+            discount_type,
+            amount,
+            services: vec![],
+            valid_from: None,
+            valid_to: None,
+            max_uses: None,
+            once_per_email: false,
+            active: true,
+            created_by: "admin".to_string(),
+            created_at: "2026-05-01T00:00:00+00:00".to_string(),
+            updated_at: "2026-05-01T00:00:00+00:00".to_string(),
+            uses: 0,
+        }
+    }
+
+    /// Twelve tubers at $25 on a weekday: $300, less the 10% group discount
+    fn group_quote() -> PriceQuote {
+        PriceList::default().quote(BookingService::Tubing, date("2026-07-06"), 12, 1, 3.0).unwrap()
+    }
+
+    #[test]
+    fn promo_applies_after_the_group_discount() {
+        let quote = group_quote().with_promo(&promo(DiscountType::Percent, 20.0));
+        assert_eq!(quote.subtotal, 300.0);
+        assert_eq!(quote.discount, 30.0);
+        // 20% of the $270 left, not of the $300 subtotal
+        assert_eq!(quote.promo_discount, 54.0);
+        assert_eq!(quote.total, 216.0);
+        assert_eq!(quote.amount_due, 216.0);
+        assert_eq!(quote.promo_code.as_deref(), Some("SUMMER"));
+    }
+
+    #[test]
+    fn a_fixed_promo_larger_than_the_total_is_clamped() {
+        let quote = group_quote().with_promo(&promo(DiscountType::Fixed, 500.0));
+        assert_eq!(quote.promo_discount, 270.0);
+        assert_eq!(quote.total, 0.0);
+        assert_eq!(quote.amount_due, 0.0);
+    }
+
+    #[test]
+    fn checks_the_validity_window_inclusively() {
+        let mut code = promo(DiscountType::Percent, 10.0);
+        code.valid_from = Some("2026-06-01".to_string());
+        code.valid_to = Some("2026-08-31".to_string());
+        let check = |day: &str| code.check(BookingService::Tubing, date(day), false);
+        assert_eq!(check("2026-05-31"), Err("That promo code is not active yet".to_string()));
+        assert_eq!(check("2026-06-01"), Ok(()));
+        assert_eq!(check("2026-08-31"), Ok(()));
+        assert_eq!(check("2026-09-01"), Err("That promo code has expired".to_string()));
+    }
+
+    #[test]
+    fn checks_services_caps_and_repeat_customers() {
+        let today = date("2026-07-06");
+        let mut code = promo(DiscountType::Percent, 10.0);
+        code.services = vec![BookingService::Tubing];
+        assert!(code.check(BookingService::Tubing, today, false).is_ok());
+        assert!(code.check(BookingService::Canoe, today, false).is_err());
+
+        code.once_per_email = true;
+        assert_eq!(code.check(BookingService::Tubing, today, true), Err("You've already used that promo code".to_string()));
+
+        code.max_uses = Some(5);
+        code.uses = 5;
+        assert_eq!(code.check(BookingService::Tubing, today, false), Err("That promo code has been fully redeemed".to_string()));
+
+        code.active = false;
+        assert_eq!(code.check(BookingService::Tubing, today, false), Err("That promo code is not valid".to_string()));
+    }
+
+    #[test]
+    fn normalizes_codes() {
+        assert_eq!(normalize_promo_code("  summer-26 "), Some("SUMMER-26".to_string()));
+        assert_eq!(normalize_promo_code("ab"), None);
+        assert_eq!(normalize_promo_code("no spaces"), None);
+    }
+}
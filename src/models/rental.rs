@@ -0,0 +1,290 @@
+//! Rental transactions: which assets went out, with whom, and when they came back
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use super::booking::{Booking, BookingService};
+use super::fleet::FleetCategory;
+use super::pricing::round_cents;
+
+/// Local `YYYY-MM-DDTHH:MM`, the same form as booking windows
+pub const RENTAL_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+pub fn parse_rental_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), RENTAL_TIME_FORMAT).ok()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RentalStatus {
+    /// At least one asset hasn't come back
+    Out,
+    Returned,
+}
+
+/// A row in `rentals`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rental {
+    pub id: String,
+    #[serde(default)]
+    pub booking_id: Option<String>,
+    pub customer_name: String,
+    #[serde(default)]
+    pub customer_phone: Option<String>,
+    #[serde(default)]
+    pub customer_email: Option<String>,
+    pub checked_out_at: String,
+    pub expected_return_at: String,
+    #[serde(default)]
+    pub checked_in_at: Option<String>,
+    pub status: RentalStatus,
+    /// Sum of the late fees on returned assets
+    pub late_fee: f64,
+    #[serde(default)]
+    pub checked_out_by: Option<String>,
+    #[serde(default)]
+    pub checked_in_by: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A row in `rental_assets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RentalAsset {
+    pub rental_id: String,
+    pub asset_id: String,
+    pub asset_name: String,
+    pub category: FleetCategory,
+    #[serde(default)]
+    pub returned_at: Option<String>,
+    pub late_minutes: i64,
+    pub late_fee: f64,
+    #[serde(default)]
+    pub return_note: Option<String>,
+}
+
+/// `rental_assets` joined to its rental, for the out-now list and reconciliation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RentalAssetLine {
+    pub rental_id: String,
+    pub asset_id: String,
+    pub asset_name: String,
+    pub category: FleetCategory,
+    #[serde(default)]
+    pub returned_at: Option<String>,
+    pub late_minutes: i64,
+    pub late_fee: f64,
+    #[serde(default)]
+    pub return_note: Option<String>,
+    pub customer_name: String,
+    #[serde(default)]
+    pub customer_phone: Option<String>,
+    pub checked_out_at: String,
+    pub expected_return_at: String,
+}
+
+/// Minutes past `expected_return_at` at `at`; zero when on time
+pub fn late_minutes(expected_return_at: &str, at: NaiveDateTime) -> i64 {
+    parse_rental_time(expected_return_at)
+        .map(|due| (at - due).num_minutes().max(0))
+        .unwrap_or(0)
+}
+
+/// A rental with its assets
+#[derive(Debug, Clone, Serialize)]
+pub struct RentalDetail {
+    #[serde(flatten)]
+    pub rental: Rental,
+    pub assets: Vec<RentalAsset>,
+}
+
+/// POST /api/admin/rentals - hand equipment over. With a booking, customer details and
+/// the expected return come from it unless given.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RentalCheckOut {
+    #[serde(default)]
+    pub booking_id: Option<String>,
+    #[serde(default)]
+    pub customer_name: Option<String>,
+    #[serde(default)]
+    pub customer_phone: Option<String>,
+    #[serde(default)]
+    pub customer_email: Option<String>,
+    pub asset_ids: Vec<String>,
+    /// Local `YYYY-MM-DDTHH:MM`
+    #[serde(default)]
+    pub expected_return: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl RentalCheckOut {
+    /// Distinct asset ids in the order given
+    pub fn asset_ids(&self) -> Result<Vec<String>, String> {
+        let mut ids: Vec<String> = vec![];
+        for id in self.asset_ids.iter().map(|i| i.trim()).filter(|i| !i.is_empty()) {
+            if !ids.iter().any(|i| i == id) {
+                ids.push(id.to_string());
+            }
+        }
+        if ids.is_empty() {
+            return Err("Scan or choose at least one asset".to_string());
+        }
+        if ids.len() > 50 {
+            return Err("Check out at most 50 assets at a time".to_string());
+        }
+        Ok(ids)
+    }
+}
+
+/// POST /api/admin/rentals/:id/checkin - `asset_ids` empty returns everything still out
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RentalCheckIn {
+    #[serde(default)]
+    pub asset_ids: Vec<String>,
+    /// Damage or condition notes, kept on each returned asset
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// An asset on the water right now
+#[derive(Debug, Clone, Serialize)]
+pub struct OutAsset {
+    #[serde(flatten)]
+    pub line: RentalAssetLine,
+    pub overdue_minutes: i64,
+    /// What the late fee would be if it came back now
+    pub late_fee_now: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryOut {
+    pub category: FleetCategory,
+    pub label: String,
+    pub out: u32,
+    pub overdue: u32,
+}
+
+/// GET /api/admin/rentals/out
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentlyOut {
+    pub as_of: String,
+    pub total: u32,
+    pub overdue: u32,
+    pub by_category: Vec<CategoryOut>,
+    /// Most overdue first
+    pub assets: Vec<OutAsset>,
+}
+
+impl CurrentlyOut {
+    pub fn new(now: NaiveDateTime, assets: Vec<OutAsset>) -> Self {
+        let mut assets = assets;
+        assets.sort_by(|a, b| a.line.expected_return_at.cmp(&b.line.expected_return_at));
+        let by_category = FleetCategory::ALL
+            .iter()
+            .map(|&category| {
+                let out: Vec<&OutAsset> = assets.iter().filter(|a| a.line.category == category).collect();
+                CategoryOut {
+                    category,
+                    label: category.label().to_string(),
+                    out: out.len() as u32,
+                    overdue: out.iter().filter(|a| a.overdue_minutes > 0).count() as u32,
+                }
+            })
+            .filter(|c| c.out > 0)
+            .collect();
+        Self {
+            as_of: now.format(RENTAL_TIME_FORMAT).to_string(),
+            total: assets.len() as u32,
+            overdue: assets.iter().filter(|a| a.overdue_minutes > 0).count() as u32,
+            by_category,
+            assets,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryReconciliation {
+    pub category: FleetCategory,
+    pub label: String,
+    pub checked_out: u32,
+    pub returned: u32,
+    pub still_out: u32,
+}
+
+/// A confirmed booking for the day that never had equipment checked out
+#[derive(Debug, Clone, Serialize)]
+pub struct UnclaimedBooking {
+    pub booking_id: String,
+    pub confirmation_number: Option<String>,
+    pub customer_name: Option<String>,
+    pub service: BookingService,
+    pub start_time: String,
+    pub quantity: u32,
+}
+
+/// GET /api/admin/rentals/reconciliation?date= - end-of-day count of what went out and came back
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyReconciliation {
+    pub date: String,
+    pub rentals: u32,
+    pub assets_out: u32,
+    pub assets_returned: u32,
+    pub late_fees: f64,
+    pub by_category: Vec<CategoryReconciliation>,
+    pub late_returns: Vec<RentalAssetLine>,
+    /// Went out on the day and hasn't been checked back in
+    pub still_out: Vec<RentalAssetLine>,
+    /// Confirmed bookings with no rental: no-shows, or check-outs not recorded
+    pub unclaimed_bookings: Vec<UnclaimedBooking>,
+}
+
+impl DailyReconciliation {
+    /// `lines` are assets checked out on `date`; `bookings` are the day's confirmed bookings
+    /// and `claimed` the booking ids that have a rental.
+    pub fn build(date: NaiveDate, lines: Vec<RentalAssetLine>, bookings: &[Booking], claimed: &[String]) -> Self {
+        let by_category = FleetCategory::ALL
+            .iter()
+            .map(|&category| {
+                let of: Vec<&RentalAssetLine> = lines.iter().filter(|l| l.category == category).collect();
+                let back = of.iter().filter(|l| l.returned_at.is_some()).count() as u32;
+                CategoryReconciliation {
+                    category,
+                    label: category.label().to_string(),
+                    checked_out: of.len() as u32,
+                    returned: back,
+                    still_out: of.len() as u32 - back,
+                }
+            })
+            .filter(|c| c.checked_out > 0)
+            .collect();
+
+        let mut rental_ids: Vec<&str> = lines.iter().map(|l| l.rental_id.as_str()).collect();
+        rental_ids.sort_unstable();
+        rental_ids.dedup();
+
+        Self {
+            date: date.format("%Y-%m-%d").to_string(),
+            rentals: rental_ids.len() as u32,
+            assets_out: lines.len() as u32,
+            assets_returned: lines.iter().filter(|l| l.returned_at.is_some()).count() as u32,
+            late_fees: round_cents(lines.iter().map(|l| l.late_fee).sum()),
+            by_category,
+            late_returns: lines.iter().filter(|l| l.late_minutes > 0).cloned().collect(),
+            still_out: lines.iter().filter(|l| l.returned_at.is_none()).cloned().collect(),
+            unclaimed_bookings: bookings
+                .iter()
+                .filter(|b| !claimed.contains(&b.id))
+                .map(|b| UnclaimedBooking {
+                    booking_id: b.id.clone(),
+                    confirmation_number: b.confirmation_number.clone(),
+                    customer_name: b.customer_name.clone(),
+                    service: b.service,
+                    start_time: b.start_time.clone(),
+                    quantity: b.quantity,
+                })
+                .collect(),
+        }
+    }
+}
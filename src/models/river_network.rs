@@ -0,0 +1,208 @@
+//! Gauges watched along the river: the primary station plus upstream and tributary
+//! stations that give early warning of a surge
+
+use serde::{Deserialize, Serialize};
+use super::flow::{FlowData, FlowThresholds, FlowTrend};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StationRole {
+    /// The gauge at our launch; its status drives bookings
+    Primary,
+    Upstream,
+    Tributary,
+}
+
+impl StationRole {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StationRole::Primary => "Primary",
+            StationRole::Upstream => "Upstream",
+            StationRole::Tributary => "Tributary",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiverStation {
+    /// USGS site number
+    pub id: String,
+    pub name: String,
+    pub role: StationRole,
+    #[serde(default)]
+    pub river_mile: Option<f64>,
+    /// Roughly how long water takes to get from here to the primary station
+    #[serde(default)]
+    pub travel_hours: Option<f64>,
+    /// Flow limits for this gauge; the primary falls back to the saved thresholds
+    #[serde(default)]
+    pub thresholds: Option<FlowThresholds>,
+    /// A rise at least this fast is flagged even if it's gentle relative to the flow
+    #[serde(default)]
+    pub sharp_rise_cfs_per_hour: Option<f64>,
+}
+
+impl RiverStation {
+    /// A lone primary station, used until a network is configured
+    pub fn primary(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: "Wisconsin River at Sauk City".to_string(),
+            role: StationRole::Primary,
+            river_mile: None,
+            travel_hours: None,
+            thresholds: None,
+            sharp_rise_cfs_per_hour: None,
+        }
+    }
+
+    /// Thresholds used to rate this station's flow, if it has any; the primary
+    /// falls back to the saved `flow_thresholds`
+    pub fn flow_thresholds(&self, saved: &FlowThresholds) -> Option<FlowThresholds> {
+        match (&self.thresholds, self.role) {
+            (Some(t), _) => Some(t.clone()),
+            (None, StationRole::Primary) => Some(saved.clone()),
+            (None, _) => None,
+        }
+    }
+
+    pub fn rising_sharply(&self, flow: &FlowData) -> bool {
+        flow.trend == Some(FlowTrend::RisingFast)
+            || matches!(
+                (flow.rate_cfs_per_hour, self.sharp_rise_cfs_per_hour),
+                (Some(rate), Some(limit)) if rate >= limit
+            )
+    }
+}
+
+/// Stations watched (SETTINGS KV `river_network`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiverNetwork {
+    pub stations: Vec<RiverStation>,
+}
+
+impl RiverNetwork {
+    /// The configured network, or just `primary_id` when none is configured
+    pub fn or_primary(self, primary_id: &str) -> Self {
+        if self.stations.is_empty() {
+            Self { stations: vec![RiverStation::primary(primary_id)] }
+        } else {
+            self
+        }
+    }
+
+    pub fn primary(&self) -> Option<&RiverStation> {
+        self.stations.iter().find(|s| s.role == StationRole::Primary)
+    }
+
+    pub fn station(&self, id: &str) -> Option<&RiverStation> {
+        self.stations.iter().find(|s| s.id == id)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stations.len() > 12 {
+            return Err("Watch at most 12 stations".to_string());
+        }
+        if !self.stations.is_empty() && self.stations.iter().filter(|s| s.role == StationRole::Primary).count() != 1 {
+            return Err("Mark exactly one station as primary".to_string());
+        }
+        for (i, station) in self.stations.iter().enumerate() {
+            if !(8..=15).contains(&station.id.len()) || !station.id.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(format!("{} is not a USGS site number", station.id));
+            }
+            if self.stations[..i].iter().any(|other| other.id == station.id) {
+                return Err(format!("Station {} is listed twice", station.id));
+            }
+            if station.name.trim().is_empty() {
+                return Err(format!("Station {} needs a name", station.id));
+            }
+            if station.travel_hours.is_some_and(|h| !(0.0..=240.0).contains(&h)) {
+                return Err(format!("{}: travel time must be 0-240 hours", station.name));
+            }
+            if let Some(t) = &station.thresholds {
+                t.validate().map_err(|e| format!("{}: {}", station.name, e))?;
+            }
+            if station.sharp_rise_cfs_per_hour.is_some_and(|r| r <= 0.0) {
+                return Err(format!("{}: sharp rise rate must be positive", station.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One gauge in the network summary
+#[derive(Debug, Clone, Serialize)]
+pub struct StationFlow {
+    pub station: RiverStation,
+    pub flow: Option<FlowData>,
+    /// Why the reading is missing
+    pub error: Option<String>,
+    pub rising_sharply: bool,
+}
+
+/// A surge on its way down to the primary station
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamWarning {
+    pub station_id: String,
+    pub name: String,
+    pub role: StationRole,
+    pub flow_cfs: f64,
+    pub rate_cfs_per_hour: Option<f64>,
+    pub change_4h: Option<f64>,
+    /// When the rise should reach the primary, from the station's travel time
+    pub arrives_at: Option<String>,
+    pub message: String,
+}
+
+/// GET /api/flow/network
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkSummary {
+    pub primary: Option<String>,
+    /// Upstream first, by river mile, ending at the primary
+    pub stations: Vec<StationFlow>,
+    pub warnings: Vec<UpstreamWarning>,
+    pub timestamp: String,
+}
+
+impl NetworkSummary {
+    pub fn build(network: &RiverNetwork, mut stations: Vec<StationFlow>, timestamp: String) -> Self {
+        // River miles count up from the mouth, so upstream gauges have higher miles
+        stations.sort_by(|a, b| {
+            (a.station.role == StationRole::Primary)
+                .cmp(&(b.station.role == StationRole::Primary))
+                .then_with(|| {
+                    b.station.river_mile.unwrap_or(0.0).partial_cmp(&a.station.river_mile.unwrap_or(0.0))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+        });
+        let warnings = stations
+            .iter()
+            .filter(|s| s.station.role != StationRole::Primary && s.rising_sharply)
+            .filter_map(|s| {
+                let flow = s.flow.as_ref()?;
+                let arrives_at = s.station.travel_hours.and_then(|hours| {
+                    let at = chrono::DateTime::parse_from_rfc3339(&flow.timestamp).ok()?;
+                    Some((at + chrono::Duration::minutes((hours * 60.0).round() as i64)).to_rfc3339())
+                });
+                let rate = flow.rate_cfs_per_hour.map(|r| format!(" at {:.0} cfs/hour", r)).unwrap_or_default();
+                let eta = s.station.travel_hours.map(|h| format!(", about {:.0} hours upstream", h)).unwrap_or_default();
+                Some(UpstreamWarning {
+                    station_id: s.station.id.clone(),
+                    name: s.station.name.clone(),
+                    role: s.station.role,
+                    flow_cfs: flow.flow_cfs,
+                    rate_cfs_per_hour: flow.rate_cfs_per_hour,
+                    change_4h: flow.change_4h,
+                    arrives_at,
+                    message: format!("{} gauge {} is rising sharply{}{}", s.station.role.label(), s.station.name, rate, eta),
+                })
+            })
+            .collect();
+        Self {
+            primary: network.primary().map(|p| p.id.clone()),
+            stations,
+            warnings,
+            timestamp,
+        }
+    }
+}
@@ -21,6 +21,13 @@ pub struct GeneralSettings {
     pub language: String,
 }
 
+impl GeneralSettings {
+    /// The shop's time zone, falling back to Central when the name isn't recognized
+    pub fn tz(&self) -> chrono_tz::Tz {
+        self.timezone.trim().parse().unwrap_or(super::booking::DEFAULT_TIMEZONE)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppearanceSettings {
     pub primary_color: String,
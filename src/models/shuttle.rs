@@ -0,0 +1,310 @@
+//! Tubing shuttle: runs that drive tubers upstream to a put-in, with a fixed number of seats
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use super::booking::{parse_time, Booking, BookingStatus, TimeWindow};
+
+/// Where a shuttle drops tubers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PutIn {
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// One scheduled departure, repeated every day it runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShuttleRun {
+    /// Stable id bookings keep, e.g. `1015-upper`
+    pub id: String,
+    /// Departure time, HH:MM local
+    pub departs: String,
+    pub seats: u32,
+    /// `PutIn::slug`
+    pub put_in: String,
+    /// Extra runs for weekend crowds
+    #[serde(default)]
+    pub weekends_only: bool,
+}
+
+impl ShuttleRun {
+    pub fn runs_on(&self, date: NaiveDate) -> bool {
+        !self.weekends_only || matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+}
+
+/// Shuttle schedule (SETTINGS KV `shuttle_schedule`). When enabled, every tubing
+/// booking rides a run departing during its window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShuttleSchedule {
+    pub enabled: bool,
+    pub put_ins: Vec<PutIn>,
+    pub runs: Vec<ShuttleRun>,
+}
+
+impl Default for ShuttleSchedule {
+    fn default() -> Self {
+        let run = |id: &str, departs: &str, weekends_only| ShuttleRun {
+            id: id.to_string(),
+            departs: departs.to_string(),
+            seats: 14,
+            put_in: "prairie-du-sac".to_string(),
+            weekends_only,
+        };
+        Self {
+            enabled: true,
+            put_ins: vec![PutIn {
+                slug: "prairie-du-sac".to_string(),
+                name: "Prairie du Sac boat landing".to_string(),
+                notes: None,
+            }],
+            runs: vec![
+                run("1015", "10:15", false),
+                run("1045", "10:45", true),
+                run("1215", "12:15", false),
+                run("1245", "12:45", true),
+                run("1415", "14:15", false),
+                run("1445", "14:45", true),
+            ],
+        }
+    }
+}
+
+impl ShuttleSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        for put_in in &self.put_ins {
+            if put_in.slug.trim().is_empty() || put_in.name.trim().is_empty() {
+                return Err("Every put-in needs a slug and a name".to_string());
+            }
+        }
+        for (i, run) in self.runs.iter().enumerate() {
+            if run.id.trim().is_empty() {
+                return Err("Every shuttle run needs an id".to_string());
+            }
+            if self.runs[..i].iter().any(|r| r.id == run.id) {
+                return Err(format!("Duplicate shuttle run id {}", run.id));
+            }
+            if parse_time(&run.departs).is_none() {
+                return Err(format!("Run {}: invalid departure time {}", run.id, run.departs));
+            }
+            if run.seats == 0 || run.seats > 60 {
+                return Err(format!("Run {}: seats must be between 1 and 60", run.id));
+            }
+            if self.put_in(&run.put_in).is_none() {
+                return Err(format!("Run {}: unknown put-in {}", run.id, run.put_in));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn put_in(&self, slug: &str) -> Option<&PutIn> {
+        self.put_ins.iter().find(|p| p.slug == slug)
+    }
+
+    pub fn run(&self, id: &str) -> Option<&ShuttleRun> {
+        self.runs.iter().find(|r| r.id == id)
+    }
+
+    /// Runs on `date` that leave during `window`, earliest first
+    pub fn runs_for(&self, date: NaiveDate, window: &TimeWindow) -> Vec<&ShuttleRun> {
+        let mut runs: Vec<&ShuttleRun> = self
+            .runs
+            .iter()
+            .filter(|r| r.runs_on(date))
+            .filter(|r| {
+                parse_time(&r.departs)
+                    .map(|t| date.and_time(t))
+                    .is_some_and(|departs| departs >= window.start && departs < window.end)
+            })
+            .collect();
+        runs.sort_by(|a, b| a.departs.cmp(&b.departs));
+        runs
+    }
+
+    /// The run a party of `seats` rides: the requested one if it has room, otherwise
+    /// the earliest in the window that does
+    pub fn choose<'a>(
+        &'a self,
+        date: NaiveDate,
+        window: &TimeWindow,
+        requested: Option<&str>,
+        seats: u32,
+        booked: &[RunSeats],
+    ) -> Result<&'a ShuttleRun, String> {
+        let runs = self.runs_for(date, window);
+        if runs.is_empty() {
+            return Err("No shuttle runs leave during that time. Please choose another slot.".to_string());
+        }
+        let fits = |run: &ShuttleRun| run_seats(booked, &run.id) + seats <= run.seats;
+        match requested.map(str::trim).filter(|r| !r.is_empty()) {
+            Some(id) => {
+                let run = runs
+                    .into_iter()
+                    .find(|r| r.id == id)
+                    .ok_or_else(|| "Please choose one of the shuttle departures for your time".to_string())?;
+                if fits(run) {
+                    Ok(run)
+                } else {
+                    Err(format!("The {} shuttle doesn't have {} seats left", run.departs, seats))
+                }
+            }
+            None => runs
+                .into_iter()
+                .find(|r| fits(r))
+                .ok_or_else(|| "The shuttle is full for that time. Please choose another slot.".to_string()),
+        }
+    }
+}
+
+/// Seats held or confirmed on a run for one day
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunSeats {
+    pub shuttle_run: String,
+    pub seats: u32,
+}
+
+pub fn run_seats(booked: &[RunSeats], run_id: &str) -> u32 {
+    booked.iter().filter(|b| b.shuttle_run == run_id).map(|b| b.seats).sum()
+}
+
+/// Seats left on one run, shown with a tubing slot
+#[derive(Debug, Clone, Serialize)]
+pub struct ShuttleRunAvailability {
+    pub id: String,
+    pub departs: String,
+    pub put_in: String,
+    pub put_in_name: String,
+    pub seats: u32,
+    pub available: u32,
+}
+
+impl ShuttleRunAvailability {
+    pub fn new(schedule: &ShuttleSchedule, run: &ShuttleRun, booked: &[RunSeats]) -> Self {
+        Self {
+            id: run.id.clone(),
+            departs: run.departs.clone(),
+            put_in: run.put_in.clone(),
+            put_in_name: schedule.put_in(&run.put_in).map(|p| p.name.clone()).unwrap_or_else(|| run.put_in.clone()),
+            seats: run.seats,
+            available: run.seats.saturating_sub(run_seats(booked, &run.id)),
+        }
+    }
+}
+
+/// POST /api/admin/bookings/:id/shuttle - move a party to another run (`null` takes them off)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShuttleAssignment {
+    pub run: Option<String>,
+}
+
+/// One party on a departure
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestParty {
+    pub booking_id: String,
+    pub confirmation_number: Option<String>,
+    pub customer_name: Option<String>,
+    pub customer_phone: Option<String>,
+    pub party_size: u32,
+    pub start_time: String,
+    pub at_risk: bool,
+    pub notes: Option<String>,
+}
+
+impl From<&Booking> for ManifestParty {
+    fn from(b: &Booking) -> Self {
+        Self {
+            booking_id: b.id.clone(),
+            confirmation_number: b.confirmation_number.clone(),
+            customer_name: b.customer_name.clone(),
+            customer_phone: b.customer_phone.clone(),
+            party_size: b.party_size,
+            start_time: b.start_time.clone(),
+            at_risk: b.status == BookingStatus::AtRisk,
+            notes: b.notes.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestRun {
+    pub id: String,
+    pub departs: String,
+    pub put_in: String,
+    pub put_in_name: String,
+    pub seats: u32,
+    pub headcount: u32,
+    /// False for a run dropped from the schedule that still has riders
+    pub scheduled: bool,
+    pub parties: Vec<ManifestParty>,
+}
+
+/// GET /api/admin/shuttle/manifest?date=
+#[derive(Debug, Clone, Serialize)]
+pub struct ShuttleManifest {
+    pub date: String,
+    pub runs: Vec<ManifestRun>,
+    pub headcount: u32,
+    /// Tubing parties with no run, for staff to seat
+    pub unassigned: Vec<ManifestParty>,
+}
+
+impl ShuttleManifest {
+    /// Group confirmed tubing bookings under the day's runs. Riders on runs no longer in
+    /// the schedule are listed from what their booking recorded.
+    pub fn build(schedule: &ShuttleSchedule, date: NaiveDate, bookings: &[Booking]) -> Self {
+        let mut runs: Vec<ManifestRun> = schedule
+            .runs
+            .iter()
+            .filter(|r| r.runs_on(date))
+            .map(|r| ManifestRun {
+                id: r.id.clone(),
+                departs: r.departs.clone(),
+                put_in: r.put_in.clone(),
+                put_in_name: schedule.put_in(&r.put_in).map(|p| p.name.clone()).unwrap_or_else(|| r.put_in.clone()),
+                seats: r.seats,
+                headcount: 0,
+                scheduled: true,
+                parties: vec![],
+            })
+            .collect();
+        let mut unassigned = vec![];
+
+        for booking in bookings {
+            let id = match &booking.shuttle_run {
+                Some(id) => id,
+                None => {
+                    unassigned.push(ManifestParty::from(booking));
+                    continue;
+                }
+            };
+            let index = match runs.iter().position(|r| &r.id == id) {
+                Some(i) => i,
+                None => {
+                    let put_in = booking.shuttle_put_in.clone().unwrap_or_default();
+                    runs.push(ManifestRun {
+                        id: id.clone(),
+                        departs: booking.shuttle_departs.clone().unwrap_or_default(),
+                        put_in_name: schedule.put_in(&put_in).map(|p| p.name.clone()).unwrap_or_else(|| put_in.clone()),
+                        put_in,
+                        seats: schedule.run(id).map(|r| r.seats).unwrap_or(0),
+                        headcount: 0,
+                        scheduled: false,
+                        parties: vec![],
+                    });
+                    runs.len() - 1
+                }
+            };
+            runs[index].headcount += booking.shuttle_seats;
+            runs[index].parties.push(ManifestParty::from(booking));
+        }
+
+        runs.sort_by(|a, b| a.departs.cmp(&b.departs).then_with(|| a.put_in.cmp(&b.put_in)));
+        Self {
+            date: date.format("%Y-%m-%d").to_string(),
+            headcount: runs.iter().map(|r| r.headcount).sum(),
+            runs,
+            unassigned,
+        }
+    }
+}
@@ -0,0 +1,876 @@
+//! Liability waiver models
+
+use chrono::NaiveDate;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use super::waiver_group::normalize_group_code;
+
+/// Signers must be adults; minors are listed on a parent or guardian's waiver
+pub const ADULT_AGE: u32 = 18;
+
+/// Most minors one guardian can list on a single waiver
+pub const MAX_MINORS_PER_WAIVER: usize = 20;
+
+/// Adventures a waiver can be signed for (labels match the waiver form)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RentalType {
+    #[serde(rename = "Float on the Wisconsin River")]
+    Tubing,
+    #[serde(rename = "Kayak Rental")]
+    Kayak,
+    #[serde(rename = "E-Bicycle Rental")]
+    EBike,
+    #[serde(rename = "Traditional Bicycle Rental")]
+    Bicycle,
+}
+
+impl RentalType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RentalType::Tubing => "Float on the Wisconsin River",
+            RentalType::Kayak => "Kayak Rental",
+            RentalType::EBike => "E-Bicycle Rental",
+            RentalType::Bicycle => "Traditional Bicycle Rental",
+        }
+    }
+
+    /// Short form used in query strings (`?rental_type=tubing`)
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        match slug.trim().to_lowercase().as_str() {
+            "tubing" | "float" => Some(RentalType::Tubing),
+            "kayak" => Some(RentalType::Kayak),
+            "ebike" | "e-bike" => Some(RentalType::EBike),
+            "bike" | "bicycle" => Some(RentalType::Bicycle),
+            _ => None,
+        }
+    }
+
+    /// House minimum participant age. Younger minors may still sign on, but are
+    /// flagged so staff can confirm fit and equipment at check-in.
+    pub fn minimum_age(&self) -> u32 {
+        match self {
+            RentalType::Tubing => 6,
+            RentalType::Kayak => 8,
+            RentalType::EBike => 16,
+            RentalType::Bicycle => 0,
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim() {
+            "Float on the Wisconsin River" => Some(RentalType::Tubing),
+            "Kayak Rental" => Some(RentalType::Kayak),
+            "E-Bicycle Rental" => Some(RentalType::EBike),
+            "Traditional Bicycle Rental" => Some(RentalType::Bicycle),
+            _ => None,
+        }
+    }
+}
+
+/// Waiver form as posted by static/waiver.html
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaiverSubmission {
+    pub rental_type: String,
+    pub adventure_date: String,
+    #[serde(default = "default_group_size", deserialize_with = "de_group_size")]
+    pub group_size: u32,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    #[serde(default)]
+    pub phone: Option<String>,
+    pub dob: String,
+    pub emergency_contact: String,
+    pub emergency_phone: String,
+    #[serde(default, deserialize_with = "de_flag")]
+    pub has_minors: bool,
+    #[serde(default)]
+    pub minors: Vec<MinorParticipant>,
+    #[serde(default)]
+    pub signature: String,
+    pub sign_date: String,
+    #[serde(default, deserialize_with = "de_flag")]
+    pub agreed_terms: bool,
+    /// Legal text version shown to the signer (absent if the page fell back to its built-in copy)
+    #[serde(default, deserialize_with = "de_optional_id")]
+    pub text_version_id: Option<i64>,
+    /// Group link code from `/waiver?group=...`
+    #[serde(default)]
+    pub group_code: Option<String>,
+}
+
+/// Largest signature data URL we accept (canvas PNGs are typically 10-40 KB)
+pub const MAX_SIGNATURE_BYTES: usize = 512 * 1024;
+
+impl WaiverSubmission {
+    /// Server-side validation; the form is `novalidate` so nothing is checked client-side
+    /// beyond the signature and terms checkbox.
+    pub fn validate(&self, today: NaiveDate) -> Result<(), String> {
+        if self.first_name.trim().is_empty()
+            || self.last_name.trim().is_empty()
+            || self.email.trim().is_empty()
+            || self.emergency_contact.trim().is_empty()
+            || self.emergency_phone.trim().is_empty()
+        {
+            return Err("Please fill in all required fields".to_string());
+        }
+
+        if RentalType::from_label(&self.rental_type).is_none() {
+            return Err("Please select your type of adventure".to_string());
+        }
+
+        if !self.email.contains('@') || !self.email.contains('.') {
+            return Err("Please enter a valid email address".to_string());
+        }
+
+        let adventure_date = parse_date(&self.adventure_date)
+            .ok_or_else(|| "Please enter a valid adventure date".to_string())?;
+        if adventure_date < today {
+            return Err("Adventure date cannot be in the past".to_string());
+        }
+
+        let dob = parse_date(&self.dob)
+            .ok_or_else(|| "Please enter a valid date of birth".to_string())?;
+        if dob >= today {
+            return Err("Please enter a valid date of birth".to_string());
+        }
+
+        parse_date(&self.sign_date)
+            .ok_or_else(|| "Please enter a valid signature date".to_string())?;
+
+        if self.group_size == 0 || self.group_size > 50 {
+            return Err("Group size must be between 1 and 50".to_string());
+        }
+
+        if age_on(dob, adventure_date) < ADULT_AGE {
+            return Err(format!(
+                "You must be {} or older on the adventure date to sign. A parent or guardian must sign for minors.",
+                ADULT_AGE
+            ));
+        }
+
+        if self.has_minors && self.minors.is_empty() {
+            return Err("Please add each minor's name and date of birth".to_string());
+        }
+        if self.minors.len() > MAX_MINORS_PER_WAIVER {
+            return Err(format!("A single waiver can list at most {} minors", MAX_MINORS_PER_WAIVER));
+        }
+        for minor in &self.minors {
+            let name = minor.name.trim();
+            if name.is_empty() {
+                return Err("Please enter a name for each minor".to_string());
+            }
+            let minor_dob = parse_date(&minor.dob)
+                .filter(|d| *d <= adventure_date)
+                .ok_or_else(|| format!("Please enter a valid date of birth for {}", name))?;
+            if age_on(minor_dob, adventure_date) >= ADULT_AGE {
+                return Err(format!(
+                    "{} will be {} or older and must sign their own waiver",
+                    name, ADULT_AGE
+                ));
+            }
+        }
+
+        if !self.agreed_terms {
+            return Err("You must agree to the liability release terms.".to_string());
+        }
+
+        if !self.signature.starts_with("data:image/png;base64,") {
+            return Err("Please provide your signature.".to_string());
+        }
+        if self.signature.len() > MAX_SIGNATURE_BYTES {
+            return Err("Signature image is too large".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Minors with their age on the adventure date and the activity minimum-age flag.
+    /// Call after [`validate`](Self::validate); ages and flags from the client are ignored.
+    pub fn assessed_minors(&self) -> Vec<MinorParticipant> {
+        match parse_date(&self.adventure_date) {
+            Some(date) => assess_minors(&self.minors, &self.rental_type, date),
+            None => vec![],
+        }
+    }
+}
+
+/// Fill in each minor's age on `adventure_date` and flag those under the activity minimum
+pub fn assess_minors(
+    minors: &[MinorParticipant],
+    rental_type: &str,
+    adventure_date: NaiveDate,
+) -> Vec<MinorParticipant> {
+    let minimum_age = RentalType::from_label(rental_type)
+        .map(|r| r.minimum_age())
+        .unwrap_or(0);
+
+    minors
+        .iter()
+        .map(|minor| {
+            let age = parse_date(&minor.dob).map(|dob| age_on(dob, adventure_date));
+            MinorParticipant {
+                name: minor.name.trim().to_string(),
+                dob: minor.dob.trim().to_string(),
+                age,
+                below_minimum_age: age.map(|a| a < minimum_age).unwrap_or(false),
+            }
+        })
+        .collect()
+}
+
+/// A minor listed on a guardian's waiver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinorParticipant {
+    pub name: String,
+    pub dob: String,
+    /// Age on the adventure date (computed server-side)
+    #[serde(default)]
+    pub age: Option<u32>,
+    /// Younger than the activity's minimum age; staff confirm at check-in
+    #[serde(default)]
+    pub below_minimum_age: bool,
+}
+
+/// A signed waiver as stored in the DB_WAIVERS `waivers` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverRecord {
+    pub id: String,
+    pub rental_type: String,
+    pub adventure_date: String,
+    pub group_size: u32,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub dob: String,
+    pub emergency_contact: String,
+    pub emergency_phone: String,
+    #[serde(deserialize_with = "de_flag")]
+    pub has_minors: bool,
+    #[serde(default, deserialize_with = "de_json")]
+    pub minors: Vec<MinorParticipant>,
+    /// At least one minor is under the activity minimum age
+    #[serde(default, deserialize_with = "de_flag")]
+    pub minor_age_flag: bool,
+    /// Free-text minors from waivers signed before structured minors
+    #[serde(default)]
+    pub minors_info: Option<String>,
+    pub signature: String,
+    pub sign_date: String,
+    #[serde(default)]
+    pub text_version_id: Option<i64>,
+    pub legal_text_hash: String,
+    pub submitted_at: String,
+    pub ip_address: String,
+    pub user_agent: String,
+    #[serde(default)]
+    pub checked_in_at: Option<String>,
+    #[serde(default)]
+    pub checked_in_by: Option<String>,
+    /// Set on season confirmations: the original waiver whose signature is reused
+    #[serde(default)]
+    pub reuses_waiver_id: Option<String>,
+    #[serde(default)]
+    pub group_code: Option<String>,
+}
+
+impl WaiverRecord {
+    pub fn from_submission(
+        id: String,
+        submission: WaiverSubmission,
+        text_version: &WaiverTextVersion,
+        ip_address: String,
+        user_agent: String,
+    ) -> Self {
+        let minors = submission.assessed_minors();
+        Self {
+            id,
+            rental_type: RentalType::from_label(&submission.rental_type)
+                .map(|r| r.label().to_string())
+                .unwrap_or(submission.rental_type),
+            adventure_date: submission.adventure_date,
+            group_size: submission.group_size,
+            first_name: submission.first_name.trim().to_string(),
+            last_name: submission.last_name.trim().to_string(),
+            email: submission.email.trim().to_lowercase(),
+            phone: submission.phone.filter(|p| !p.trim().is_empty()),
+            dob: submission.dob,
+            emergency_contact: submission.emergency_contact.trim().to_string(),
+            emergency_phone: submission.emergency_phone.trim().to_string(),
+            has_minors: !minors.is_empty(),
+            minor_age_flag: minors.iter().any(|m| m.below_minimum_age),
+            minors,
+            minors_info: None,
+            signature: submission.signature,
+            sign_date: submission.sign_date,
+            text_version_id: Some(text_version.id),
+            legal_text_hash: text_version.content_hash.clone(),
+            submitted_at: chrono::Utc::now().to_rfc3339(),
+            ip_address,
+            user_agent,
+            checked_in_at: None,
+            checked_in_by: None,
+            reuses_waiver_id: None,
+            group_code: submission.group_code.as_deref().and_then(normalize_group_code),
+        }
+    }
+
+    /// A "confirm for today" record: the original signature, agreement version and
+    /// signer details carried forward to a new adventure
+    pub fn confirmation_of(
+        id: String,
+        original: &WaiverRecord,
+        request: &ConfirmWaiverRequest,
+        ip_address: String,
+        user_agent: String,
+    ) -> Self {
+        let rental_type = RentalType::from_label(&request.rental_type)
+            .map(|r| r.label().to_string())
+            .unwrap_or_else(|| request.rental_type.clone());
+        let minors = parse_date(&request.adventure_date)
+            .map(|date| assess_minors(&original.minors, &rental_type, date))
+            .unwrap_or_default();
+
+        Self {
+            id,
+            rental_type,
+            adventure_date: request.adventure_date.trim().to_string(),
+            group_size: request.group_size,
+            has_minors: !minors.is_empty(),
+            minor_age_flag: minors.iter().any(|m| m.below_minimum_age),
+            minors,
+            minors_info: None,
+            submitted_at: chrono::Utc::now().to_rfc3339(),
+            ip_address,
+            user_agent,
+            checked_in_at: None,
+            checked_in_by: None,
+            reuses_waiver_id: Some(original.id.clone()),
+            group_code: request.group_code.as_deref().and_then(normalize_group_code),
+            ..original.clone()
+        }
+    }
+}
+
+/// Admin-configurable rules for reusing a waiver across visits (SETTINGS KV `waiver_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverSettings {
+    pub reuse_enabled: bool,
+    /// Season start as MM-DD; waivers signed before the current season's start are not reused
+    pub season_start: String,
+    /// Upper bound on signature age in days, even within a season
+    pub reuse_max_days: u32,
+}
+
+impl Default for WaiverSettings {
+    fn default() -> Self {
+        Self {
+            reuse_enabled: true,
+            season_start: "04-01".to_string(),
+            reuse_max_days: 180,
+        }
+    }
+}
+
+impl WaiverSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.season_start_in(2024).is_none() {
+            return Err("Season start must be MM-DD".to_string());
+        }
+        if self.reuse_max_days == 0 || self.reuse_max_days > 366 {
+            return Err("Reuse window must be between 1 and 366 days".to_string());
+        }
+        Ok(())
+    }
+
+    fn season_start_in(&self, year: i32) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&format!("{}-{}", year, self.season_start.trim()), "%Y-%m-%d").ok()
+    }
+
+    /// Earliest sign date that can still be reused on `today`
+    pub fn reuse_valid_since(&self, today: NaiveDate) -> NaiveDate {
+        use chrono::Datelike;
+        let window_start = today - chrono::Duration::days(self.reuse_max_days as i64);
+        let season_start = match self.season_start_in(today.year()) {
+            Some(start) if start <= today => start,
+            _ => self.season_start_in(today.year() - 1).unwrap_or(window_start),
+        };
+        season_start.max(window_start)
+    }
+}
+
+/// Returning-customer lookup (`POST /api/waiver/lookup`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaiverLookupRequest {
+    pub email: String,
+    pub dob: String,
+}
+
+/// Match or no match and the id to confirm; nothing from the waiver itself, since
+/// anyone who knows an email can ask
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverLookupResponse {
+    pub found: bool,
+    pub message: String,
+    pub waiver_id: Option<String>,
+}
+
+/// "Confirm for today" on an existing signature (`POST /api/waiver/confirm`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmWaiverRequest {
+    pub email: String,
+    pub dob: String,
+    pub waiver_id: String,
+    pub rental_type: String,
+    pub adventure_date: String,
+    #[serde(default = "default_group_size", deserialize_with = "de_group_size")]
+    pub group_size: u32,
+    #[serde(default)]
+    pub group_code: Option<String>,
+}
+
+impl ConfirmWaiverRequest {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), String> {
+        if RentalType::from_label(&self.rental_type).is_none() {
+            return Err("Please select your type of adventure".to_string());
+        }
+        let adventure_date = parse_date(&self.adventure_date)
+            .ok_or_else(|| "Please enter a valid adventure date".to_string())?;
+        if adventure_date < today {
+            return Err("Adventure date cannot be in the past".to_string());
+        }
+        if self.group_size == 0 || self.group_size > 50 {
+            return Err("Group size must be between 1 and 50".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Waiver row for admin search results (signature image omitted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverSummary {
+    pub id: String,
+    pub rental_type: String,
+    pub adventure_date: String,
+    pub group_size: u32,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub dob: String,
+    pub emergency_contact: String,
+    pub emergency_phone: String,
+    #[serde(deserialize_with = "de_flag")]
+    pub has_minors: bool,
+    #[serde(default, deserialize_with = "de_json")]
+    pub minors: Vec<MinorParticipant>,
+    #[serde(default, deserialize_with = "de_flag")]
+    pub minor_age_flag: bool,
+    #[serde(default)]
+    pub minors_info: Option<String>,
+    pub submitted_at: String,
+    pub checked_in_at: Option<String>,
+    pub checked_in_by: Option<String>,
+    #[serde(default)]
+    pub reuses_waiver_id: Option<String>,
+    #[serde(default)]
+    pub group_code: Option<String>,
+}
+
+/// Column list matching [`WaiverSummary`]
+pub const WAIVER_SUMMARY_COLUMNS: &str = "id, rental_type, adventure_date, group_size, first_name, \
+    last_name, email, phone, dob, emergency_contact, emergency_phone, has_minors, minors, \
+    minor_age_flag, minors_info, submitted_at, checked_in_at, checked_in_by, reuses_waiver_id, group_code";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WaiverSortField {
+    AdventureDate,
+    LastName,
+    SubmittedAt,
+    CheckedInAt,
+}
+
+impl WaiverSortField {
+    pub fn from_param(value: &str) -> Option<Self> {
+        match value {
+            "adventure_date" | "date" => Some(WaiverSortField::AdventureDate),
+            "last_name" | "name" => Some(WaiverSortField::LastName),
+            "submitted_at" | "submitted" => Some(WaiverSortField::SubmittedAt),
+            "checked_in_at" | "checked_in" => Some(WaiverSortField::CheckedInAt),
+            _ => None,
+        }
+    }
+
+    /// ORDER BY clause (never built from user input directly)
+    pub fn order_by(&self, descending: bool) -> String {
+        let direction = if descending { "DESC" } else { "ASC" };
+        match self {
+            WaiverSortField::AdventureDate => format!("adventure_date {0}, last_name ASC, first_name ASC", direction),
+            WaiverSortField::LastName => format!("last_name {0}, first_name {0}", direction),
+            WaiverSortField::SubmittedAt => format!("submitted_at {}", direction),
+            WaiverSortField::CheckedInAt => format!("checked_in_at IS NULL, checked_in_at {}", direction),
+        }
+    }
+}
+
+/// Admin search filters, parsed from the query string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverSearchQuery {
+    pub q: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub date: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub rental_type: Option<String>,
+    pub checked_in: Option<bool>,
+    /// Only waivers with a minor under the activity minimum age
+    pub age_flagged: bool,
+    pub sort: WaiverSortField,
+    pub descending: bool,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl Default for WaiverSearchQuery {
+    fn default() -> Self {
+        Self {
+            q: None,
+            name: None,
+            email: None,
+            date: None,
+            date_from: None,
+            date_to: None,
+            rental_type: None,
+            checked_in: None,
+            age_flagged: false,
+            sort: WaiverSortField::AdventureDate,
+            descending: false,
+            page: 1,
+            per_page: 50,
+        }
+    }
+}
+
+impl WaiverSearchQuery {
+    pub const MAX_PER_PAGE: u32 = 200;
+
+    /// Build from `?key=value` pairs; `date=today` resolves against `today`
+    pub fn from_pairs<'a, I>(pairs: I, today: NaiveDate) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut query = Self::default();
+        let today_str = today.format("%Y-%m-%d").to_string();
+        let non_empty = |v: &str| Some(v.trim().to_string()).filter(|s| !s.is_empty());
+
+        for (key, value) in pairs {
+            match key {
+                "q" => query.q = non_empty(value),
+                "name" => query.name = non_empty(value),
+                "email" => query.email = non_empty(value),
+                "date" => query.date = non_empty(value)
+                    .map(|d| if d == "today" { today_str.clone() } else { d }),
+                "date_from" => query.date_from = non_empty(value),
+                "date_to" => query.date_to = non_empty(value),
+                "rental_type" => query.rental_type = non_empty(value).map(|t| {
+                    RentalType::from_slug(&t).map(|r| r.label().to_string()).unwrap_or(t)
+                }),
+                "checked_in" => query.checked_in = match value {
+                    "true" | "1" | "yes" => Some(true),
+                    "false" | "0" | "no" => Some(false),
+                    _ => None,
+                },
+                "age_flagged" => query.age_flagged = matches!(value, "true" | "1" | "yes"),
+                "sort" => if let Some(field) = WaiverSortField::from_param(value) {
+                    query.sort = field;
+                },
+                "order" => query.descending = value.eq_ignore_ascii_case("desc"),
+                "page" => query.page = value.parse().unwrap_or(1).max(1),
+                "per_page" => query.per_page = value.parse().unwrap_or(50).clamp(1, Self::MAX_PER_PAGE),
+                _ => {}
+            }
+        }
+
+        query
+    }
+
+    /// WHERE clause with `?N` placeholders plus the values to bind, in order
+    pub fn where_clause(&self) -> (String, Vec<String>) {
+        let mut conditions: Vec<String> = vec![];
+        let mut params: Vec<String> = vec![];
+
+        if let Some(q) = &self.q {
+            params.push(format!("%{}%", q.to_lowercase()));
+            let n = params.len();
+            conditions.push(format!(
+                "(lower(first_name || ' ' || last_name) LIKE ?{0} OR email LIKE ?{0} OR phone LIKE ?{0})",
+                n
+            ));
+        }
+        if let Some(name) = &self.name {
+            params.push(format!("%{}%", name.to_lowercase()));
+            conditions.push(format!("lower(first_name || ' ' || last_name) LIKE ?{}", params.len()));
+        }
+        if let Some(email) = &self.email {
+            params.push(format!("%{}%", email.to_lowercase()));
+            conditions.push(format!("email LIKE ?{}", params.len()));
+        }
+        if let Some(date) = &self.date {
+            params.push(date.clone());
+            conditions.push(format!("adventure_date = ?{}", params.len()));
+        }
+        if let Some(from) = &self.date_from {
+            params.push(from.clone());
+            conditions.push(format!("adventure_date >= ?{}", params.len()));
+        }
+        if let Some(to) = &self.date_to {
+            params.push(to.clone());
+            conditions.push(format!("adventure_date <= ?{}", params.len()));
+        }
+        if let Some(rental_type) = &self.rental_type {
+            params.push(rental_type.clone());
+            conditions.push(format!("rental_type = ?{}", params.len()));
+        }
+        match self.checked_in {
+            Some(true) => conditions.push("checked_in_at IS NOT NULL".to_string()),
+            Some(false) => conditions.push("checked_in_at IS NULL".to_string()),
+            None => {}
+        }
+        if self.age_flagged {
+            conditions.push("minor_age_flag = 1".to_string());
+        }
+
+        if conditions.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), params)
+        }
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.page.saturating_sub(1).saturating_mul(self.per_page)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverSearchResponse {
+    pub waivers: Vec<WaiverSummary>,
+    pub total: i64,
+    pub total_headcount: i64,
+    pub page: u32,
+    pub per_page: u32,
+    pub total_pages: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckInRequest {
+    #[serde(default = "default_checked_in")]
+    pub checked_in: bool,
+}
+
+impl Default for CheckInRequest {
+    fn default() -> Self {
+        Self { checked_in: true }
+    }
+}
+
+fn default_checked_in() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverResponse {
+    pub success: bool,
+    pub message: String,
+    pub waiver_id: Option<String>,
+    pub error: Option<String>,
+    /// Signed token staff scan at check-in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_token: Option<String>,
+    /// QR code (SVG markup) encoding `confirmation_token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qr_svg: Option<String>,
+}
+
+/// POST /api/admin/waivers/scan
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScanCheckInRequest {
+    pub token: String,
+}
+
+/// Prefix on check-in tokens so other QR codes are rejected before signature checks
+pub const CHECKIN_TOKEN_PREFIX: &str = "RACW1:";
+
+/// Original release agreement as presented on static/waiver.html (version 1)
+pub const WAIVER_RELEASE_PARAGRAPHS: &[&str] = &[
+    "In exchange for participation in the activities of Regular and E-Bike Rental, Kayak rental, and/or 'Tubing' on the Wisconsin River organized by \"Riverview Adventure Company\", or \"RAC\" as named throughout this document, of 740 Water Street, Sauk City, Wisconsin, 53583 and/or use of the property, facilities, and services of RAC, I agree for myself and (if applicable) for the members of my family, to the following:",
+    "1. Agreement To Follow Directions. I agree to observe and obey all posted rules and warnings, and further agree to follow any oral instructions or directions given by RAC, or the employees, representatives, or agents of RAC.",
+    "2. Assumption of the Risks and Release. I recognize that there are certain inherent risks associated with the above-described activities and I assume full responsibility for personal injury to myself and (if applicable) my family members, and further release and discharge RAC for injury, loss, or damage arising out of my or my family's use of or presence upon the facilities of RAC, whether caused by the fault of myself, my family, RAC, third parties acting with permission from RAC, or any other individual(s) or circumstances.",
+    "3. Indemnification. I agree to indemnify and defend RAC against all claims, causes of action, damages, judgments, costs, or expenses, including attorney fees and other litigation costs, which may in any way arise from my or my family's use of or presence at locations where RAC equipment or activities are occurring.",
+    "4. Fees. I agree to pay for all damages to the facilities of RAC caused by any negligent, reckless, or willful actions by me or my family.",
+    "5. Minors. I consent to the participation of me and any minors listed in the activities above and agree on behalf of the above minor to all of the terms and conditions of this agreement. By signing this Release of Liability, I represent that I have legal authority over and custody of minors listed.",
+    "6. Applicable Law. Any legal or equitable claim that may arise from participation in the above shall be resolved under Wisconsin law.",
+    "7. No Pressure. I agree and acknowledge that I am under no pressure to sign this agreement and that I have been given a reasonable opportunity to review it before signing.",
+    "8. Arm's Length Agreement. This agreement and each of its terms are the product of an arm's length negotiation between the Parties. In the event any ambiguity is found to exist in the interpretation of this agreement or any of its provisions, the Parties, and each of them, explicitly reject the application of any legal or equitable rule of interpretation which would lead to a construction either \"for\" or \"against\" a particular party based upon their status as the drafter of a specific term, language, or provision giving rise to such ambiguity.",
+    "9. Enforceability. The invalidity or unenforceability of any provision of this agreement, whether standing alone or as applied to a particular occurrence or circumstance, shall not affect the validity or enforceability of any other provision of this agreement or of any other applications of such provision, as the case may be. Such invalid or unenforceable provision shall be deemed not to be a part of this agreement.",
+];
+
+pub const WAIVER_ACKNOWLEDGEMENT: &str = "I have read this entire document and understand it. I further understand that by signing this release, I voluntarily surrender certain legal rights. I acknowledge that my electronic signature is the same as a handwritten one.";
+
+/// Canonical form of an agreement for hashing: paragraphs then the
+/// acknowledgement, separated by blank lines
+pub fn legal_text<S: AsRef<str>>(paragraphs: &[S], acknowledgement: &str) -> String {
+    let mut text = paragraphs.iter().map(|p| p.as_ref()).collect::<Vec<_>>().join("\n\n");
+    text.push_str("\n\n");
+    text.push_str(acknowledgement);
+    text
+}
+
+/// SHA-256 of [`legal_text`], stored with every signature
+pub fn legal_text_hash<S: AsRef<str>>(paragraphs: &[S], acknowledgement: &str) -> String {
+    crate::utils::auth::sha256_hex(legal_text(paragraphs, acknowledgement).as_bytes())
+}
+
+/// A published revision of the release agreement (DB_WAIVERS `waiver_text_versions`).
+/// Versions are immutable once published; edits are made by publishing a new one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverTextVersion {
+    pub id: i64,
+    pub label: String,
+    #[serde(deserialize_with = "de_json")]
+    pub paragraphs: Vec<String>,
+    pub acknowledgement: String,
+    pub content_hash: String,
+    pub effective_date: String,
+    pub created_at: String,
+    pub created_by: String,
+}
+
+impl WaiverTextVersion {
+    /// The text compiled into the worker, seeded as version 1 by the migrations
+    pub fn builtin() -> Self {
+        Self {
+            id: 1,
+            label: "Original release agreement".to_string(),
+            paragraphs: WAIVER_RELEASE_PARAGRAPHS.iter().map(|p| p.to_string()).collect(),
+            acknowledgement: WAIVER_ACKNOWLEDGEMENT.to_string(),
+            content_hash: legal_text_hash(WAIVER_RELEASE_PARAGRAPHS, WAIVER_ACKNOWLEDGEMENT),
+            effective_date: "2026-01-01".to_string(),
+            created_at: "2026-01-01T00:00:00+00:00".to_string(),
+            created_by: "system".to_string(),
+        }
+    }
+
+    /// True if the stored hash still matches the stored text
+    pub fn verify_hash(&self) -> bool {
+        legal_text_hash(&self.paragraphs, &self.acknowledgement) == self.content_hash
+    }
+}
+
+/// Admin request to publish a new agreement revision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishWaiverTextRequest {
+    pub label: String,
+    pub effective_date: String,
+    pub paragraphs: Vec<String>,
+    pub acknowledgement: String,
+}
+
+impl PublishWaiverTextRequest {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), String> {
+        if self.label.trim().is_empty() {
+            return Err("Version label is required".to_string());
+        }
+        let effective = parse_date(&self.effective_date)
+            .ok_or_else(|| "Effective date must be YYYY-MM-DD".to_string())?;
+        // Back-dating would change what earlier signers appear to have agreed to
+        if effective < today {
+            return Err("Effective date cannot be in the past".to_string());
+        }
+        if self.paragraphs.iter().all(|p| p.trim().is_empty()) {
+            return Err("Agreement text is required".to_string());
+        }
+        if self.acknowledgement.trim().is_empty() {
+            return Err("Acknowledgement text is required".to_string());
+        }
+        Ok(())
+    }
+
+    /// Trimmed paragraphs with blanks removed, as they will be stored and hashed
+    pub fn normalized_paragraphs(&self) -> Vec<String> {
+        self.paragraphs
+            .iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+}
+
+/// Whole years from `dob` to `on`
+pub fn age_on(dob: NaiveDate, on: NaiveDate) -> u32 {
+    use chrono::Datelike;
+    let mut age = on.year() - dob.year();
+    if (on.month(), on.day()) < (dob.month(), dob.day()) {
+        age -= 1;
+    }
+    age.max(0) as u32
+}
+
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()
+}
+
+pub(crate) fn default_group_size() -> u32 {
+    1
+}
+
+/// FormData sends numbers as strings ("4"); accept either form
+pub(crate) fn de_group_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(match value {
+        serde_json::Value::Number(n) => n.as_u64().unwrap_or(0) as u32,
+        serde_json::Value::String(s) if s.trim().is_empty() => default_group_size(),
+        serde_json::Value::String(s) => s.trim().parse().unwrap_or(0),
+        _ => default_group_size(),
+    })
+}
+
+/// Hidden inputs arrive as strings from FormData
+fn de_optional_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(match value {
+        serde_json::Value::Number(n) => n.as_i64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+/// D1 returns JSON columns as text; the API sends real arrays and objects
+pub(crate) fn de_json<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    match value {
+        serde_json::Value::Null => Ok(T::default()),
+        serde_json::Value::String(s) => serde_json::from_str(&s).map_err(serde::de::Error::custom),
+        other => serde_json::from_value(other).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Checkboxes arrive as "on" from FormData, booleans from JS, and 0/1 from D1
+pub(crate) fn de_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(match value {
+        serde_json::Value::Bool(b) => b,
+        serde_json::Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
+        serde_json::Value::String(s) => matches!(s.as_str(), "on" | "true" | "1" | "yes"),
+        _ => false,
+    })
+}
@@ -0,0 +1,157 @@
+//! Group waiver links: one shareable code that every member's waiver attaches to
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use super::waiver::{de_group_size, default_group_size, parse_date, RentalType, WaiverSummary};
+
+/// Characters used in group codes (no 0/O, 1/I/L to survive being read aloud)
+pub const GROUP_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+pub const GROUP_CODE_LENGTH: usize = 6;
+pub const MAX_GROUP_MEMBERS: u32 = 100;
+
+/// A group created by its leader (`waiver_groups` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaiverGroup {
+    pub code: String,
+    pub name: String,
+    pub leader_name: String,
+    pub leader_email: String,
+    pub rental_type: String,
+    pub adventure_date: String,
+    pub expected_members: u32,
+    pub created_at: String,
+}
+
+/// POST /api/waiver/group
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWaiverGroupRequest {
+    pub name: String,
+    pub leader_name: String,
+    pub leader_email: String,
+    pub rental_type: String,
+    pub adventure_date: String,
+    #[serde(default = "default_group_size", deserialize_with = "de_group_size")]
+    pub expected_members: u32,
+}
+
+impl CreateWaiverGroupRequest {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.leader_name.trim().is_empty() {
+            return Err("Please enter a group name and your name".to_string());
+        }
+        if self.name.trim().len() > 80 {
+            return Err("Group name is too long".to_string());
+        }
+        if !self.leader_email.contains('@') || !self.leader_email.contains('.') {
+            return Err("Please enter a valid email address".to_string());
+        }
+        if RentalType::from_label(&self.rental_type).is_none() {
+            return Err("Please select your type of adventure".to_string());
+        }
+        let adventure_date = parse_date(&self.adventure_date)
+            .ok_or_else(|| "Please enter a valid adventure date".to_string())?;
+        if adventure_date < today {
+            return Err("Adventure date cannot be in the past".to_string());
+        }
+        if self.expected_members < 2 || self.expected_members > MAX_GROUP_MEMBERS {
+            return Err(format!("Groups must have between 2 and {} members", MAX_GROUP_MEMBERS));
+        }
+        Ok(())
+    }
+
+    pub fn into_group(self, code: String) -> WaiverGroup {
+        WaiverGroup {
+            code,
+            name: self.name.trim().to_string(),
+            leader_name: self.leader_name.trim().to_string(),
+            leader_email: self.leader_email.trim().to_lowercase(),
+            rental_type: RentalType::from_label(&self.rental_type)
+                .map(|r| r.label().to_string())
+                .unwrap_or(self.rental_type),
+            adventure_date: self.adventure_date.trim().to_string(),
+            expected_members: self.expected_members,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Signed vs expected headcount; safe to show anyone holding the link
+#[derive(Debug, Clone, Serialize)]
+pub struct WaiverGroupProgress {
+    pub code: String,
+    pub name: String,
+    pub rental_type: String,
+    pub adventure_date: String,
+    pub expected_members: u32,
+    /// Participants covered: each signer plus the minors on their waiver
+    pub signed_members: u32,
+    pub waivers_signed: u32,
+    pub remaining: u32,
+    pub complete: bool,
+}
+
+impl WaiverGroupProgress {
+    pub fn new(group: &WaiverGroup, waivers_signed: u32, signed_members: u32) -> Self {
+        Self {
+            code: group.code.clone(),
+            name: group.name.clone(),
+            rental_type: group.rental_type.clone(),
+            adventure_date: group.adventure_date.clone(),
+            expected_members: group.expected_members,
+            signed_members,
+            waivers_signed,
+            remaining: group.expected_members.saturating_sub(signed_members),
+            complete: signed_members >= group.expected_members,
+        }
+    }
+}
+
+/// Row in the admin group list
+#[derive(Debug, Clone, Serialize)]
+pub struct WaiverGroupListItem {
+    #[serde(flatten)]
+    pub progress: WaiverGroupProgress,
+    pub leader_name: String,
+    pub leader_email: String,
+    pub created_at: String,
+}
+
+/// Admin view of a group with every attached waiver
+#[derive(Debug, Clone, Serialize)]
+pub struct WaiverGroupDetail {
+    #[serde(flatten)]
+    pub progress: WaiverGroupProgress,
+    pub leader_name: String,
+    pub leader_email: String,
+    pub created_at: String,
+    pub waivers: Vec<WaiverSummary>,
+}
+
+impl WaiverGroup {
+    /// Whether a waiver for this activity and date may attach to the group
+    pub fn accepts(&self, rental_type: &str, adventure_date: &str) -> Result<(), String> {
+        let rental_type = RentalType::from_label(rental_type).map(|r| r.label()).unwrap_or(rental_type);
+        if rental_type != self.rental_type || adventure_date.trim() != self.adventure_date {
+            return Err(format!(
+                "This group link is for {} on {}. Please match the adventure details.",
+                self.rental_type, self.adventure_date
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Canonical form of a code typed or pasted by a guest (`abc-234` → `ABC234`)
+pub fn normalize_group_code(value: &str) -> Option<String> {
+    let code: String = value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if code.len() == GROUP_CODE_LENGTH && code.bytes().all(|b| GROUP_CODE_ALPHABET.contains(&b)) {
+        Some(code)
+    } else {
+        None
+    }
+}
@@ -116,6 +116,80 @@ pub struct NwsAlertProperties {
     pub sender_name: Option<String>,
 }
 
+// NWS hourly forecast (`properties.periods` of the forecastHourly endpoint)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NwsForecastPeriod {
+    pub start_time: String,
+    pub temperature: f64,
+    #[serde(default)]
+    pub temperature_unit: String,
+    #[serde(default)]
+    pub wind_speed: String,
+    #[serde(default)]
+    pub short_forecast: String,
+    #[serde(default)]
+    pub probability_of_precipitation: Option<NwsQuantity>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NwsQuantity {
+    pub value: Option<f64>,
+}
+
+/// Outlook for a stretch of hours, summarized from the NWS hourly forecast
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastSummary {
+    pub high: f64,
+    pub low: f64,
+    pub unit: String,
+    pub conditions: String,
+    pub precip_chance: f64,
+    pub wind: String,
+}
+
+impl ForecastSummary {
+    /// Hours on `date` from `start_hour` up to `end_hour`, in the local time NWS reports
+    pub fn from_nws(forecast: &serde_json::Value, date: &str, start_hour: u32, end_hour: u32) -> Option<Self> {
+        let periods: Vec<NwsForecastPeriod> =
+            serde_json::from_value(forecast["properties"]["periods"].clone()).ok()?;
+        let hours: Vec<&NwsForecastPeriod> = periods
+            .iter()
+            .filter(|p| p.start_time.starts_with(date))
+            .filter(|p| {
+                p.start_time
+                    .get(11..13)
+                    .and_then(|h| h.parse::<u32>().ok())
+                    .is_some_and(|h| h >= start_hour && h < end_hour.max(start_hour + 1))
+            })
+            .collect();
+        let first = hours.first()?;
+
+        // The most common description over the window
+        let mut conditions = first.short_forecast.clone();
+        let mut best = 0;
+        for period in &hours {
+            let count = hours.iter().filter(|p| p.short_forecast == period.short_forecast).count();
+            if count > best {
+                best = count;
+                conditions = period.short_forecast.clone();
+            }
+        }
+
+        Some(Self {
+            high: hours.iter().map(|p| p.temperature).fold(f64::MIN, f64::max),
+            low: hours.iter().map(|p| p.temperature).fold(f64::MAX, f64::min),
+            unit: first.temperature_unit.clone(),
+            conditions,
+            precip_chance: hours
+                .iter()
+                .filter_map(|p| p.probability_of_precipitation.as_ref().and_then(|q| q.value))
+                .fold(0.0, f64::max),
+            wind: first.wind_speed.clone(),
+        })
+    }
+}
+
 /// Convert wind direction degrees to compass direction
 pub fn degrees_to_compass(degrees: f64) -> String {
     let directions = ["N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
@@ -0,0 +1,404 @@
+//! Bike repair work orders: customer requests against the repair catalog, the shop queue
+//! and public status lookup by ticket
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use super::pricing::round_cents;
+use super::store::RepairService;
+use super::waiver::parse_date;
+use super::waiver_group::GROUP_CODE_ALPHABET;
+
+/// Tickets read `RW-` plus six code characters, e.g. `RW-K7RM2Q`
+pub const TICKET_PREFIX: &str = "RW-";
+pub const TICKET_CODE_LENGTH: usize = 6;
+/// Repair services one order may request
+pub const MAX_ORDER_SERVICES: usize = 10;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkOrderStatus {
+    Received,
+    Diagnosing,
+    WaitingParts,
+    Ready,
+    PickedUp,
+}
+
+impl WorkOrderStatus {
+    pub const ALL: [WorkOrderStatus; 5] = [
+        WorkOrderStatus::Received,
+        WorkOrderStatus::Diagnosing,
+        WorkOrderStatus::WaitingParts,
+        WorkOrderStatus::Ready,
+        WorkOrderStatus::PickedUp,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WorkOrderStatus::Received => "Received",
+            WorkOrderStatus::Diagnosing => "Diagnosing",
+            WorkOrderStatus::WaitingParts => "Waiting for parts",
+            WorkOrderStatus::Ready => "Ready for pickup",
+            WorkOrderStatus::PickedUp => "Picked up",
+        }
+    }
+
+    /// What the customer sees on the status page
+    pub fn message(&self) -> &'static str {
+        match self {
+            WorkOrderStatus::Received => "We have your request and will look at your bike when you drop it off.",
+            WorkOrderStatus::Diagnosing => "A mechanic is looking over your bike.",
+            WorkOrderStatus::WaitingParts => "We're waiting on parts for your repair.",
+            WorkOrderStatus::Ready => "Your bike is ready. Stop by the shop to pick it up.",
+            WorkOrderStatus::PickedUp => "Picked up. Thanks for riding with us!",
+        }
+    }
+
+    fn step(&self) -> usize {
+        WorkOrderStatus::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+
+    /// Orders move forward (skipping steps as needed) and may go back to the bench for
+    /// rework; nothing returns to received and picked-up is final
+    pub fn can_move_to(&self, next: WorkOrderStatus) -> bool {
+        match (self, next) {
+            (WorkOrderStatus::PickedUp, _) | (_, WorkOrderStatus::Received) => false,
+            (_, WorkOrderStatus::PickedUp) => *self == WorkOrderStatus::Ready,
+            (_, WorkOrderStatus::Diagnosing | WorkOrderStatus::WaitingParts) => *self != next,
+            _ => next.step() > self.step(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        *self != WorkOrderStatus::PickedUp
+    }
+}
+
+/// Upper-case a typed ticket, with or without the `RW-` prefix
+pub fn normalize_ticket(raw: &str) -> Option<String> {
+    let code: String = raw.to_uppercase().chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+    let code = match code.strip_prefix("RW") {
+        Some(rest) if rest.len() == TICKET_CODE_LENGTH => rest,
+        _ => &code,
+    };
+    (code.len() == TICKET_CODE_LENGTH && code.bytes().all(|b| GROUP_CODE_ALPHABET.contains(&b)))
+        .then(|| format!("{}{}", TICKET_PREFIX, code))
+}
+
+/// Days a catalog duration like "Same day" or "1-2 days" takes, at the long end
+pub fn turnaround_days(duration: &str) -> u32 {
+    let lower = duration.to_lowercase();
+    let longest = lower
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    if lower.contains("week") {
+        longest.max(1) * 7
+    } else if lower.contains("day") && !lower.contains("same") {
+        longest.max(1)
+    } else {
+        0
+    }
+}
+
+/// Price range for a set of catalog services; open-ended services count at their minimum
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct RepairEstimate {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl RepairEstimate {
+    pub fn for_services(services: &[RepairService]) -> Self {
+        Self {
+            min: round_cents(services.iter().map(|s| s.price_min).sum()),
+            max: round_cents(services.iter().map(|s| s.price_max.unwrap_or(s.price_min).max(s.price_min)).sum()),
+        }
+    }
+
+    /// `$45` or `$45 - $120`
+    pub fn display(&self) -> String {
+        if self.max > self.min {
+            format!("${:.2} - ${:.2}", self.min, self.max)
+        } else {
+            format!("${:.2}", self.min)
+        }
+    }
+}
+
+/// Catalog services in the order given; unknown ids are skipped
+pub fn catalog_services(ids: &[String], catalog: &[RepairService]) -> Vec<RepairService> {
+    ids.iter().filter_map(|id| catalog.iter().find(|s| &s.id == id).cloned()).collect()
+}
+
+/// When the shop promises an order opened on `opened`: the longest service's turnaround
+pub fn promised_date(services: &[RepairService], opened: NaiveDate) -> NaiveDate {
+    let days = services.iter().map(|s| turnaround_days(&s.duration)).max().unwrap_or(0);
+    opened + chrono::Duration::days(days as i64)
+}
+
+/// A row in `work_orders`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkOrder {
+    pub id: String,
+    pub ticket: String,
+    pub status: WorkOrderStatus,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub customer_phone: String,
+    pub bike: String,
+    #[serde(default)]
+    pub problem: Option<String>,
+    /// JSON array of `RepairService` ids from the repair catalog
+    pub repair_service_ids: String,
+    pub estimate_min: f64,
+    pub estimate_max: f64,
+    /// YYYY-MM-DD
+    pub promised_on: String,
+    #[serde(default)]
+    pub final_price: Option<f64>,
+    #[serde(default)]
+    pub mechanic: Option<String>,
+    /// Shown on the public status page
+    #[serde(default)]
+    pub customer_message: Option<String>,
+    #[serde(default)]
+    pub staff_notes: Option<String>,
+    #[serde(default)]
+    pub ready_at: Option<String>,
+    #[serde(default)]
+    pub picked_up_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl WorkOrder {
+    pub fn repair_service_ids(&self) -> Vec<String> {
+        serde_json::from_str(&self.repair_service_ids).unwrap_or_default()
+    }
+
+    pub fn estimate(&self) -> RepairEstimate {
+        RepairEstimate { min: self.estimate_min, max: self.estimate_max }
+    }
+}
+
+/// A row in `work_order_events`: one status change or shop note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkOrderEvent {
+    pub id: String,
+    pub work_order_id: String,
+    #[serde(default)]
+    pub from_status: Option<WorkOrderStatus>,
+    pub status: WorkOrderStatus,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    pub created_at: String,
+}
+
+/// POST /api/repairs/orders - a customer asks for repairs from the catalog
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkOrderRequest {
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    /// Make, model and color, so the shop can match the bike at drop-off
+    pub bike: String,
+    pub repair_service_ids: Vec<String>,
+    #[serde(default)]
+    pub problem: Option<String>,
+}
+
+impl WorkOrderRequest {
+    pub fn validate(&self, catalog: &[RepairService]) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.phone.trim().is_empty() {
+            return Err("Please enter your name and phone number".to_string());
+        }
+        if !self.email.contains('@') || !self.email.contains('.') {
+            return Err("Please enter a valid email address".to_string());
+        }
+        if self.bike.trim().is_empty() || self.bike.len() > 200 {
+            return Err("Tell us about your bike (200 characters max)".to_string());
+        }
+        if self.problem.as_deref().map(|p| p.len() > 2000).unwrap_or(false) {
+            return Err("Problem descriptions are limited to 2000 characters".to_string());
+        }
+        validate_services(&self.repair_service_ids, catalog)
+    }
+}
+
+/// POST /api/admin/repairs/:id - omitted fields are kept; a new service list is re-estimated
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorkOrderUpdate {
+    pub status: Option<WorkOrderStatus>,
+    pub repair_service_ids: Option<Vec<String>>,
+    /// YYYY-MM-DD
+    pub promised_on: Option<String>,
+    pub final_price: Option<f64>,
+    pub mechanic: Option<String>,
+    pub customer_message: Option<String>,
+    pub staff_notes: Option<String>,
+    /// Kept with the status change in the order's history
+    pub note: Option<String>,
+}
+
+impl WorkOrderUpdate {
+    pub fn validate(&self, catalog: &[RepairService]) -> Result<(), String> {
+        if let Some(ids) = &self.repair_service_ids {
+            validate_services(ids, catalog)?;
+        }
+        if let Some(date) = &self.promised_on {
+            parse_date(date).ok_or_else(|| "Invalid promised date (YYYY-MM-DD)".to_string())?;
+        }
+        if let Some(price) = self.final_price {
+            if !price.is_finite() || !(0.0..=100_000.0).contains(&price) {
+                return Err("Final price must be between $0 and $100,000".to_string());
+            }
+        }
+        if self.customer_message.as_deref().map(|m| m.len() > 500).unwrap_or(false) {
+            return Err("Customer messages are limited to 500 characters".to_string());
+        }
+        if self.note.as_deref().map(|n| n.len() > 1000).unwrap_or(false) {
+            return Err("Notes are limited to 1000 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn validate_services(ids: &[String], catalog: &[RepairService]) -> Result<(), String> {
+    if ids.is_empty() {
+        return Err("Choose at least one repair service".to_string());
+    }
+    if ids.len() > MAX_ORDER_SERVICES {
+        return Err(format!("Choose at most {} repair services", MAX_ORDER_SERVICES));
+    }
+    if let Some(id) = ids.iter().find(|id| !catalog.iter().any(|s| &s.id == *id)) {
+        return Err(format!("Unknown repair service {}", id));
+    }
+    if ids.iter().enumerate().any(|(i, id)| ids[..i].contains(id)) {
+        return Err("Each repair service can only be chosen once".to_string());
+    }
+    Ok(())
+}
+
+/// An order with its services and history, for staff
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkOrderDetail {
+    #[serde(flatten)]
+    pub order: WorkOrder,
+    pub repair_services: Vec<RepairService>,
+    pub events: Vec<WorkOrderEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkOrderStep {
+    pub status: WorkOrderStatus,
+    pub label: &'static str,
+    pub at: String,
+}
+
+/// GET /api/repairs/orders/:ticket - what a customer may see; no contact details or shop notes
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkOrderLookup {
+    pub ticket: String,
+    pub status: WorkOrderStatus,
+    pub status_label: &'static str,
+    pub message: String,
+    pub bike: String,
+    pub services: Vec<String>,
+    pub estimate: RepairEstimate,
+    pub estimate_display: String,
+    pub final_price: Option<f64>,
+    pub promised_on: String,
+    pub history: Vec<WorkOrderStep>,
+    pub updated_at: String,
+}
+
+impl WorkOrderLookup {
+    pub fn new(order: &WorkOrder, events: &[WorkOrderEvent], catalog: &[RepairService]) -> Self {
+        Self {
+            ticket: order.ticket.clone(),
+            status: order.status,
+            status_label: order.status.label(),
+            message: order.customer_message.clone().unwrap_or_else(|| order.status.message().to_string()),
+            bike: order.bike.clone(),
+            services: catalog_services(&order.repair_service_ids(), catalog).into_iter().map(|s| s.name).collect(),
+            estimate: order.estimate(),
+            estimate_display: order.estimate().display(),
+            final_price: order.final_price,
+            promised_on: order.promised_on.clone(),
+            history: events
+                .iter()
+                .filter(|e| e.from_status != Some(e.status))
+                .map(|e| WorkOrderStep { status: e.status, label: e.status.label(), at: e.created_at.clone() })
+                .collect(),
+            updated_at: order.updated_at.clone(),
+        }
+    }
+}
+
+/// One order in the mechanics' queue
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedWorkOrder {
+    #[serde(flatten)]
+    pub order: WorkOrder,
+    pub services: Vec<String>,
+    /// Negative when the promised date is still ahead
+    pub days_late: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusCount {
+    pub status: WorkOrderStatus,
+    pub label: &'static str,
+    pub count: u32,
+}
+
+/// GET /api/admin/repairs - open orders, soonest promised first
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkOrderQueue {
+    pub as_of: String,
+    pub total: u32,
+    pub late: u32,
+    pub by_status: Vec<StatusCount>,
+    pub orders: Vec<QueuedWorkOrder>,
+}
+
+impl WorkOrderQueue {
+    pub fn build(orders: Vec<WorkOrder>, catalog: &[RepairService], today: NaiveDate) -> Self {
+        let mut orders: Vec<QueuedWorkOrder> = orders
+            .into_iter()
+            .map(|order| QueuedWorkOrder {
+                services: catalog_services(&order.repair_service_ids(), catalog).into_iter().map(|s| s.name).collect(),
+                days_late: parse_date(&order.promised_on).map(|d| (today - d).num_days()).unwrap_or(0),
+                order,
+            })
+            .collect();
+        orders.sort_by(|a, b| {
+            a.order.promised_on.cmp(&b.order.promised_on).then_with(|| a.order.created_at.cmp(&b.order.created_at))
+        });
+        let open = |o: &&QueuedWorkOrder| o.order.status.is_open();
+        Self {
+            as_of: today.format("%Y-%m-%d").to_string(),
+            total: orders.len() as u32,
+            late: orders
+                .iter()
+                .filter(open)
+                .filter(|o| o.days_late > 0 && o.order.status != WorkOrderStatus::Ready)
+                .count() as u32,
+            by_status: WorkOrderStatus::ALL
+                .iter()
+                .map(|&status| StatusCount {
+                    status,
+                    label: status.label(),
+                    count: orders.iter().filter(|o| o.order.status == status).count() as u32,
+                })
+                .filter(|c| c.count > 0)
+                .collect(),
+            orders,
+        }
+    }
+}
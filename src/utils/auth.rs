@@ -2,6 +2,14 @@
 
 use hmac::{Hmac, Mac};
 use sha1::Sha1;
+use worker::{Error, Result};
+
+/// Bytes from the platform RNG. A failure is returned, never papered over with zeros.
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| Error::RustError(format!("Random number generator failed: {}", e)))?;
+    Ok(bytes)
+}
 
 /// Verify TOTP code (RFC 6238)
 pub fn verify_totp(secret: &str, code: &str) -> bool {
@@ -49,11 +57,9 @@ pub fn verify_totp(secret: &str, code: &str) -> bool {
 }
 
 /// Generate a simple session token
-pub fn generate_session_token() -> String {
-    use getrandom::getrandom;
-    let mut bytes = [0u8; 32];
-    getrandom(&mut bytes).unwrap_or_default();
-    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+pub fn generate_session_token() -> Result<String> {
+    let bytes: [u8; 32] = random_bytes()?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
 }
 
 /// Hash password with SHA-256 (simple implementation)
@@ -64,3 +70,79 @@ pub fn hash_password(password: &str) -> String {
     let result = hasher.finalize();
     result.iter().map(|b| format!("{:02x}", b)).collect()
 }
+
+/// Generate a random record identifier, e.g. `wv_3f9a1c07d2e45b68`
+pub fn generate_id(prefix: &str) -> Result<String> {
+    let bytes: [u8; 8] = random_bytes()?;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}_{}", prefix, hex))
+}
+
+/// Random short code drawn from `alphabet`, e.g. group links (`K7RM2Q`)
+pub fn generate_code(alphabet: &[u8], length: usize) -> Result<String> {
+    // Rejection sampling keeps every character equally likely
+    let limit = 256 - (256 % alphabet.len());
+    let mut code = String::with_capacity(length);
+    while code.len() < length {
+        let bytes: [u8; 16] = random_bytes()?;
+        for b in bytes.iter().filter(|&&b| (b as usize) < limit) {
+            if code.len() == length {
+                break;
+            }
+            code.push(alphabet[*b as usize % alphabet.len()] as char);
+        }
+    }
+    Ok(code)
+}
+
+/// SHA-256 digest as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let result = Sha256::digest(data);
+    result.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Append an HMAC-SHA256 signature: `payload.signature` (base64url, 128-bit tag)
+pub fn sign_token(secret: &str, payload: &str) -> String {
+    use base64::Engine;
+    let mut mac = match Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(m) => m,
+        Err(_) => return payload.to_string(),
+    };
+    mac.update(payload.as_bytes());
+    let tag = mac.finalize().into_bytes();
+    let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&tag[..16]);
+    format!("{}.{}", payload, signature)
+}
+
+/// Check a token from [`sign_token`] and return its payload
+pub fn verify_token(secret: &str, token: &str) -> Option<String> {
+    use base64::Engine;
+    let (payload, signature) = token.trim().rsplit_once('.')?;
+    let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(signature).ok()?;
+    // verify_truncated_left accepts any prefix length; insist on the full tag we issue
+    if signature.len() != 16 {
+        return None;
+    }
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(payload.as_bytes());
+    mac.verify_truncated_left(&signature).ok()?;
+    Some(payload.to_string())
+}
+
+/// Check a hex HMAC-SHA256 signature of `payload`, as sent with webhooks
+pub fn verify_hmac_hex(secret: &str, payload: &str, signature: &str) -> bool {
+    let signature = signature.trim();
+    if signature.len() != 64 || !signature.is_ascii() {
+        return false;
+    }
+    let bytes: Option<Vec<u8>> = (0..64)
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&signature[i..i + 2], 16).ok())
+        .collect();
+    let (Some(bytes), Ok(mut mac)) = (bytes, Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes())) else {
+        return false;
+    };
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&bytes).is_ok()
+}
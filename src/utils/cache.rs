@@ -29,3 +29,22 @@ pub async fn set_cached<T: Serialize>(
         .await
         .map_err(|e| e.to_string())
 }
+
+/// Delete every key that starts with `prefix`
+pub async fn delete_prefix(kv: &KvStore, prefix: &str) -> Result<(), String> {
+    let mut cursor = None;
+    loop {
+        let mut list = kv.list().prefix(prefix.to_string());
+        if let Some(c) = cursor.take() {
+            list = list.cursor(c);
+        }
+        let page = list.execute().await.map_err(|e| e.to_string())?;
+        for key in &page.keys {
+            kv.delete(&key.name).await.map_err(|e| e.to_string())?;
+        }
+        if page.list_complete || page.cursor.is_none() {
+            return Ok(());
+        }
+        cursor = page.cursor;
+    }
+}
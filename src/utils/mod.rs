@@ -2,3 +2,7 @@
 
 pub mod cache;
 pub mod auth;
+pub mod png;
+pub mod pdf;
+pub mod zip;
+pub mod qr;
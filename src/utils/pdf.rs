@@ -0,0 +1,317 @@
+//! Small pure-Rust PDF writer
+//!
+//! Produces US Letter documents using the built-in Helvetica fonts (no embedding),
+//! with automatic word wrap, page breaks, page-numbered footers and grayscale images.
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use super::png::GrayImage;
+
+const PAGE_WIDTH: f64 = 612.0;
+const PAGE_HEIGHT: f64 = 792.0;
+const MARGIN: f64 = 54.0;
+const FOOTER_HEIGHT: f64 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+pub struct PdfBuilder {
+    title: String,
+    footer: String,
+    pages: Vec<String>,
+    current: String,
+    y: f64,
+    images: Vec<GrayImage>,
+}
+
+impl PdfBuilder {
+    pub fn new(title: &str, footer: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            footer: footer.to_string(),
+            pages: vec![],
+            current: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+            images: vec![],
+        }
+    }
+
+    fn content_width() -> f64 {
+        PAGE_WIDTH - 2.0 * MARGIN
+    }
+
+    fn ensure_space(&mut self, height: f64) {
+        if self.y - height < MARGIN + FOOTER_HEIGHT {
+            self.new_page();
+        }
+    }
+
+    pub fn new_page(&mut self) {
+        let page = std::mem::take(&mut self.current);
+        self.pages.push(page);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    pub fn gap(&mut self, points: f64) {
+        self.y -= points;
+    }
+
+    /// Wrapped text in the given font and size
+    pub fn text(&mut self, text: &str, font: Font, size: f64) {
+        let leading = size * 1.35;
+        for paragraph in text.split('\n') {
+            for line in wrap(paragraph, font, size, Self::content_width()) {
+                self.ensure_space(leading);
+                self.y -= leading;
+                self.show_text(MARGIN, self.y, &line, font, size);
+            }
+        }
+    }
+
+    pub fn heading(&mut self, text: &str, size: f64) {
+        self.ensure_space(size * 3.0);
+        self.gap(size * 0.5);
+        self.text(text, Font::Bold, size);
+        self.gap(size * 0.3);
+    }
+
+    /// Bold label followed by a value on the same line (value wraps beneath if long)
+    pub fn field(&mut self, label: &str, value: &str) {
+        let size = 10.0;
+        let leading = size * 1.35;
+        let label = format!("{}: ", label);
+        let label_width = text_width(&label, Font::Bold, size);
+        let value_width = Self::content_width() - label_width;
+        let lines = wrap(value, Font::Regular, size, value_width);
+
+        for (i, line) in lines.iter().enumerate() {
+            self.ensure_space(leading);
+            self.y -= leading;
+            if i == 0 {
+                self.show_text(MARGIN, self.y, &label, Font::Bold, size);
+            }
+            self.show_text(MARGIN + label_width, self.y, line, Font::Regular, size);
+        }
+    }
+
+    /// Horizontal rule across the content width
+    pub fn rule(&mut self) {
+        self.ensure_space(10.0);
+        self.y -= 6.0;
+        self.current.push_str(&format!(
+            "0.6 G 0.5 w {:.2} {:.2} m {:.2} {:.2} l S 0 G\n",
+            MARGIN, self.y, PAGE_WIDTH - MARGIN, self.y
+        ));
+        self.y -= 4.0;
+    }
+
+    /// Draw an image scaled to fit within `max_width` x `max_height`, with a light border
+    pub fn image(&mut self, image: GrayImage, max_width: f64, max_height: f64) {
+        let scale = (max_width / image.width as f64).min(max_height / image.height as f64);
+        let width = image.width as f64 * scale;
+        let height = image.height as f64 * scale;
+
+        self.ensure_space(height + 8.0);
+        self.y -= height + 4.0;
+
+        let index = self.images.len();
+        self.images.push(image);
+        self.current.push_str(&format!(
+            "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q\n",
+            width, height, MARGIN, self.y, index
+        ));
+        self.current.push_str(&format!(
+            "0.75 G 0.5 w {:.2} {:.2} {:.2} {:.2} re S 0 G\n",
+            MARGIN, self.y, width, height
+        ));
+        self.y -= 4.0;
+    }
+
+    fn show_text(&mut self, x: f64, y: f64, text: &str, font: Font, size: f64) {
+        self.current.push_str(&format!(
+            "BT /{} {:.1} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+            font.resource(), size, x, y, escape(text)
+        ));
+    }
+
+    /// Serialize the document
+    pub fn finish(mut self) -> Vec<u8> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.new_page();
+        }
+
+        let page_count = self.pages.len();
+        let mut objects: Vec<Vec<u8>> = vec![];
+
+        // 1: catalog, 2: page tree, 3-4: fonts, 5: info, then images, then page/content pairs
+        let first_image = 6;
+        let first_page = first_image + self.images.len();
+        let page_ids: Vec<usize> = (0..page_count).map(|i| first_page + i * 2).collect();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+            page_count
+        ).into_bytes());
+        objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+        objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec());
+        objects.push(format!(
+            "<< /Title ({}) /Producer (Riverview Adventure Company) /CreationDate (D:{}Z) >>",
+            escape(&self.title),
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ).into_bytes());
+
+        for image in &self.images {
+            let data = compress_to_vec_zlib(&image.pixels, 6);
+            let mut obj = format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray \
+                 /BitsPerComponent 8 /Filter /FlateDecode /Length {} >>\nstream\n",
+                image.width, image.height, data.len()
+            ).into_bytes();
+            obj.extend_from_slice(&data);
+            obj.extend_from_slice(b"\nendstream");
+            objects.push(obj);
+        }
+
+        let xobjects: String = (0..self.images.len())
+            .map(|i| format!("/Im{} {} 0 R", i, first_image + i))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        for (i, content) in self.pages.iter().enumerate() {
+            let footer = format!("{}  |  Page {} of {}", self.footer, i + 1, page_count);
+            let mut stream = content.clone();
+            stream.push_str(&format!(
+                "0.4 g BT /F1 8.0 Tf {:.2} {:.2} Td ({}) Tj ET 0 g\n",
+                MARGIN, MARGIN - 18.0, escape(&footer)
+            ));
+            let data = compress_to_vec_zlib(stream.as_bytes(), 6);
+
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> /XObject << {} >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT, xobjects, page_ids[i] + 1
+            ).into_bytes());
+
+            let mut obj = format!("<< /Length {} /Filter /FlateDecode >>\nstream\n", data.len()).into_bytes();
+            obj.extend_from_slice(&data);
+            obj.extend_from_slice(b"\nendstream");
+            objects.push(obj);
+        }
+
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(obj);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        ).as_bytes());
+
+        out
+    }
+}
+
+/// Greedy word wrap using Helvetica metrics
+fn wrap(text: &str, font: Font, size: f64, max_width: f64) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if text_width(&candidate, font, size) <= max_width || line.is_empty() {
+            line = candidate;
+        } else {
+            lines.push(std::mem::take(&mut line));
+            line = word.to_string();
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+pub fn text_width(text: &str, font: Font, size: f64) -> f64 {
+    let units: u32 = text.chars().map(|c| char_width(c, font)).sum();
+    units as f64 * size / 1000.0
+}
+
+/// Standard Helvetica / Helvetica-Bold advance widths for ASCII 32..=126
+fn char_width(c: char, font: Font) -> u32 {
+    const REGULAR: [u16; 95] = [
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+        556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+        1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+        667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+        333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+        556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    ];
+    const BOLD: [u16; 95] = [
+        278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+        556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+        975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+        667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+        333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+        611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+    ];
+    let table = match font {
+        Font::Regular => &REGULAR,
+        Font::Bold => &BOLD,
+    };
+    match c as u32 {
+        32..=126 => table[(c as u32 - 32) as usize] as u32,
+        _ => 556,
+    }
+}
+
+/// Escape a string for a PDF literal, mapping to WinAnsiEncoding
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let code: u32 = match c {
+            '\u{2018}' => 0x91,
+            '\u{2019}' => 0x92,
+            '\u{201C}' => 0x93,
+            '\u{201D}' => 0x94,
+            '\u{2022}' => 0x95,
+            '\u{2013}' => 0x96,
+            '\u{2014}' => 0x97,
+            c if (c as u32) < 0x100 => c as u32,
+            _ => '?' as u32,
+        };
+        match code {
+            0x28 => out.push_str("\\("),
+            0x29 => out.push_str("\\)"),
+            0x5C => out.push_str("\\\\"),
+            32..=126 => out.push(code as u8 as char),
+            _ if code < 32 => out.push(' '),
+            _ => out.push_str(&format!("\\{:03o}", code)),
+        }
+    }
+    out
+}
@@ -0,0 +1,210 @@
+//! Minimal PNG decoding for signature images, and encoding for emailed QR codes
+//!
+//! Canvas `toDataURL('image/png')` always produces 8-bit, non-interlaced RGBA,
+//! so that is what we handle (plus RGB, gray and gray+alpha for good measure).
+
+use base64::Engine;
+use super::zip::crc32;
+
+/// Larger than any signature canvas; bounds the memory a crafted header can ask for
+const MAX_DIMENSION: u32 = 4096;
+
+/// 8-bit grayscale image, one byte per pixel, row-major
+pub struct GrayImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Decode a `data:image/png;base64,...` URL
+pub fn decode_data_url(data_url: &str) -> Result<GrayImage, String> {
+    let encoded = data_url
+        .strip_prefix("data:image/png;base64,")
+        .ok_or_else(|| "Not a PNG data URL".to_string())?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid base64: {}", e))?;
+    decode_to_gray(&bytes)
+}
+
+/// Decode a PNG and flatten it onto a white background as grayscale
+pub fn decode_to_gray(bytes: &[u8]) -> Result<GrayImage, String> {
+    const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+    if bytes.len() < 8 || bytes[..8] != SIGNATURE {
+        return Err("Not a PNG file".to_string());
+    }
+
+    let mut pos = 8;
+    let mut width = 0u32;
+    let mut height = 0u32;
+    let mut color_type = 0u8;
+    let mut idat: Vec<u8> = vec![];
+
+    while pos + 8 <= bytes.len() {
+        let length = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let chunk_type = &bytes[pos + 4..pos + 8];
+        let data_start = pos + 8;
+        // Chunk data plus its 4-byte CRC must fit in what we were given
+        let data_end = match data_start.checked_add(length) {
+            Some(end) if end.checked_add(4).is_some_and(|crc_end| crc_end <= bytes.len()) => end,
+            _ => return Err("Truncated PNG chunk".to_string()),
+        };
+        let data = &bytes[data_start..data_end];
+
+        match chunk_type {
+            b"IHDR" => {
+                if data.len() < 13 {
+                    return Err("Invalid IHDR".to_string());
+                }
+                width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+                height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+                let bit_depth = data[8];
+                color_type = data[9];
+                let interlace = data[12];
+                if bit_depth != 8 || interlace != 0 {
+                    return Err("Only 8-bit non-interlaced PNGs are supported".to_string());
+                }
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+
+        pos = data_end + 4; // skip CRC
+    }
+
+    let channels = match color_type {
+        0 => 1, // gray
+        2 => 3, // RGB
+        4 => 2, // gray + alpha
+        6 => 4, // RGBA
+        _ => return Err(format!("Unsupported PNG color type {}", color_type)),
+    };
+    if width == 0 || height == 0 {
+        return Err("Missing PNG header".to_string());
+    }
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(format!("PNG is larger than {0}x{0}", MAX_DIMENSION));
+    }
+
+    let stride = (width as usize)
+        .checked_mul(channels)
+        .ok_or_else(|| "PNG is too large".to_string())?;
+    let expected = stride
+        .checked_add(1)
+        .and_then(|row| row.checked_mul(height as usize))
+        .ok_or_else(|| "PNG is too large".to_string())?;
+
+    // Never inflate more than the header says the image holds
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&idat, expected)
+        .map_err(|e| format!("Invalid PNG data: {:?}", e.status))?;
+    if raw.len() < expected {
+        return Err("PNG image data is truncated".to_string());
+    }
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    let mut previous = vec![0u8; stride];
+    let mut current = vec![0u8; stride];
+
+    for row in 0..height as usize {
+        let start = row * (stride + 1);
+        let filter = raw[start];
+        current.copy_from_slice(&raw[start + 1..start + 1 + stride]);
+        unfilter(filter, &mut current, &previous, channels)?;
+
+        for px in current.chunks(channels) {
+            let (luma, alpha) = match channels {
+                1 => (px[0] as u32, 255),
+                2 => (px[0] as u32, px[1] as u32),
+                3 => (luma(px[0], px[1], px[2]), 255),
+                _ => (luma(px[0], px[1], px[2]), px[3] as u32),
+            };
+            // Composite over white: transparent canvas pixels become paper
+            let gray = 255 - alpha * (255 - luma) / 255;
+            pixels.push(gray as u8);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Ok(GrayImage { width, height, pixels })
+}
+
+/// Encode an 8-bit grayscale image as a PNG file
+pub fn encode_gray(image: &GrayImage) -> Vec<u8> {
+    let stride = image.width as usize;
+    let mut raw = Vec::with_capacity((stride + 1) * image.height as usize);
+    for row in image.pixels.chunks(stride.max(1)).take(image.height as usize) {
+        raw.push(0); // filter: none
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    header.extend_from_slice(&[8, 0, 0, 0, 0]); // 8-bit gray, deflate, no interlace
+
+    let mut png = vec![137, 80, 78, 71, 13, 10, 26, 10];
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&raw, 9));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn luma(r: u8, g: u8, b: u8) -> u32 {
+    (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000
+}
+
+fn unfilter(filter: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> Result<(), String> {
+    match filter {
+        0 => {}
+        1 => {
+            for i in bpp..row.len() {
+                row[i] = row[i].wrapping_add(row[i - bpp]);
+            }
+        }
+        2 => {
+            for i in 0..row.len() {
+                row[i] = row[i].wrapping_add(prev[i]);
+            }
+        }
+        3 => {
+            for i in 0..row.len() {
+                let left = if i >= bpp { row[i - bpp] as u16 } else { 0 };
+                row[i] = row[i].wrapping_add(((left + prev[i] as u16) / 2) as u8);
+            }
+        }
+        4 => {
+            for i in 0..row.len() {
+                let a = if i >= bpp { row[i - bpp] } else { 0 };
+                let c = if i >= bpp { prev[i - bpp] } else { 0 };
+                row[i] = row[i].wrapping_add(paeth(a, prev[i], c));
+            }
+        }
+        _ => return Err(format!("Unknown PNG filter {}", filter)),
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
@@ -0,0 +1,54 @@
+//! QR code rendering for check-in tokens
+
+use qrcodegen::{QrCode, QrCodeEcc};
+use super::png::{self, GrayImage};
+
+/// Quiet zone around the symbol, in modules (the spec asks for 4)
+const BORDER: i32 = 4;
+/// Pixels per module in PNG output
+const PNG_SCALE: i32 = 6;
+
+/// Encode `text` as a standalone SVG document (black modules on white)
+pub fn svg(text: &str) -> Option<String> {
+    let qr = QrCode::encode_text(text, QrCodeEcc::Medium).ok()?;
+    let size = qr.size();
+    let dimension = size + BORDER * 2;
+
+    let mut path = String::new();
+    for y in 0..size {
+        for x in 0..size {
+            if qr.get_module(x, y) {
+                path.push_str(&format!("M{},{}h1v1h-1z", x + BORDER, y + BORDER));
+            }
+        }
+    }
+
+    Some(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {d} {d}\" width=\"256\" height=\"256\" \
+         shape-rendering=\"crispEdges\" role=\"img\" aria-label=\"Check-in QR code\">\
+         <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\
+         <path d=\"{path}\" fill=\"#000000\"/></svg>",
+        d = dimension,
+        path = path
+    ))
+}
+
+/// Encode `text` as a PNG, for email clients that won't show SVG images
+pub fn png(text: &str) -> Option<Vec<u8>> {
+    let qr = QrCode::encode_text(text, QrCodeEcc::Medium).ok()?;
+    let dimension = (qr.size() + BORDER * 2) * PNG_SCALE;
+
+    let mut pixels = Vec::with_capacity((dimension * dimension) as usize);
+    for py in 0..dimension {
+        for px in 0..dimension {
+            let dark = qr.get_module(px / PNG_SCALE - BORDER, py / PNG_SCALE - BORDER);
+            pixels.push(if dark { 0 } else { 255 });
+        }
+    }
+
+    Some(png::encode_gray(&GrayImage {
+        width: dimension as u32,
+        height: dimension as u32,
+        pixels,
+    }))
+}
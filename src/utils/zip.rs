@@ -0,0 +1,105 @@
+//! Minimal ZIP archive writer (stored entries, no compression)
+//!
+//! Used for bulk document downloads; PDFs are already deflated internally,
+//! so storing them uncompressed costs little and keeps this tiny.
+
+pub struct ZipWriter {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl Default for ZipWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self { data: vec![], central: vec![], entries: 0 }
+    }
+
+    pub fn add_file(&mut self, name: &str, contents: &[u8]) {
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+        let (dos_time, dos_date) = dos_timestamp(chrono::Utc::now().naive_utc());
+        let offset = self.data.len() as u32;
+        let name_bytes = name.as_bytes();
+
+        // Local file header
+        self.data.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        self.data.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.data.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+        self.data.extend_from_slice(&0u16.to_le_bytes()); // stored
+        self.data.extend_from_slice(&dos_time.to_le_bytes());
+        self.data.extend_from_slice(&dos_date.to_le_bytes());
+        self.data.extend_from_slice(&crc.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&(name_bytes.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(name_bytes);
+        self.data.extend_from_slice(contents);
+
+        // Central directory record
+        self.central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.central.extend_from_slice(&0x0800u16.to_le_bytes());
+        self.central.extend_from_slice(&0u16.to_le_bytes());
+        self.central.extend_from_slice(&dos_time.to_le_bytes());
+        self.central.extend_from_slice(&dos_date.to_le_bytes());
+        self.central.extend_from_slice(&crc.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&(name_bytes.len() as u16).to_le_bytes());
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // extra
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // comment
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // disk
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // internal attrs
+        self.central.extend_from_slice(&0u32.to_le_bytes()); // external attrs
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name_bytes);
+
+        self.entries += 1;
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.data.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.data.extend_from_slice(&self.central);
+
+        // End of central directory
+        self.data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&central_size.to_le_bytes());
+        self.data.extend_from_slice(&central_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+
+        self.data
+    }
+}
+
+fn dos_timestamp(dt: chrono::NaiveDateTime) -> (u16, u16) {
+    use chrono::{Datelike, Timelike};
+    let time = ((dt.hour() as u16) << 11) | ((dt.minute() as u16) << 5) | (dt.second() as u16 / 2);
+    let date = (((dt.year() - 1980).max(0) as u16) << 9) | ((dt.month() as u16) << 5) | dt.day() as u16;
+    (time, date)
+}
+
+/// CRC-32 (IEEE 802.3), as required by the ZIP format
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
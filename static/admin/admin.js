@@ -165,6 +165,13 @@
                 flowEl.textContent = `${Math.round(data.flow.flow_cfs).toLocaleString()} CFS`;
             }
 
+            // Flag surges coming down from upstream gauges
+            const network = await fetch('/api/flow/network').then(r => r.json()).catch(() => null);
+            if (flowEl && network && network.warnings && network.warnings.length) {
+                flowEl.textContent += ' ⚠';
+                flowEl.title = network.warnings.map(w => w.message).join('\n');
+            }
+
             // Update weather stat
             const weatherEl = document.getElementById('stat-weather');
             if (weatherEl && data.weather) {
@@ -175,6 +182,45 @@
         }
     }
 
+    // Pricing form: edits weekday rates and keeps the rest of the price list
+    async function setupPricingForm() {
+        const form = document.getElementById('pricing-form');
+        if (!form) return;
+
+        let priceList = null;
+        try {
+            const response = await fetch(`${API_BASE}/pricing`, {
+                headers: { 'Authorization': `Bearer ${authToken}` }
+            });
+            priceList = await response.json();
+            priceList.services.forEach(price => {
+                const input = form.elements[price.service];
+                if (input) input.value = price.weekday;
+            });
+        } catch (error) {
+            console.error('Failed to load prices:', error);
+            return;
+        }
+
+        form.addEventListener('submit', async (e) => {
+            e.preventDefault();
+            priceList.services.forEach(price => {
+                const input = form.elements[price.service];
+                if (input && input.value !== '') price.weekday = parseFloat(input.value);
+            });
+
+            const response = await fetch(`${API_BASE}/pricing`, {
+                method: 'POST',
+                headers: {
+                    'Authorization': `Bearer ${authToken}`,
+                    'Content-Type': 'application/json'
+                },
+                body: JSON.stringify(priceList)
+            });
+            alert(response.ok ? 'Prices updated' : `Failed to update prices: ${await response.text()}`);
+        });
+    }
+
     // Logout handler
     function setupLogout() {
         const logoutBtn = document.getElementById('logout-btn');
@@ -192,6 +238,7 @@
             setupLoginForm();
         } else {
             loadDashboard();
+            setupPricingForm();
             setupLogout();
         }
     }
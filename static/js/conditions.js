@@ -38,6 +38,9 @@
         flowGauge: document.getElementById('flow-gauge'),
         flowYesterday: document.getElementById('flow-yesterday'),
         flowLastweek: document.getElementById('flow-lastweek'),
+        flowChange: document.getElementById('flow-change'),
+        flowTrend: document.getElementById('flow-trend'),
+        flowGage: document.getElementById('flow-gage'),
         
         // Weather
         airTemp: document.getElementById('air-temp'),
@@ -186,6 +189,26 @@
             elements.flowLastweek.textContent = `${formatNumber(flow.lastWeek)} ${arrow}`;
         }
 
+        // Update short-term trend
+        if (elements.flowChange && typeof flow.change_4h === 'number') {
+            const sign = flow.change_4h > 0 ? '+' : '';
+            elements.flowChange.textContent = `${sign}${formatNumber(Math.round(flow.change_4h))}`;
+        }
+
+        if (elements.flowTrend && flow.trend) {
+            const labels = { rising_fast: 'Rising fast ↑↑', rising: 'Rising ↑', steady: 'Steady', falling: 'Falling ↓' };
+            let text = labels[flow.trend] || '--';
+            if (flow.caution_at) {
+                const at = new Date(flow.caution_at);
+                text += ` (caution by ${at.toLocaleString([], { weekday: 'short', hour: 'numeric', minute: '2-digit' })})`;
+            }
+            elements.flowTrend.textContent = text;
+        }
+
+        if (elements.flowGage && flow.gage_height) {
+            elements.flowGage.textContent = `${flow.gage_height.value.toFixed(2)} ${flow.gage_height.unit}`;
+        }
+
         // Update homepage widget status
         if (elements.conditionsStatus) {
             const statusDot = elements.conditionsStatus.querySelector('.conditions-widget__status-dot');
@@ -190,6 +190,43 @@
             });
     }
 
+    // ==========================================================================
+    // Rental Prices (from /api/pricing; the HTML holds fallback prices)
+    // ==========================================================================
+    
+    const priceEls = document.querySelectorAll('[data-price], [data-price-from]');
+    
+    if (priceEls.length) {
+        const formatPrice = (amount) => new Intl.NumberFormat('en-US', {
+            style: 'currency',
+            currency: 'USD',
+            minimumFractionDigits: Number.isInteger(amount) ? 0 : 2
+        }).format(amount);
+        
+        fetch('/api/pricing')
+            .then(response => response.json())
+            .then(pricing => {
+                const byService = {};
+                pricing.services.forEach(price => { byService[price.service] = price; });
+                
+                document.querySelectorAll('[data-price]').forEach(el => {
+                    const price = byService[el.dataset.price];
+                    if (price) el.textContent = formatPrice(price.today);
+                });
+                document.querySelectorAll('[data-price-unit]').forEach(el => {
+                    const price = byService[el.dataset.priceUnit];
+                    if (price) el.textContent = `/ ${price.unit}`;
+                });
+                document.querySelectorAll('[data-price-from]').forEach(el => {
+                    const prices = el.dataset.priceFrom.split(',').map(s => byService[s]).filter(Boolean);
+                    if (!prices.length) return;
+                    const lowest = prices.reduce((a, b) => (b.starting_at < a.starting_at ? b : a));
+                    el.innerHTML = `From ${formatPrice(lowest.starting_at)} <span>/ ${lowest.unit}</span>`;
+                });
+            })
+            .catch(error => console.error('Failed to load prices:', error));
+    }
+
     // ==========================================================================
     // Intersection Observer for Animations
     // ==========================================================================